@@ -0,0 +1,69 @@
+use brykto::attacks::hash_flooding;
+use brykto::hasher::blake3;
+use brykto::hasher::fnv::Fnv1a32;
+use brykto::hasher::siphash::{Sip13, SipBuildHasher};
+use brykto::hasher::{md4, sha1};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use std::collections::HashMap;
+use std::hash::{BuildHasher, BuildHasherDefault};
+
+// Throughput of BLAKE3, single and multithreaded, against the Merkle–Damgård
+// hashes. Only BLAKE3's tree lets independent chunks be hashed at once.
+pub fn criterion_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("hash throughput");
+    for size in [1 << 10, 1 << 16, 1 << 20] {
+        let input: Vec<u8> = (0..size).map(|i| (i % 251) as u8).collect();
+        group.throughput(Throughput::Bytes(size as u64));
+
+        group.bench_with_input(BenchmarkId::new("sha1", size), &input, |b, input| {
+            b.iter(|| sha1(black_box(input.as_slice())))
+        });
+        group.bench_with_input(BenchmarkId::new("md4", size), &input, |b, input| {
+            b.iter(|| md4(black_box(input.as_slice())))
+        });
+        group.bench_with_input(BenchmarkId::new("blake3", size), &input, |b, input| {
+            b.iter(|| blake3::hash(black_box(input.as_slice())))
+        });
+        for threads in [2, 4, 8] {
+            group.bench_with_input(
+                BenchmarkId::new(format!("blake3 {} threads", threads), size),
+                &input,
+                |b, input| b.iter(|| blake3::hash_parallel(black_box(input), threads)),
+            );
+        }
+    }
+    group.finish();
+}
+
+fn fill_map<S>(keys: &[String], build_hasher: S) -> HashMap<&str, usize, S>
+where
+    S: BuildHasher,
+{
+    let mut map = HashMap::with_hasher(build_hasher);
+    for (i, key) in keys.iter().enumerate() {
+        map.insert(key.as_str(), i);
+    }
+    map
+}
+
+// Inserting FNV-1a multicollisions: quadratic with the unkeyed hash, linear
+// once the map is keyed with SipHash.
+pub fn flooding_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("hash flooding");
+    for pairs in [6, 8, 10] {
+        let keys = hash_flooding::fnv1a_32_multicollision(pairs).unwrap();
+
+        group.bench_with_input(BenchmarkId::new("fnv1a", keys.len()), &keys, |b, keys| {
+            b.iter(|| fill_map(keys, BuildHasherDefault::<Fnv1a32>::default()))
+        });
+        group.bench_with_input(
+            BenchmarkId::new("siphash13", keys.len()),
+            &keys,
+            |b, keys| b.iter(|| fill_map(keys, SipBuildHasher::<Sip13>::new([0x42; 16]))),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark, flooding_benchmark);
+criterion_main!(benches);
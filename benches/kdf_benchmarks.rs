@@ -0,0 +1,30 @@
+use brykto::hasher::sha1::Sha1;
+use brykto::hasher::sha256::Sha256;
+use brykto::kdf::pbkdf2::pbkdf2;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+// PBKDF2 is meant to be slow: every iteration is another pair of HMAC
+// compressions, so the cost should scale linearly with the iteration count.
+pub fn criterion_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("pbkdf2 iterations");
+    for iterations in [1, 10, 100, 1000, 10000] {
+        group.bench_with_input(
+            BenchmarkId::new("sha1", iterations),
+            &iterations,
+            |b, &iterations| {
+                b.iter(|| pbkdf2::<Sha1>(b"password", b"salt", black_box(iterations), 20))
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("sha256", iterations),
+            &iterations,
+            |b, &iterations| {
+                b.iter(|| pbkdf2::<Sha256>(b"password", b"salt", black_box(iterations), 32))
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -1,48 +1,37 @@
-use std::collections::HashSet;
-
-use brykto::hasher::sha512_n;
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
-
-fn test_sha512_n_finds_match() {
-    let mut first: u64 = 1;
-    let target_bytes: Vec<u8> = vec![0x3D, 0x4B];
-
-    loop {
-        let matching_hash = sha512_n(&first.to_be_bytes(), 2);
-        if matching_hash == target_bytes {
-            break;
-        }
-        first += 1;
-    }
-}
-
-fn test_sha512_n_finds_collision(number_of_bytes: usize) {
-    let mut collisions = HashSet::new();
-    let mut next: u64 = 1;
-    let mut is_unique;
-    loop {
-        let matching_hash = sha512_n(&next.to_be_bytes(), number_of_bytes);
-        is_unique = collisions.insert(matching_hash.clone());
-        if !is_unique {
-            break;
-        }
-        next += 1;
-    }
-    assert!(!is_unique);
-}
+use brykto::attacks::birthday::{self, TruncatedHash};
+use brykto::hasher::sha512::Sha512;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
 
+// Week 3: preimages and collisions for SHA-512 cut down to n bits. A preimage
+// costs about 2^n hashes and a collision about 2^(n/2), whichever search is
+// used; they trade memory and threads, not evaluations.
 pub fn criterion_benchmark(c: &mut Criterion) {
-    c.bench_function("sha512n finds match", |b| {
-        b.iter(|| test_sha512_n_finds_match())
-    });
-
-    c.bench_function("sha512n finds collision (2)", |b| {
-        b.iter(|| test_sha512_n_finds_collision(black_box(2)))
+    let hash = TruncatedHash::<Sha512>::new(16).unwrap();
+    let target = 0x3D4B;
+    c.bench_function("sha512n finds match (16 bits)", |b| {
+        b.iter(|| birthday::preimage(&hash, black_box(target), u64::MAX))
     });
 
-    c.bench_function("sha512n finds collision (4)", |b| {
-        b.iter(|| test_sha512_n_finds_collision(black_box(4)))
-    });
+    let mut group = c.benchmark_group("sha512n finds collision");
+    for bits in [16, 32] {
+        let hash = TruncatedHash::<Sha512>::new(bits).unwrap();
+
+        group.bench_with_input(BenchmarkId::new("table", bits), &hash, |b, hash| {
+            b.iter(|| birthday::collision_table(hash, u64::MAX))
+        });
+        group.bench_with_input(BenchmarkId::new("floyd", bits), &hash, |b, hash| {
+            b.iter(|| birthday::collision_floyd(hash, u64::MAX))
+        });
+        group.bench_with_input(BenchmarkId::new("brent", bits), &hash, |b, hash| {
+            b.iter(|| birthday::collision_brent(hash, u64::MAX))
+        });
+        group.bench_with_input(
+            BenchmarkId::new("parallel 4 threads", bits),
+            &hash,
+            |b, hash| b.iter(|| birthday::collision_parallel(hash, 4, bits / 4, u64::MAX)),
+        );
+    }
+    group.finish();
 }
 
 criterion_group!(benches, criterion_benchmark);
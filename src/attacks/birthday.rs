@@ -0,0 +1,477 @@
+use crate::hasher::Hasher;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+// Generic collision and preimage searches against hashes cut down to a few
+// bits. An n-bit hash takes about 2^(n/2) evaluations to collide and 2^n to
+// invert; the searches differ in memory and parallelism, not in that count.
+// https://people.scs.carleton.ca/~paulv/papers/JoC97.pdf
+
+// Any brykto hasher keeping only the first `bits` bits of its digest.
+// Values are returned as integers below 2^bits, so they can also be fed back
+// in as messages for the cycle-finding searches.
+pub struct TruncatedHash<H> {
+    bits: u32,
+    hasher: PhantomData<fn() -> H>,
+}
+
+impl<H> Clone for TruncatedHash<H> {
+    fn clone(&self) -> Self {
+        TruncatedHash {
+            bits: self.bits,
+            hasher: PhantomData,
+        }
+    }
+}
+
+impl<H> TruncatedHash<H>
+where
+    H: Hasher,
+    H::Digest: AsRef<[u8]>,
+{
+    // None unless 1 <= bits <= 64 and the digest has that many bits.
+    pub fn new(bits: u32) -> Option<Self> {
+        let digest_bits = 8 * H::new().finalize().as_ref().len() as u32;
+        if bits == 0 || bits > 64 || bits > digest_bits {
+            return None;
+        }
+        Some(TruncatedHash {
+            bits,
+            hasher: PhantomData,
+        })
+    }
+
+    pub fn bits(&self) -> u32 {
+        self.bits
+    }
+
+    pub fn hash(&self, message: &[u8]) -> u64 {
+        let mut hasher = H::new();
+        hasher.update(message);
+        let digest = hasher.finalize();
+
+        // The first bits, big endian.
+        let mut prefix = [0u8; 8];
+        let length = digest.as_ref().len().min(8);
+        prefix[..length].copy_from_slice(&digest.as_ref()[..length]);
+        u64::from_be_bytes(prefix) >> (64 - self.bits)
+    }
+
+    // The hash as a map from truncated values to truncated values, what the
+    // cycle-finding searches walk.
+    pub fn step(&self, value: u64) -> u64 {
+        self.hash(&message(value))
+    }
+}
+
+// Messages tried by every search: 8 byte big endian integers.
+pub fn message(value: u64) -> Vec<u8> {
+    value.to_be_bytes().to_vec()
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Collision {
+    pub first: Vec<u8>,
+    pub second: Vec<u8>,
+    pub digest: u64,
+    pub evaluations: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Preimage {
+    pub message: Vec<u8>,
+    pub evaluations: u64,
+}
+
+// Counts hash evaluations and stops a search once the budget is spent.
+struct Counter<'a, H> {
+    hash: &'a TruncatedHash<H>,
+    evaluations: u64,
+    max_evaluations: u64,
+}
+
+impl<H> Counter<'_, H>
+where
+    H: Hasher,
+    H::Digest: AsRef<[u8]>,
+{
+    fn step(&mut self, value: u64) -> Option<u64> {
+        if self.evaluations >= self.max_evaluations {
+            return None;
+        }
+        self.evaluations += 1;
+        Some(self.hash.step(value))
+    }
+
+    fn collision(&self, first: u64, second: u64, digest: u64) -> Collision {
+        Collision {
+            first: message(first),
+            second: message(second),
+            digest,
+            evaluations: self.evaluations,
+        }
+    }
+}
+
+// Hashes distinct messages until one repeats a digest. Fastest in
+// evaluations, but stores every digest seen: O(2^(n/2)) memory.
+pub fn collision_table<H>(hash: &TruncatedHash<H>, max_evaluations: u64) -> Option<Collision>
+where
+    H: Hasher,
+    H::Digest: AsRef<[u8]>,
+{
+    let mut counter = Counter {
+        hash,
+        evaluations: 0,
+        max_evaluations,
+    };
+    let mut seen = HashMap::new();
+
+    for value in 0.. {
+        let digest = counter.step(value)?;
+        if let Some(other) = seen.insert(digest, value) {
+            return Some(counter.collision(other, value, digest));
+        }
+    }
+    None
+}
+
+// Steps `tortoise` and `hare`, equally far from the start of the cycle, in
+// lockstep until they map to the same value: the last two values before the
+// walks join collide. None if both start on the cycle.
+fn find_merge<H>(counter: &mut Counter<H>, tortoise: u64, hare: u64) -> Option<Collision>
+where
+    H: Hasher,
+    H::Digest: AsRef<[u8]>,
+{
+    let (mut tortoise, mut hare) = (tortoise, hare);
+    if tortoise == hare {
+        return None;
+    }
+    loop {
+        let next_tortoise = counter.step(tortoise)?;
+        let next_hare = counter.step(hare)?;
+        if next_tortoise == next_hare {
+            return Some(counter.collision(tortoise, hare, next_tortoise));
+        }
+        tortoise = next_tortoise;
+        hare = next_hare;
+    }
+}
+
+// Pollard's rho with Floyd's cycle detection. Iterating the hash from a seed
+// enters a cycle after about 2^(n/2) steps; the value entering the cycle has
+// two preimages, one on the tail and one on the cycle. O(1) memory, about
+// three times the evaluations of the table.
+pub fn collision_floyd<H>(hash: &TruncatedHash<H>, max_evaluations: u64) -> Option<Collision>
+where
+    H: Hasher,
+    H::Digest: AsRef<[u8]>,
+{
+    let mut counter = Counter {
+        hash,
+        evaluations: 0,
+        max_evaluations,
+    };
+
+    // A seed on the cycle has no tail, so try the next.
+    for start in 0.. {
+        let mut tortoise = counter.step(start)?;
+        let mut hare = counter.step(tortoise)?;
+        while tortoise != hare {
+            tortoise = counter.step(tortoise)?;
+            let next = counter.step(hare)?;
+            hare = counter.step(next)?;
+        }
+
+        // The meeting point is a multiple of the cycle length from the start,
+        // so walking from the start and the meeting point reaches the cycle
+        // entry together.
+        if let Some(collision) = find_merge(&mut counter, start, hare) {
+            return Some(collision);
+        }
+    }
+    None
+}
+
+// Brent's variant: the tortoise teleports to the hare at powers of two, which
+// finds the cycle length directly and saves about a third of Floyd's steps.
+pub fn collision_brent<H>(hash: &TruncatedHash<H>, max_evaluations: u64) -> Option<Collision>
+where
+    H: Hasher,
+    H::Digest: AsRef<[u8]>,
+{
+    let mut counter = Counter {
+        hash,
+        evaluations: 0,
+        max_evaluations,
+    };
+
+    for start in 0.. {
+        let mut power = 1u64;
+        let mut cycle_length = 1u64;
+        let mut tortoise = start;
+        let mut hare = counter.step(start)?;
+        while tortoise != hare {
+            if power == cycle_length {
+                tortoise = hare;
+                power *= 2;
+                cycle_length = 0;
+            }
+            hare = counter.step(hare)?;
+            cycle_length += 1;
+        }
+
+        // Start a second walker one cycle length ahead.
+        let mut ahead = start;
+        for _ in 0..cycle_length {
+            ahead = counter.step(ahead)?;
+        }
+        if let Some(collision) = find_merge(&mut counter, start, ahead) {
+            return Some(collision);
+        }
+    }
+    None
+}
+
+// Where a trail started and how many steps it took to reach a distinguished
+// point.
+#[derive(Clone, Copy)]
+struct Trail {
+    start: u64,
+    length: u64,
+}
+
+// van Oorschot-Wiener parallel collision search. Each thread walks trails
+// from fresh starting points until it hits a distinguished point, a value
+// whose low `distinguished_bits` bits are zero, and only those are stored.
+// Two trails ending in the same distinguished point have merged somewhere,
+// and rewalking both finds where. Memory shrinks by 2^distinguished_bits and
+// the work splits linearly across threads.
+pub fn collision_parallel<H>(
+    hash: &TruncatedHash<H>,
+    threads: usize,
+    distinguished_bits: u32,
+    max_evaluations: u64,
+) -> Option<Collision>
+where
+    H: Hasher,
+    H::Digest: AsRef<[u8]>,
+{
+    if threads == 0 || distinguished_bits >= hash.bits() {
+        return None;
+    }
+
+    let mask = (1u64 << distinguished_bits) - 1;
+    // Starting points wrap around the value space, 2^64 values included.
+    let start_mask = u64::MAX >> (64 - hash.bits());
+    // Trails stuck in a cycle without distinguished points are abandoned.
+    // Past 59 distinguished bits the limit no longer fits, and no trail
+    // would get anywhere near such a point anyway.
+    let max_trail_length = 20u64
+        .checked_shl(distinguished_bits)
+        .filter(|length| length >> distinguished_bits == 20)?;
+
+    let distinguished = Mutex::new(HashMap::<u64, Trail>::new());
+    let result = Mutex::new(None);
+    let done = AtomicBool::new(false);
+    let evaluations = AtomicU64::new(0);
+    let next_start = AtomicU64::new(0);
+
+    let step = |value: u64| -> Option<u64> {
+        if done.load(Ordering::Relaxed)
+            || evaluations.fetch_add(1, Ordering::Relaxed) >= max_evaluations
+        {
+            return None;
+        }
+        Some(hash.step(value))
+    };
+
+    // Walks two merging trails forward to their first common value.
+    let resolve = |first: Trail, second: Trail| -> Option<(u64, u64, u64)> {
+        let (mut longer, mut shorter) = if first.length >= second.length {
+            (first, second)
+        } else {
+            (second, first)
+        };
+        let mut value_longer = longer.start;
+        while longer.length > shorter.length {
+            value_longer = step(value_longer)?;
+            longer.length -= 1;
+        }
+
+        let mut value_shorter = shorter.start;
+        // One trail started on the other: no collision, just overlap.
+        if value_longer == value_shorter {
+            return None;
+        }
+        while shorter.length > 0 {
+            let next_longer = step(value_longer)?;
+            let next_shorter = step(value_shorter)?;
+            if next_longer == next_shorter {
+                return Some((value_longer, value_shorter, next_longer));
+            }
+            value_longer = next_longer;
+            value_shorter = next_shorter;
+            shorter.length -= 1;
+        }
+        None
+    };
+
+    let worker = || {
+        'trails: while !done.load(Ordering::Relaxed) {
+            let start = next_start.fetch_add(1, Ordering::Relaxed) & start_mask;
+            let mut value = start;
+            let mut length = 0;
+
+            while value & mask != 0 || length == 0 {
+                value = match step(value) {
+                    Some(value) => value,
+                    None => break 'trails,
+                };
+                length += 1;
+                if length > max_trail_length {
+                    continue 'trails;
+                }
+            }
+
+            let trail = Trail { start, length };
+            let previous = distinguished.lock().unwrap().insert(value, trail);
+            if let Some(previous) = previous {
+                if let Some(found) = resolve(previous, trail) {
+                    *result.lock().unwrap() = Some(found);
+                    done.store(true, Ordering::Relaxed);
+                }
+            }
+        }
+    };
+
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(worker);
+        }
+    });
+
+    let (first, second, digest) = result.into_inner().unwrap()?;
+    Some(Collision {
+        first: message(first),
+        second: message(second),
+        digest,
+        // Steps refused once the search stopped were counted too.
+        evaluations: evaluations.into_inner().min(max_evaluations),
+    })
+}
+
+// Brute force preimage search for a truncated digest: tries messages in
+// order until one hashes to `target`, about 2^n evaluations.
+pub fn preimage<H>(hash: &TruncatedHash<H>, target: u64, max_evaluations: u64) -> Option<Preimage>
+where
+    H: Hasher,
+    H::Digest: AsRef<[u8]>,
+{
+    let mut counter = Counter {
+        hash,
+        evaluations: 0,
+        max_evaluations,
+    };
+    let mut value = 0;
+    while counter.step(value)? != target {
+        value += 1;
+    }
+    Some(Preimage {
+        message: message(value),
+        evaluations: counter.evaluations,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hasher::md5::Md5;
+    use crate::hasher::sha256::Sha256;
+    use crate::hasher::sha512::Sha512;
+    use crate::hasher::{sha256, truncate};
+
+    fn assert_collision<H>(hash: &TruncatedHash<H>, collision: &Collision)
+    where
+        H: Hasher,
+        H::Digest: AsRef<[u8]>,
+    {
+        assert_ne!(collision.first, collision.second);
+        assert_eq!(hash.hash(&collision.first), collision.digest);
+        assert_eq!(hash.hash(&collision.second), collision.digest);
+    }
+
+    #[test]
+    fn test_truncated_hash() {
+        let hash = TruncatedHash::<Sha256>::new(12).unwrap();
+        let digest = sha256(b"abc".as_slice());
+        let expected = truncate(&digest, 12).unwrap();
+        assert_eq!(
+            hash.hash(b"abc"),
+            ((expected[0] as u64) << 4) | (expected[1] as u64 >> 4)
+        );
+
+        assert!(TruncatedHash::<Sha256>::new(0).is_none());
+        assert!(TruncatedHash::<Sha256>::new(65).is_none());
+        assert!(TruncatedHash::<Sha512>::new(64).is_some());
+    }
+
+    #[test]
+    fn test_collision_table() {
+        let hash = TruncatedHash::<Sha256>::new(24).unwrap();
+        let collision = collision_table(&hash, 1 << 20).unwrap();
+        assert_collision(&hash, &collision);
+        // Around 2^12 expected.
+        assert!(collision.evaluations < 1 << 16);
+
+        assert_eq!(collision_table(&hash, 10), None);
+    }
+
+    #[test]
+    fn test_collision_cycle_finding() {
+        let hash = TruncatedHash::<Md5>::new(24).unwrap();
+
+        let floyd = collision_floyd(&hash, 1 << 20).unwrap();
+        assert_collision(&hash, &floyd);
+
+        let brent = collision_brent(&hash, 1 << 20).unwrap();
+        assert_collision(&hash, &brent);
+
+        // Same walk from the same seed, so the same cycle entry.
+        assert_eq!(floyd.digest, brent.digest);
+        assert_eq!(collision_floyd(&hash, 100), None);
+    }
+
+    #[test]
+    fn test_collision_parallel() {
+        let hash = TruncatedHash::<Sha256>::new(28).unwrap();
+        let collision = collision_parallel(&hash, 4, 6, 1 << 22).unwrap();
+        assert_collision(&hash, &collision);
+
+        assert_eq!(collision_parallel(&hash, 0, 6, 1 << 22), None);
+        assert_eq!(collision_parallel(&hash, 2, 28, 1 << 22), None);
+
+        // Full width: only checks that every step stays in range, a collision
+        // is out of reach.
+        let hash = TruncatedHash::<Sha512>::new(64).unwrap();
+        assert_eq!(collision_parallel(&hash, 2, 8, 1 << 12), None);
+        // Rejected up front rather than spending the whole budget.
+        assert_eq!(collision_parallel(&hash, 2, 62, u64::MAX), None);
+        assert_eq!(collision_parallel(&hash, 2, 63, u64::MAX), None);
+    }
+
+    #[test]
+    fn test_preimage() {
+        let hash = TruncatedHash::<Sha256>::new(14).unwrap();
+        let target = hash.hash(b"target");
+        let found = preimage(&hash, target, 1 << 20).unwrap();
+
+        assert_eq!(hash.hash(&found.message), target);
+        assert_eq!(found.message, message(found.evaluations - 1));
+        assert_eq!(preimage(&hash, target, 0), None);
+    }
+}
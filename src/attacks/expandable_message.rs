@@ -0,0 +1,182 @@
+use crate::attacks::multicollision::{bridge, pair_collision, WeakHash};
+use crate::hasher::CompressionFunction;
+use std::collections::HashMap;
+
+// Long-message second preimages (Kelsey–Schneier). Hashing a message of 2^k
+// blocks passes through 2^k chaining values, and hitting any of them is
+// 2^k times easier than hitting the digest. The padding encodes the length
+// though, so the forged prefix has to be exactly as long as the one it
+// replaces: an expandable message provides a prefix of any length in
+// [k, k + 2^k - 1] blocks, all ending in the same state.
+// https://www.schneier.com/wp-content/uploads/2016/02/paper-secondpreimages.pdf
+// https://cryptopals.com/sets/7/challenges/53
+
+// k pieces, each either a single block or 2^(k-1-i) + 1 blocks for the i-th,
+// both ending in the same state.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExpandableMessage {
+    pub pieces: Vec<(Vec<u8>, Vec<u8>)>,
+    pub start: u64,
+    // State every expansion ends in.
+    pub state: u64,
+    pub compressions: u64,
+}
+
+impl ExpandableMessage {
+    pub fn k(&self) -> usize {
+        self.pieces.len()
+    }
+
+    pub fn min_blocks(&self) -> usize {
+        self.k()
+    }
+
+    pub fn max_blocks(&self) -> usize {
+        self.k() + (1 << self.k()) - 1
+    }
+
+    // The expansion `blocks` blocks long: the i-th piece is the long one when
+    // bit k-1-i of blocks - k is set. None out of range.
+    pub fn message(&self, blocks: usize) -> Option<Vec<u8>> {
+        if blocks < self.min_blocks() || blocks > self.max_blocks() {
+            return None;
+        }
+        let extra = blocks - self.k();
+        let mut message = vec![];
+        for (i, (short, long)) in self.pieces.iter().enumerate() {
+            if (extra >> (self.k() - 1 - i)) & 1 == 0 {
+                message.extend(short);
+            } else {
+                message.extend(long);
+            }
+        }
+        Some(message)
+    }
+}
+
+// Builds an expandable message from `start`. The i-th piece hashes 2^(k-1-i)
+// dummy blocks first, then collides one block from the start of the piece
+// with one block after the dummies. Each collision search gets its own
+// budget.
+pub fn expandable_message<C>(
+    hash: &WeakHash<C>,
+    start: u64,
+    k: usize,
+    max_compressions: u64,
+) -> Option<ExpandableMessage>
+where
+    C: CompressionFunction,
+{
+    if k == 0 || k >= usize::BITS as usize {
+        return None;
+    }
+
+    let dummy = vec![0; C::BLOCK_SIZE];
+    let mut pieces = vec![];
+    let mut state = start;
+    let mut compressions = 0;
+    for i in 0..k {
+        let dummies = 1usize << (k - 1 - i);
+        let mut dummy_state = state;
+        for _ in 0..dummies {
+            dummy_state = hash.compress(dummy_state, &dummy);
+        }
+
+        let collision = pair_collision(hash, state, dummy_state, max_compressions)?;
+        let mut long = dummy.repeat(dummies);
+        long.extend(&collision.second);
+
+        pieces.push((collision.first, long));
+        state = collision.state;
+        compressions += dummies as u64 + collision.compressions;
+    }
+    Some(ExpandableMessage {
+        pieces,
+        start,
+        state,
+        compressions,
+    })
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SecondPreimage {
+    pub message: Vec<u8>,
+    pub compressions: u64,
+}
+
+// A different message with the digest of `message`, which should be about
+// 2^k blocks long: an expandable message of k pieces, a bridge block into
+// one of the original chaining values, then the original from there on.
+// Costs about 2^(bits - k) compressions for the bridge.
+pub fn second_preimage<C>(
+    hash: &WeakHash<C>,
+    message: &[u8],
+    k: usize,
+    max_compressions: u64,
+) -> Option<SecondPreimage>
+where
+    C: CompressionFunction,
+{
+    let expandable = expandable_message(hash, hash.iv(), k, max_compressions)?;
+
+    // Chaining values after j whole blocks, for the j an expansion plus the
+    // bridge can stand in for. The first j wins if values repeat.
+    let blocks: Vec<&[u8]> = message.chunks_exact(C::BLOCK_SIZE).collect();
+    let mut targets = HashMap::new();
+    let mut state = hash.iv();
+    for (j, block) in blocks.iter().enumerate() {
+        state = hash.compress(state, block);
+        let replaced = j + 1;
+        if replaced > expandable.min_blocks() && replaced <= expandable.max_blocks() + 1 {
+            targets.entry(state).or_insert(replaced);
+        }
+    }
+    if targets.is_empty() {
+        return None;
+    }
+
+    let bridge = bridge(hash, expandable.state, &targets, max_compressions)?;
+    let mut forgery = expandable.message(bridge.target - 1)?;
+    forgery.extend(&bridge.block);
+    forgery.extend(&message[bridge.target * C::BLOCK_SIZE..]);
+
+    Some(SecondPreimage {
+        message: forgery,
+        compressions: expandable.compressions + blocks.len() as u64 + bridge.compressions,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hasher::md4::Md4Compression;
+
+    #[test]
+    fn test_expandable_message() {
+        let hash = WeakHash::<Md4Compression>::new(16).unwrap();
+        let expandable = expandable_message(&hash, hash.iv(), 4, 1 << 16).unwrap();
+        assert_eq!((expandable.min_blocks(), expandable.max_blocks()), (4, 19));
+        assert!(expandable.message(3).is_none());
+        assert!(expandable.message(20).is_none());
+
+        for blocks in 4..=19 {
+            let message = expandable.message(blocks).unwrap();
+            assert_eq!(message.len(), blocks * 64);
+            let state = message
+                .chunks(64)
+                .fold(hash.iv(), |state, block| hash.compress(state, block));
+            assert_eq!(state, expandable.state);
+        }
+    }
+
+    #[test]
+    fn test_second_preimage() {
+        let hash = WeakHash::<Md4Compression>::new(16).unwrap();
+        let message: Vec<u8> = (0..64 * 256 + 10).map(|i| (i % 251) as u8).collect();
+
+        let forgery = second_preimage(&hash, &message, 8, 1 << 20).unwrap();
+        assert_ne!(forgery.message, message);
+        assert_eq!(forgery.message.len(), message.len());
+        assert_eq!(hash.hash(&forgery.message), hash.hash(&message));
+    }
+}
@@ -0,0 +1,134 @@
+use crate::hasher::fnv::{Fnv1a32, OFFSET_BASIS_32};
+use std::collections::HashMap;
+
+// Hash flooding: a hash table keyed by attacker controlled strings degrades
+// to a linked list when every key lands in the same bucket, turning n inserts
+// into O(n^2) work. With an unkeyed hash the colliding keys are computed once,
+// offline, and work against every table. Keyed hashes such as SipHash take
+// that away, the attacker never learns the function.
+// https://www.aumasson.jp/siphash/siphash.pdf
+
+const ALPHABET: &[u8] = b"abcdefghijklmnopqrstuvwxyz";
+
+// Blocks are lowercase so the colliding keys are printable strings.
+pub const BLOCK_LENGTH: usize = 8;
+
+// Give up after this many candidates, far beyond the 2^16 expected.
+const MAX_CANDIDATES: u64 = 1 << 24;
+
+// Every string is built in memory, so multicollisions stop at 2^20 strings
+// of 160 bytes, plenty to flood a table.
+pub const MAX_PAIRS: usize = 20;
+
+// Counter scrambled by splitmix64 into letters. Counting through blocks in
+// order keeps FNV-1a outputs too structured to collide early.
+fn candidate(index: u64) -> [u8; BLOCK_LENGTH] {
+    let mut z = index.wrapping_add(0x9E3779B97F4A7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^= z >> 31;
+
+    let mut block = [0u8; BLOCK_LENGTH];
+    for byte in block.iter_mut() {
+        *byte = ALPHABET[(z % ALPHABET.len() as u64) as usize];
+        z /= ALPHABET.len() as u64;
+    }
+    block
+}
+
+// Two distinct blocks taking the FNV-1a state to the same value. Birthday
+// search: about 2^16 candidates for a 32-bit state.
+pub fn fnv1a_32_collision(state: u32) -> Option<([u8; BLOCK_LENGTH], [u8; BLOCK_LENGTH])> {
+    let mut seen = HashMap::new();
+
+    for index in 0..MAX_CANDIDATES {
+        let block = candidate(index);
+        let mut hasher = Fnv1a32::from_state(state);
+        hasher.update(&block);
+        // Letters drop bits of the counter, so the same block can come up twice.
+        match seen.insert(hasher.state(), block) {
+            Some(other) if other != block => return Some((other, block)),
+            _ => {}
+        }
+    }
+    None
+}
+
+// 2^pairs distinct strings with one FNV-1a hash. Since the state is the whole
+// hash, collisions chain: after either block of a pair the state is the same,
+// so the next pair is searched from there and any choice of one block per
+// pair collides (Joux's multicollisions). The 64-bit hash falls the same
+// way at about 2^32 work per pair. Returns `None` for more than MAX_PAIRS
+// pairs.
+pub fn fnv1a_32_multicollision(pairs: usize) -> Option<Vec<String>> {
+    if pairs > MAX_PAIRS {
+        return None;
+    }
+
+    let mut state = OFFSET_BASIS_32;
+    let mut messages = vec![String::new()];
+
+    for _ in 0..pairs {
+        let (a, b) = fnv1a_32_collision(state)?;
+        let mut hasher = Fnv1a32::from_state(state);
+        hasher.update(&a);
+        state = hasher.state();
+
+        let (a, b) = (
+            String::from_utf8(a.to_vec()).ok()?,
+            String::from_utf8(b.to_vec()).ok()?,
+        );
+        messages = messages
+            .into_iter()
+            .flat_map(|message| [message.clone() + &a, message + &b])
+            .collect();
+    }
+    Some(messages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hasher::fnv::fnv1a_32;
+    use crate::hasher::siphash::{Sip13, SipBuildHasher};
+    use std::collections::HashSet;
+    use std::hash::{BuildHasher, BuildHasherDefault};
+
+    #[test]
+    fn test_fnv1a_32_collision() {
+        let (a, b) = fnv1a_32_collision(OFFSET_BASIS_32).unwrap();
+        assert_ne!(a, b);
+        assert_eq!(fnv1a_32(&a), fnv1a_32(&b));
+    }
+
+    #[test]
+    fn test_fnv1a_32_multicollision() {
+        assert_eq!(fnv1a_32_multicollision(MAX_PAIRS + 1), None);
+        assert_eq!(fnv1a_32_multicollision(64), None);
+
+        let keys = fnv1a_32_multicollision(6).unwrap();
+        assert_eq!(keys.len(), 64);
+        assert_eq!(keys.iter().collect::<HashSet<_>>().len(), 64);
+
+        let digest = fnv1a_32(keys[0].as_bytes());
+        assert!(keys.iter().all(|key| fnv1a_32(key.as_bytes()) == digest));
+
+        // Every key falls into one bucket of an FNV backed map, while a keyed
+        // SipHash spreads them out.
+        let fnv = BuildHasherDefault::<Fnv1a32>::default();
+        let fnv_hashes: HashSet<u64> = keys.iter().map(|key| fnv.hash_one(key)).collect();
+        assert_eq!(fnv_hashes.len(), 1);
+
+        let sip = SipBuildHasher::<Sip13>::new(*b"a secret 16B key");
+        let sip_hashes: HashSet<u64> = keys.iter().map(|key| sip.hash_one(key)).collect();
+        assert_eq!(sip_hashes.len(), 64);
+
+        // Still a correct map, only slow.
+        let mut map = HashMap::with_hasher(fnv);
+        for (i, key) in keys.iter().enumerate() {
+            map.insert(key.clone(), i);
+        }
+        assert_eq!(map.len(), 64);
+        assert_eq!(map.get(&keys[17]), Some(&17));
+    }
+}
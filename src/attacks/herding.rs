@@ -0,0 +1,183 @@
+use crate::attacks::multicollision::{bridge, pair_collision, WeakHash};
+use crate::hasher::CompressionFunction;
+use std::collections::HashMap;
+
+// Herding, or the Nostradamus attack (Kelsey–Kohno). Commit to a digest
+// first, then produce a message with that digest starting with any prefix
+// chosen later. A diamond structure, a binary tree of collisions funnelling
+// 2^k states into one, is precomputed; the prefix needs a single bridge
+// block into any of its leaves, 2^(bits - k) work instead of 2^bits.
+// https://eprint.iacr.org/2005/281.pdf
+// https://cryptopals.com/sets/7/challenges/54
+
+// Padding appended to prefixes shorter than the committed length.
+const FILLER: u8 = b' ';
+
+// `states[0]` holds the 2^k leaves and `states[k]` the root. `blocks[d][i]`
+// takes `states[d][i]` to `states[d + 1][i / 2]`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diamond {
+    pub states: Vec<Vec<u64>>,
+    pub blocks: Vec<Vec<Vec<u8>>>,
+    pub compressions: u64,
+}
+
+impl Diamond {
+    pub fn depth(&self) -> usize {
+        self.blocks.len()
+    }
+
+    pub fn leaves(&self) -> &[u64] {
+        &self.states[0]
+    }
+
+    pub fn root(&self) -> u64 {
+        self.states[self.depth()][0]
+    }
+
+    // The k blocks from leaf `leaf` up to the root.
+    pub fn path(&self, mut leaf: usize) -> Option<Vec<u8>> {
+        if leaf >= self.leaves().len() {
+            return None;
+        }
+        let mut path = vec![];
+        for level in &self.blocks {
+            path.extend(&level[leaf]);
+            leaf /= 2;
+        }
+        Some(path)
+    }
+}
+
+// Builds a diamond of depth k. The leaves are any 2^k distinct states, here
+// spread evenly over the state space; each level pairs up neighbours with
+// one collision search per pair, 2^k - 1 searches in all.
+pub fn diamond<C>(hash: &WeakHash<C>, k: usize, max_compressions: u64) -> Option<Diamond>
+where
+    C: CompressionFunction,
+{
+    if k >= hash.bits() as usize {
+        return None;
+    }
+
+    let spacing = hash.bits() as usize - k;
+    let mut states = vec![(0..1u64 << k)
+        .map(|leaf| leaf << spacing)
+        .collect::<Vec<_>>()];
+    let mut blocks = vec![];
+    let mut compressions = 0;
+    for _ in 0..k {
+        let level = states.last().unwrap();
+        let mut next = vec![];
+        let mut level_blocks = vec![];
+        for pair in level.chunks(2) {
+            let collision = pair_collision(hash, pair[0], pair[1], max_compressions)?;
+            next.push(collision.state);
+            level_blocks.push(collision.first);
+            level_blocks.push(collision.second);
+            compressions += collision.compressions;
+        }
+        states.push(next);
+        blocks.push(level_blocks);
+    }
+    Some(Diamond {
+        states,
+        blocks,
+        compressions,
+    })
+}
+
+// What gets published: the digest, and how many blocks of prefix it was
+// computed for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Prediction {
+    pub digest: u64,
+    pub prefix_blocks: usize,
+}
+
+// Commits to any prefix of up to `prefix_blocks` blocks. Every herded
+// message is the prefix, one bridge block and the path through the diamond,
+// so its length and padding are known in advance.
+pub fn predict<C>(hash: &WeakHash<C>, diamond: &Diamond, prefix_blocks: usize) -> Prediction
+where
+    C: CompressionFunction,
+{
+    let length = (prefix_blocks + 1 + diamond.depth()) * C::BLOCK_SIZE;
+    Prediction {
+        digest: hash.finalize(diamond.root(), length),
+        prefix_blocks,
+    }
+}
+
+// A message starting with `prefix` whose digest is the prediction. The
+// prefix is padded with spaces to the committed length. None if it is too
+// long or no bridge turns up within the budget.
+pub fn herd<C>(
+    hash: &WeakHash<C>,
+    diamond: &Diamond,
+    prediction: &Prediction,
+    prefix: &[u8],
+    max_compressions: u64,
+) -> Option<Vec<u8>>
+where
+    C: CompressionFunction,
+{
+    let prefix_length = prediction.prefix_blocks * C::BLOCK_SIZE;
+    if prefix.len() > prefix_length {
+        return None;
+    }
+    let mut message = prefix.to_vec();
+    message.resize(prefix_length, FILLER);
+
+    let state = message
+        .chunks(C::BLOCK_SIZE)
+        .fold(hash.iv(), |state, block| hash.compress(state, block));
+    let leaves: HashMap<u64, usize> = diamond
+        .leaves()
+        .iter()
+        .enumerate()
+        .map(|(leaf, &state)| (state, leaf))
+        .collect();
+    let bridge = bridge(hash, state, &leaves, max_compressions)?;
+
+    message.extend(&bridge.block);
+    message.extend(diamond.path(bridge.target)?);
+    Some(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hasher::md4::Md4Compression;
+
+    #[test]
+    fn test_diamond() {
+        let hash = WeakHash::<Md4Compression>::new(16).unwrap();
+        let diamond = diamond(&hash, 4, 1 << 16).unwrap();
+        assert_eq!(diamond.leaves().len(), 16);
+        assert!(diamond.path(16).is_none());
+
+        for (leaf, &state) in diamond.leaves().iter().enumerate() {
+            let path = diamond.path(leaf).unwrap();
+            assert_eq!(path.len(), 4 * 64);
+            let root = path
+                .chunks(64)
+                .fold(state, |state, block| hash.compress(state, block));
+            assert_eq!(root, diamond.root());
+        }
+    }
+
+    #[test]
+    fn test_herd() {
+        let hash = WeakHash::<Md4Compression>::new(16).unwrap();
+        let diamond = diamond(&hash, 6, 1 << 16).unwrap();
+        let prediction = predict(&hash, &diamond, 2);
+
+        let prefix = b"Final score: 3-1";
+        let message = herd(&hash, &diamond, &prediction, prefix, 1 << 20).unwrap();
+        assert!(message.starts_with(prefix));
+        assert_eq!(hash.hash(&message), prediction.digest);
+
+        assert!(herd(&hash, &diamond, &prediction, &[0; 129], 1 << 20).is_none());
+    }
+}
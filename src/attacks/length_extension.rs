@@ -0,0 +1,115 @@
+use crate::hasher::{md_padding, CompressionFunction, Hasher, MerkleDamgard, State};
+
+// Length extension against secret-prefix MACs, H(key || message), for any
+// Merkle–Damgård hash. The digest is the full chaining value after hashing
+// key || message || padding, so hashing can resume from it without knowing
+// the key.
+// https://cryptopals.com/sets/4/challenges/29
+
+// A forged message together with a valid MAC for it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Forgery<D> {
+    pub message: Vec<u8>,
+    pub mac: D,
+}
+
+// Forges a MAC for message || glue padding || suffix given the MAC of
+// `message` and a guess for the key length. Returns `None` for hashes whose
+// digest is truncated, e.g. SHA-224, since the state can't be recovered.
+pub fn extend<C>(
+    mac: &C::Digest,
+    message: &[u8],
+    key_length: usize,
+    suffix: &[u8],
+) -> Option<Forgery<C::Digest>>
+where
+    C: CompressionFunction,
+{
+    // The padding the victim appended to key || message.
+    let known_length = key_length + message.len();
+    let glue_padding = md_padding(C::BLOCK_SIZE, C::LENGTH_SIZE, known_length, C::ENDIAN);
+
+    let state = State::<C>::from_digest(mac, known_length + glue_padding.len())?;
+    let mut hasher = MerkleDamgard::<C>::from_state(state);
+    hasher.update(suffix);
+
+    Some(Forgery {
+        message: [message, glue_padding.as_slice(), suffix].concat(),
+        mac: hasher.finalize(),
+    })
+}
+
+// Tries every key length up to `max_key_length` until `oracle` accepts the
+// forgery. Returns the key length along with the forgery.
+pub fn brute_force_key_length<C, F>(
+    mac: &C::Digest,
+    message: &[u8],
+    suffix: &[u8],
+    max_key_length: usize,
+    mut oracle: F,
+) -> Option<(usize, Forgery<C::Digest>)>
+where
+    C: CompressionFunction,
+    F: FnMut(&[u8], &C::Digest) -> bool,
+{
+    for key_length in 0..=max_key_length {
+        let forgery = extend::<C>(mac, message, key_length, suffix)?;
+        if oracle(&forgery.message, &forgery.mac) {
+            return Some((key_length, forgery));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hasher::sha256::{Sha224Compression, Sha256Compression};
+    use crate::hasher::sha512::Sha512Compression;
+
+    fn secret_prefix_mac<C>(key: &[u8], message: &[u8]) -> C::Digest
+    where
+        C: CompressionFunction,
+    {
+        let mut hasher = MerkleDamgard::<C>::new();
+        hasher.update(key);
+        hasher.update(message);
+        hasher.finalize()
+    }
+
+    #[test]
+    fn test_extend_known_key_length() {
+        let key = b"super secret";
+        let message = b"user=alice";
+        let mac = secret_prefix_mac::<Sha256Compression>(key, message);
+
+        let forgery =
+            extend::<Sha256Compression>(&mac, message, key.len(), b";admin=true").unwrap();
+        assert!(forgery.message.ends_with(b";admin=true"));
+        assert_eq!(
+            forgery.mac,
+            secret_prefix_mac::<Sha256Compression>(key, &forgery.message)
+        );
+    }
+
+    #[test]
+    fn test_brute_force_key_length() {
+        let key = b"0123456789abcdefghijklmnopq";
+        let message = b"user=alice";
+        let mac = secret_prefix_mac::<Sha512Compression>(key, message);
+
+        let oracle = |message: &[u8], mac: &[u8; 64]| {
+            secret_prefix_mac::<Sha512Compression>(key, message) == *mac
+        };
+        let (key_length, _) =
+            brute_force_key_length::<Sha512Compression, _>(&mac, message, b"!", 64, oracle)
+                .unwrap();
+        assert_eq!(key_length, key.len());
+    }
+
+    #[test]
+    fn test_truncated_digest() {
+        let mac = secret_prefix_mac::<Sha224Compression>(b"key", b"message");
+        assert!(extend::<Sha224Compression>(&mac, b"message", 3, b"suffix").is_none());
+    }
+}
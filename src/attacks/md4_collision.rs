@@ -0,0 +1,442 @@
+use crate::hasher::md4::{self, SHIFTS, STANDARD, STEPS_PER_ROUND, WORD_ORDER};
+
+// Wang et al.'s MD4 collisions. M' differs from M in three words,
+//
+//     m1' = m1 + 2^31, m2' = m2 + 2^31 - 2^28, m12' = m12 - 2^16,
+//
+// and the difference cancels out by the end of the compression function if
+// the intermediate registers follow a differential path, which shows up as
+// conditions on their bits. The first round conditions can all be met by
+// choosing the words (single-step modification) and some of the second
+// round ones by reaching back into the first (multi-step modification). The
+// rest are left to chance.
+// https://link.springer.com/content/pdf/10.1007/11426639_1.pdf
+// https://cryptopals.com/sets/7/challenges/55
+
+// Registers in the order they are written: a0, d0, c0 and b0 from the IV,
+// then one per step. The paper numbers them a1, d1, c1, b1, a2, ...
+pub const REGISTERS: usize = 4 + 48;
+
+pub const fn a(i: usize) -> usize {
+    4 * i
+}
+
+pub const fn d(i: usize) -> usize {
+    4 * i + 1
+}
+
+pub const fn c(i: usize) -> usize {
+    4 * i + 2
+}
+
+pub const fn b(i: usize) -> usize {
+    4 * i + 3
+}
+
+// A condition on one bit of a register. Bits are numbered from 1 as in the
+// paper; `Equal` and `Differ` compare with the same bit of another register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Condition {
+    Zero(u32),
+    One(u32),
+    Equal(u32, usize),
+    Differ(u32, usize),
+}
+
+use Condition::{Differ, Equal, One, Zero};
+
+impl Condition {
+    fn bit(&self) -> u32 {
+        match *self {
+            Zero(bit) | One(bit) | Equal(bit, _) | Differ(bit, _) => bit - 1,
+        }
+    }
+
+    // The value the bit should have.
+    fn expected(&self, registers: &[u32; REGISTERS]) -> u32 {
+        let bit = self.bit();
+        match *self {
+            Zero(_) => 0,
+            One(_) => 1,
+            Equal(_, other) => (registers[other] >> bit) & 1,
+            Differ(_, other) => !(registers[other] >> bit) & 1,
+        }
+    }
+
+    pub fn holds(&self, value: u32, registers: &[u32; REGISTERS]) -> bool {
+        (value >> self.bit()) & 1 == self.expected(registers)
+    }
+
+    pub fn enforce(&self, value: u32, registers: &[u32; REGISTERS]) -> u32 {
+        let bit = self.bit();
+        (value & !(1 << bit)) | (self.expected(registers) << bit)
+    }
+}
+
+// Sufficient conditions for the first round, table 6 of the paper.
+pub const ROUND_1: [(usize, &[Condition]); 16] = [
+    (a(1), &[Equal(7, b(0))]),
+    (d(1), &[Zero(7), Equal(8, a(1)), Equal(11, a(1))]),
+    (c(1), &[One(7), One(8), Zero(11), Equal(26, d(1))]),
+    (b(1), &[One(7), Zero(8), Zero(11), Zero(26)]),
+    (a(2), &[One(8), One(11), Zero(26), Equal(14, b(1))]),
+    (
+        d(2),
+        &[
+            Zero(14),
+            Equal(19, a(2)),
+            Equal(20, a(2)),
+            Equal(21, a(2)),
+            Equal(22, a(2)),
+            One(26),
+        ],
+    ),
+    (
+        c(2),
+        &[
+            Equal(13, d(2)),
+            Zero(14),
+            Equal(15, d(2)),
+            Zero(19),
+            Zero(20),
+            One(21),
+            Zero(22),
+        ],
+    ),
+    (
+        b(2),
+        &[
+            One(13),
+            One(14),
+            Zero(15),
+            Equal(17, c(2)),
+            Zero(19),
+            Zero(20),
+            Zero(21),
+            Zero(22),
+        ],
+    ),
+    (
+        a(3),
+        &[
+            One(13),
+            One(14),
+            One(15),
+            Zero(17),
+            Zero(19),
+            Zero(20),
+            Zero(21),
+            Equal(23, b(2)),
+            One(22),
+            Equal(26, b(2)),
+        ],
+    ),
+    (
+        d(3),
+        &[
+            One(13),
+            One(14),
+            One(15),
+            Zero(17),
+            Zero(20),
+            One(21),
+            One(22),
+            Zero(23),
+            One(26),
+            Equal(30, a(3)),
+        ],
+    ),
+    (
+        c(3),
+        &[
+            One(17),
+            Zero(20),
+            Zero(21),
+            Zero(22),
+            Zero(23),
+            Zero(26),
+            One(30),
+            Equal(32, d(3)),
+        ],
+    ),
+    (
+        b(3),
+        &[
+            Zero(20),
+            One(21),
+            One(22),
+            Equal(23, c(3)),
+            One(26),
+            Zero(30),
+            Zero(32),
+        ],
+    ),
+    (
+        a(4),
+        &[
+            Zero(23),
+            Zero(26),
+            Equal(27, b(3)),
+            Equal(29, b(3)),
+            One(30),
+            Zero(32),
+        ],
+    ),
+    (
+        d(4),
+        &[Zero(23), Zero(26), One(27), One(29), Zero(30), One(32)],
+    ),
+    (
+        c(4),
+        &[
+            Equal(19, d(4)),
+            One(23),
+            One(26),
+            Zero(27),
+            Zero(29),
+            Zero(30),
+        ],
+    ),
+    (b(4), &[Zero(19), One(26), One(27), One(29), Zero(30)]),
+];
+
+// Second and third round conditions.
+pub const LATER_ROUNDS: [(usize, &[Condition]); 9] = [
+    (
+        a(5),
+        &[Equal(19, c(4)), One(26), Zero(27), One(29), One(32)],
+    ),
+    (
+        d(5),
+        &[
+            Equal(19, a(5)),
+            Equal(26, b(4)),
+            Equal(27, b(4)),
+            Equal(29, b(4)),
+            Equal(32, b(4)),
+        ],
+    ),
+    (
+        c(5),
+        &[
+            Equal(26, d(5)),
+            Equal(27, d(5)),
+            Equal(29, d(5)),
+            Equal(30, d(5)),
+            Equal(32, d(5)),
+        ],
+    ),
+    (b(5), &[Equal(29, c(5)), One(30), Zero(32)]),
+    (a(6), &[One(29), One(32)]),
+    (d(6), &[Equal(29, b(5))]),
+    (c(6), &[Equal(29, d(6)), Differ(30, d(6)), Differ(32, d(6))]),
+    (b(9), &[One(32)]),
+    (a(10), &[One(32)]),
+];
+
+// The register written by step `step`, the registers being ordered as MD4
+// updates them.
+fn step(registers: &[u32; REGISTERS], step: usize, words: &[u32; 16]) -> u32 {
+    let round = step / STEPS_PER_ROUND;
+    let mut register = registers[step];
+    md4::round(
+        &mut register,
+        registers[step + 3],
+        registers[step + 2],
+        registers[step + 1],
+        STANDARD.functions()[round],
+        words[WORD_ORDER[round][step % 16]],
+        SHIFTS[round][step % 4],
+        STANDARD.constants()[round],
+    );
+    register
+}
+
+// The word making first round step `step` write `value`: `step` run
+// backwards. First round steps take the words in order.
+fn solve(registers: &[u32; REGISTERS], step: usize, value: u32) -> u32 {
+    value
+        .rotate_right(SHIFTS[0][step % 4])
+        .wrapping_sub(registers[step])
+        .wrapping_sub(STANDARD.functions()[0](
+            registers[step + 3],
+            registers[step + 2],
+            registers[step + 1],
+        ))
+        .wrapping_sub(STANDARD.constants()[0])
+}
+
+// Instrumented compression function: every register it writes, from the
+// chaining value `iv`.
+pub fn trace(iv: [u32; 4], words: &[u32; 16]) -> [u32; REGISTERS] {
+    let mut registers = [0; REGISTERS];
+    registers[..4].copy_from_slice(&[iv[0], iv[3], iv[2], iv[1]]);
+    for i in 0..48 {
+        registers[i + 4] = step(&registers, i, words);
+    }
+    registers
+}
+
+// The compression function computed from its trace.
+pub fn compress(iv: [u32; 4], words: &[u32; 16]) -> [u32; 4] {
+    let registers = trace(iv, words);
+    [
+        iv[0].wrapping_add(registers[a(12)]),
+        iv[1].wrapping_add(registers[b(12)]),
+        iv[2].wrapping_add(registers[c(12)]),
+        iv[3].wrapping_add(registers[d(12)]),
+    ]
+}
+
+// The conditions `registers` break, by register.
+pub fn unsatisfied(registers: &[u32; REGISTERS]) -> Vec<(usize, Condition)> {
+    ROUND_1
+        .iter()
+        .chain(LATER_ROUNDS.iter())
+        .flat_map(|(register, conditions)| {
+            conditions
+                .iter()
+                .filter(|condition| !condition.holds(registers[*register], registers))
+                .map(|condition| (*register, *condition))
+        })
+        .collect()
+}
+
+// Flips `bit` of the register written by first round step `step` and solves
+// for the words of that step and the next four, so every other first round
+// register stays as it was.
+fn flip(words: &mut [u32; 16], registers: &mut [u32; REGISTERS], step: usize, bit: u32) {
+    registers[step + 4] ^= 1 << bit;
+    for i in step..step + 5 {
+        words[i] = solve(registers, i, registers[i + 4]);
+    }
+    *registers = trace(md4::IV, words);
+}
+
+// Rewrites `words` to meet every first round condition and, most of the
+// time, those on a5 and d5.
+pub fn modify(words: &mut [u32; 16]) {
+    let mut registers = trace(md4::IV, words);
+    for (i, (register, conditions)) in ROUND_1.iter().enumerate() {
+        let value = step(&registers, i, words);
+        let value = conditions.iter().fold(value, |value, condition| {
+            condition.enforce(value, &registers)
+        });
+        words[i] = solve(&registers, i, value);
+        registers[*register] = value;
+    }
+    registers = trace(md4::IV, words);
+
+    // a5 = (a4 + G(b4, c4, d4) + m0 + K) <<< 3: flipping bit i of a1 moves m0
+    // by 2^(i-3) and a5 by 2^i. d5 takes m4, which is fixed through a2, with
+    // a rotation of 5: bit i of d5 moves with bit i-2 of a2. Neither a1 nor
+    // a2 has conditions on the bits flipped. Carries can still break them.
+    for (register, via, offset) in [(a(5), 0, 0), (d(5), 4, 2)] {
+        let conditions = LATER_ROUNDS.iter().find(|(r, _)| *r == register).unwrap().1;
+        for condition in conditions {
+            if !condition.holds(registers[register], &registers) {
+                let bit = (condition.bit() + 32 - offset) % 32;
+                flip(words, &mut registers, via, bit);
+            }
+        }
+    }
+}
+
+// M', the message M should collide with.
+pub fn differential(words: &[u32; 16]) -> [u32; 16] {
+    let mut other = *words;
+    other[1] = other[1].wrapping_add(1 << 31);
+    other[2] = other[2].wrapping_add(1 << 31).wrapping_sub(1 << 28);
+    other[12] = other[12].wrapping_sub(1 << 16);
+    other
+}
+
+pub fn words_to_bytes(words: &[u32; 16]) -> [u8; 64] {
+    let mut bytes = [0; 64];
+    for (chunk, word) in bytes.chunks_mut(4).zip(words) {
+        chunk.copy_from_slice(&word.to_le_bytes());
+    }
+    bytes
+}
+
+pub fn bytes_to_words(bytes: &[u8; 64]) -> [u32; 16] {
+    let mut words = [0; 16];
+    for (word, chunk) in words.iter_mut().zip(bytes.chunks(4)) {
+        *word = u32::from_le_bytes(chunk.try_into().unwrap());
+    }
+    words
+}
+
+// Two one-block messages with the same MD4 digest. Being equally long they
+// get the same padding, so colliding the first compression is enough.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Md4Collision {
+    pub first: [u8; 64],
+    pub second: [u8; 64],
+    // Random messages modified and tried before this one collided.
+    pub trials: u64,
+}
+
+// Random words from a splitmix64 stream.
+fn random_words(seed: &mut u64) -> [u32; 16] {
+    let mut words = [0; 16];
+    for pair in words.chunks_mut(2) {
+        *seed = seed.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = *seed;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^= z >> 31;
+        pair[0] = z as u32;
+        pair[1] = (z >> 32) as u32;
+    }
+    words
+}
+
+// Modifies random messages from `seed` until one collides with its
+// differential, up to `max_trials`.
+pub fn find_collision(mut seed: u64, max_trials: u64) -> Option<Md4Collision> {
+    for trial in 1..=max_trials {
+        let mut words = random_words(&mut seed);
+        modify(&mut words);
+        let other = differential(&words);
+        if compress(md4::IV, &words) == compress(md4::IV, &other) {
+            return Some(Md4Collision {
+                first: words_to_bytes(&words),
+                second: words_to_bytes(&other),
+                trials: trial,
+            });
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hasher::md4::Md4Compression;
+    use crate::hasher::CompressionFunction;
+
+    #[test]
+    fn test_compress() {
+        let mut seed = 7;
+        for _ in 0..10 {
+            let words = random_words(&mut seed);
+            let mut expected = md4::IV;
+            Md4Compression::compress(&mut expected, &words);
+            assert_eq!(compress(md4::IV, &words), expected);
+            assert_eq!(bytes_to_words(&words_to_bytes(&words)), words);
+        }
+    }
+
+    #[test]
+    fn test_modify() {
+        let mut seed = 11;
+        for _ in 0..100 {
+            let mut words = random_words(&mut seed);
+            modify(&mut words);
+            let registers = trace(md4::IV, &words);
+            let unsatisfied = unsatisfied(&registers);
+            assert!(unsatisfied.iter().all(|(register, _)| *register >= a(5)));
+        }
+    }
+}
@@ -0,0 +1,7 @@
+pub mod birthday;
+pub mod expandable_message;
+pub mod hash_flooding;
+pub mod herding;
+pub mod length_extension;
+pub mod md4_collision;
+pub mod multicollision;
@@ -0,0 +1,454 @@
+use crate::hasher::merkle_damgard::{compress_block, Word};
+use crate::hasher::{md_padding, CompressionFunction};
+use std::collections::HashMap;
+use std::marker::PhantomData;
+
+// Joux's multicollisions: in a Merkle–Damgård hash, a collision from some
+// chaining value can be followed by a collision from the value both
+// messages reach, so k birthday searches give 2^k messages with one digest.
+// Concatenating two such hashes is then barely stronger than the better one.
+// https://www.iacr.org/archive/crypto2004/31520306/multicollisions.pdf
+// https://cryptopals.com/sets/7/challenges/52
+
+// A Merkle–Damgård hash whose chaining value is cut down to `bits` bits,
+// small enough to collide by brute force. The state sits in the first bits
+// of the real chaining value, the rest of it is taken from the IV.
+pub struct WeakHash<C> {
+    bits: u32,
+    compression: PhantomData<fn() -> C>,
+}
+
+impl<C> Clone for WeakHash<C> {
+    fn clone(&self) -> Self {
+        WeakHash {
+            bits: self.bits,
+            compression: PhantomData,
+        }
+    }
+}
+
+impl<C> WeakHash<C>
+where
+    C: CompressionFunction,
+{
+    // None unless 1 <= bits <= 64 and the chaining value has 64 bits to
+    // spare.
+    pub fn new(bits: u32) -> Option<Self> {
+        let chaining_value_size = C::IV.as_ref().len() * <C::Word as Word>::SIZE;
+        if bits == 0 || bits > 64 || chaining_value_size < 8 {
+            return None;
+        }
+        Some(WeakHash {
+            bits,
+            compression: PhantomData,
+        })
+    }
+
+    pub fn bits(&self) -> u32 {
+        self.bits
+    }
+
+    // The IV truncated like every other state.
+    pub fn iv(&self) -> u64 {
+        self.truncate(&C::IV)
+    }
+
+    fn truncate(&self, chaining_value: &C::ChainingValue) -> u64 {
+        let mut bytes = vec![];
+        for word in chaining_value.as_ref() {
+            word.extend_bytes(&mut bytes, C::ENDIAN);
+        }
+        u64::from_be_bytes(bytes[..8].try_into().unwrap()) >> (64 - self.bits)
+    }
+
+    // One call to the compression function. `block` must be a whole block.
+    pub fn compress(&self, state: u64, block: &[u8]) -> u64 {
+        let mut bytes = vec![];
+        for word in C::IV.as_ref() {
+            word.extend_bytes(&mut bytes, C::ENDIAN);
+        }
+        bytes[..8].copy_from_slice(&(state << (64 - self.bits)).to_be_bytes());
+
+        let mut chaining_value = C::IV;
+        for (word, chunk) in chaining_value
+            .as_mut()
+            .iter_mut()
+            .zip(bytes.chunks(<C::Word as Word>::SIZE))
+        {
+            *word = C::Word::from_bytes(chunk, C::ENDIAN);
+        }
+        compress_block::<C>(&mut chaining_value, block);
+        self.truncate(&chaining_value)
+    }
+
+    // Digest of a message already hashed `processed_length` bytes into, ending
+    // in `state`: the padding is compressed on top.
+    pub fn finalize(&self, state: u64, processed_length: usize) -> u64 {
+        let padding = md_padding(C::BLOCK_SIZE, C::LENGTH_SIZE, processed_length, C::ENDIAN);
+        padding
+            .chunks(C::BLOCK_SIZE)
+            .fold(state, |state, block| self.compress(state, block))
+    }
+
+    pub fn hash(&self, message: &[u8]) -> u64 {
+        let mut padded = message.to_vec();
+        padded.extend(md_padding(
+            C::BLOCK_SIZE,
+            C::LENGTH_SIZE,
+            message.len(),
+            C::ENDIAN,
+        ));
+        padded
+            .chunks(C::BLOCK_SIZE)
+            .fold(self.iv(), |state, block| self.compress(state, block))
+    }
+}
+
+// Candidate blocks: a big endian counter followed by zeros.
+fn block(index: u64, block_size: usize) -> Vec<u8> {
+    let mut block = vec![0; block_size];
+    block[..8].copy_from_slice(&index.to_be_bytes());
+    block
+}
+
+// Two distinct blocks taking `state` to the same state.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlockCollision {
+    pub first: Vec<u8>,
+    pub second: Vec<u8>,
+    pub state: u64,
+    pub compressions: u64,
+}
+
+// Birthday search over single blocks from `state`, about 2^(bits/2) calls to
+// the compression function.
+pub fn block_collision<C>(
+    hash: &WeakHash<C>,
+    state: u64,
+    max_compressions: u64,
+) -> Option<BlockCollision>
+where
+    C: CompressionFunction,
+{
+    let mut seen = HashMap::new();
+    for index in 0..max_compressions {
+        let next = hash.compress(state, &block(index, C::BLOCK_SIZE));
+        if let Some(other) = seen.insert(next, index) {
+            return Some(BlockCollision {
+                first: block(other, C::BLOCK_SIZE),
+                second: block(index, C::BLOCK_SIZE),
+                state: next,
+                compressions: index + 1,
+            });
+        }
+    }
+    None
+}
+
+// Birthday search for blocks taking two different states to one: `first`
+// is compressed from `first_state`, `second` from `second_state`. Hashes
+// from both sides until one lands on a state the other side has reached,
+// about 2^(bits/2) compressions on each.
+pub fn pair_collision<C>(
+    hash: &WeakHash<C>,
+    first_state: u64,
+    second_state: u64,
+    max_compressions: u64,
+) -> Option<BlockCollision>
+where
+    C: CompressionFunction,
+{
+    if first_state == second_state {
+        return block_collision(hash, first_state, max_compressions);
+    }
+
+    let (mut firsts, mut seconds) = (HashMap::new(), HashMap::new());
+    for index in 0..max_compressions / 2 {
+        let candidate = block(index, C::BLOCK_SIZE);
+        let first = hash.compress(first_state, &candidate);
+        let second = hash.compress(second_state, &candidate);
+        firsts.entry(first).or_insert(index);
+        seconds.entry(second).or_insert(index);
+
+        let found = match (seconds.get(&first), firsts.get(&second)) {
+            (Some(&other), _) => Some((index, other, first)),
+            (None, Some(&other)) => Some((other, index, second)),
+            (None, None) => None,
+        };
+        if let Some((first, second, state)) = found {
+            return Some(BlockCollision {
+                first: block(first, C::BLOCK_SIZE),
+                second: block(second, C::BLOCK_SIZE),
+                state,
+                compressions: 2 * (index + 1),
+            });
+        }
+    }
+    None
+}
+
+// A block from some state into one of many targets.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Bridge {
+    pub block: Vec<u8>,
+    // Whatever `targets` maps the state reached to.
+    pub target: usize,
+    pub compressions: u64,
+}
+
+// Brute force search for a block taking `state` into any of `targets`, about
+// 2^bits / targets.len() compressions.
+pub fn bridge<C>(
+    hash: &WeakHash<C>,
+    state: u64,
+    targets: &HashMap<u64, usize>,
+    max_compressions: u64,
+) -> Option<Bridge>
+where
+    C: CompressionFunction,
+{
+    for index in 0..max_compressions {
+        let candidate = block(index, C::BLOCK_SIZE);
+        if let Some(&target) = targets.get(&hash.compress(state, &candidate)) {
+            return Some(Bridge {
+                block: candidate,
+                target,
+                compressions: index + 1,
+            });
+        }
+    }
+    None
+}
+
+// 2^k messages of k blocks, all ending in the same state: block j is either
+// block of the j-th collision.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Multicollision {
+    pub collisions: Vec<BlockCollision>,
+    // State every message starts from.
+    pub start: u64,
+}
+
+impl Multicollision {
+    // Number of colliding messages, 2^k. None once it no longer fits a u64,
+    // from 64 collisions on.
+    pub fn count(&self) -> Option<u64> {
+        1u64.checked_shl(self.collisions.len() as u32)
+    }
+
+    // State every message ends in.
+    pub fn state(&self) -> u64 {
+        self.collisions.last().map_or(self.start, |c| c.state)
+    }
+
+    pub fn compressions(&self) -> u64 {
+        self.collisions.iter().map(|c| c.compressions).sum()
+    }
+
+    // The message numbered `index`, with the most significant of the k bits
+    // choosing the first block. None if `index` is out of range. Past 64
+    // collisions only the last 64 blocks can be chosen, the earlier ones are
+    // always the first of their pair.
+    pub fn message(&self, index: u64) -> Option<Vec<u8>> {
+        if self.count().is_some_and(|count| index >= count) {
+            return None;
+        }
+        let k = self.collisions.len();
+        let mut message = vec![];
+        for (j, collision) in self.collisions.iter().enumerate() {
+            let shift = (k - 1 - j) as u32;
+            if index.checked_shr(shift).unwrap_or(0) & 1 == 0 {
+                message.extend(&collision.first);
+            } else {
+                message.extend(&collision.second);
+            }
+        }
+        Some(message)
+    }
+
+    // Doubles the number of messages with one more collision search.
+    pub fn extend<C>(&mut self, hash: &WeakHash<C>, max_compressions: u64) -> Option<()>
+    where
+        C: CompressionFunction,
+    {
+        let collision = block_collision(hash, self.state(), max_compressions)?;
+        self.collisions.push(collision);
+        Some(())
+    }
+}
+
+// k chained collision searches from `start`. Each search gets its own budget.
+pub fn multicollision<C>(
+    hash: &WeakHash<C>,
+    start: u64,
+    k: usize,
+    max_compressions: u64,
+) -> Option<Multicollision>
+where
+    C: CompressionFunction,
+{
+    let mut multicollision = Multicollision {
+        collisions: vec![],
+        start,
+    };
+    for _ in 0..k {
+        multicollision.extend(hash, max_compressions)?;
+    }
+    Some(multicollision)
+}
+
+// A collision in f(x) || g(x), along with what it cost in calls to each
+// compression function.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CascadeCollision {
+    pub first: Vec<u8>,
+    pub second: Vec<u8>,
+    pub cheap_compressions: u64,
+    pub expensive_compressions: u64,
+}
+
+// Collides the cascade of a cheap b1-bit hash and an expensive b2-bit one.
+// A 2^(b2/2) multicollision in the cheap hash costs b2/2 birthday searches
+// of 2^(b1/2); hashing its messages with the expensive hash then finds a
+// collision among them with good probability. Otherwise one more pair
+// doubles the candidates, until `max_pairs`. The expensive hash is walked
+// as a tree, so 2^k messages cost about 2^(k+1) compressions rather than
+// k 2^k. Both hashes must share a block size.
+pub fn cascade_collision<F, G>(
+    cheap: &WeakHash<F>,
+    expensive: &WeakHash<G>,
+    max_pairs: usize,
+) -> Option<CascadeCollision>
+where
+    F: CompressionFunction,
+    G: CompressionFunction,
+{
+    if F::BLOCK_SIZE != G::BLOCK_SIZE {
+        return None;
+    }
+
+    // Generous budget for a single birthday search on the cheap hash.
+    let max_compressions = 1u64 << (cheap.bits() / 2 + 8).min(63);
+    let pairs = (expensive.bits() as usize).div_ceil(2).min(max_pairs);
+    let mut multicollision = multicollision(cheap, cheap.iv(), pairs, max_compressions)?;
+
+    // Expensive states after each message, in message order.
+    let mut states = vec![expensive.iv()];
+    let mut expensive_compressions = 0;
+    let mut walked = 0;
+    loop {
+        for collision in &multicollision.collisions[walked..] {
+            states = states
+                .iter()
+                .flat_map(|&state| {
+                    [
+                        expensive.compress(state, &collision.first),
+                        expensive.compress(state, &collision.second),
+                    ]
+                })
+                .collect();
+            expensive_compressions += states.len() as u64;
+        }
+        walked = multicollision.collisions.len();
+
+        // Messages have the same length, hence the same padding.
+        let length = walked * G::BLOCK_SIZE;
+        let mut seen = HashMap::new();
+        for (index, &state) in states.iter().enumerate() {
+            let digest = expensive.finalize(state, length);
+            expensive_compressions += 1;
+            if let Some(other) = seen.insert(digest, index as u64) {
+                return Some(CascadeCollision {
+                    first: multicollision.message(other)?,
+                    second: multicollision.message(index as u64)?,
+                    cheap_compressions: multicollision.compressions(),
+                    expensive_compressions,
+                });
+            }
+        }
+
+        if walked >= max_pairs {
+            return None;
+        }
+        multicollision.extend(cheap, max_compressions)?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hasher::md4::Md4Compression;
+    use crate::hasher::sha1::Sha1Compression;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_weak_hash() {
+        assert!(WeakHash::<Md4Compression>::new(0).is_none());
+        assert!(WeakHash::<Md4Compression>::new(65).is_none());
+
+        let hash = WeakHash::<Sha1Compression>::new(20).unwrap();
+        assert!(hash.iv() < 1 << 20);
+        assert_eq!(hash.iv(), 0x67452);
+
+        // Hashing is compressing the padded message block by block.
+        let message = [0x61; 100];
+        let state = hash.compress(hash.iv(), &message[..64]);
+        let mut tail = message[64..].to_vec();
+        tail.extend(md_padding(64, 8, 100, Sha1Compression::ENDIAN));
+        assert_eq!(tail.len(), 64);
+        assert_eq!(hash.hash(&message), hash.compress(state, &tail));
+    }
+
+    #[test]
+    fn test_block_collision() {
+        let hash = WeakHash::<Md4Compression>::new(16).unwrap();
+        let collision = block_collision(&hash, hash.iv(), 1 << 16).unwrap();
+        assert_ne!(collision.first, collision.second);
+        assert_eq!(hash.compress(hash.iv(), &collision.first), collision.state);
+        assert_eq!(hash.compress(hash.iv(), &collision.second), collision.state);
+
+        let collision = pair_collision(&hash, 0x1234, 0xABCD, 1 << 16).unwrap();
+        assert_eq!(hash.compress(0x1234, &collision.first), collision.state);
+        assert_eq!(hash.compress(0xABCD, &collision.second), collision.state);
+    }
+
+    #[test]
+    fn test_bridge() {
+        let hash = WeakHash::<Md4Compression>::new(12).unwrap();
+        let targets: HashMap<u64, usize> = [(0x123, 7), (0x456, 9)].into_iter().collect();
+        let bridge = bridge(&hash, hash.iv(), &targets, 1 << 20).unwrap();
+        let state = hash.compress(hash.iv(), &bridge.block);
+        assert_eq!(targets[&state], bridge.target);
+    }
+
+    #[test]
+    fn test_multicollision() {
+        let hash = WeakHash::<Md4Compression>::new(16).unwrap();
+        let multicollision = multicollision(&hash, hash.iv(), 5, 1 << 16).unwrap();
+        assert_eq!(multicollision.count(), Some(32));
+        assert!(multicollision.message(32).is_none());
+
+        let messages: HashSet<_> = (0..32)
+            .map(|index| multicollision.message(index).unwrap())
+            .collect();
+        assert_eq!(messages.len(), 32);
+
+        let digest = hash.hash(&multicollision.message(0).unwrap());
+        assert!(messages.iter().all(|message| hash.hash(message) == digest));
+    }
+
+    #[test]
+    fn test_large_multicollision() {
+        // 2^66 messages, more than a u64 counts.
+        let hash = WeakHash::<Md4Compression>::new(8).unwrap();
+        let multicollision = multicollision(&hash, hash.iv(), 66, 1 << 12).unwrap();
+        assert_eq!(multicollision.count(), None);
+
+        let first = multicollision.message(0).unwrap();
+        let last = multicollision.message(u64::MAX).unwrap();
+        assert_eq!(first.len(), 66 * 64);
+        assert_eq!(first[..2 * 64], last[..2 * 64]);
+        assert_ne!(first, last);
+        assert_eq!(hash.hash(&first), hash.hash(&last));
+    }
+}
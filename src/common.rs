@@ -4,16 +4,17 @@ pub trait AsBytes: Copy {
 
 impl AsBytes for &str {
     fn as_bytes(&self) -> &[u8] {
-        return (*self).as_bytes();
+        (*self).as_bytes()
     }
 }
 
 impl AsBytes for &[u8] {
     fn as_bytes(&self) -> &[u8] {
-        return *self;
+        self
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Endian {
     Big,
     Little,
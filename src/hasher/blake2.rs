@@ -0,0 +1,780 @@
+use super::merkle_damgard::Word;
+use super::*;
+use std::marker::PhantomData;
+use std::ops::{BitXor, Not};
+
+// BLAKE2b and BLAKE2s. Both are a HAIFA construction around a ChaCha-like
+// compression function: the chaining value is mixed with the IV, a byte
+// counter and a finalization flag, so neither length extension nor padding
+// tricks apply. They differ only in word size, round count and rotations.
+// https://www.rfc-editor.org/rfc/rfc7693
+
+const SIGMA: [[usize; 16]; 10] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+    [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+    [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+    [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+    [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+    [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+    [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+    [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+    [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
+];
+
+// Word arithmetic the mixing function needs on top of parsing.
+pub trait Blake2Word: Word + BitXor<Output = Self> + Not<Output = Self> {
+    fn wrapping_add(self, other: Self) -> Self;
+
+    fn rotate_right(self, n: u32) -> Self;
+
+    // Keeps the low bits, used to split the byte counter over two words.
+    fn truncate(value: u128) -> Self;
+}
+
+impl Blake2Word for u32 {
+    fn wrapping_add(self, other: Self) -> Self {
+        u32::wrapping_add(self, other)
+    }
+
+    fn rotate_right(self, n: u32) -> Self {
+        u32::rotate_right(self, n)
+    }
+
+    fn truncate(value: u128) -> Self {
+        value as u32
+    }
+}
+
+impl Blake2Word for u64 {
+    fn wrapping_add(self, other: Self) -> Self {
+        u64::wrapping_add(self, other)
+    }
+
+    fn rotate_right(self, n: u32) -> Self {
+        u64::rotate_right(self, n)
+    }
+
+    fn truncate(value: u128) -> Self {
+        value as u64
+    }
+}
+
+// What distinguishes BLAKE2b from BLAKE2s.
+pub trait Variant {
+    type Word: Blake2Word;
+
+    const ROUNDS: usize;
+    const ROTATIONS: [u32; 4];
+    const IV: [Self::Word; 8];
+
+    // Blocks are 16 words, the key and output at most 8 words and the salt
+    // and personalization 2 words each.
+    const BLOCK_SIZE: usize = 16 * <Self::Word as Word>::SIZE;
+    const MAX_OUTPUT_SIZE: usize = 8 * <Self::Word as Word>::SIZE;
+    const SALT_SIZE: usize = 2 * <Self::Word as Word>::SIZE;
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Blake2bVariant;
+
+impl Variant for Blake2bVariant {
+    type Word = u64;
+
+    const ROUNDS: usize = 12;
+    const ROTATIONS: [u32; 4] = [32, 24, 16, 63];
+    const IV: [u64; 8] = [
+        0x6A09E667F3BCC908,
+        0xBB67AE8584CAA73B,
+        0x3C6EF372FE94F82B,
+        0xA54FF53A5F1D36F1,
+        0x510E527FADE682D1,
+        0x9B05688C2B3E6C1F,
+        0x1F83D9ABFB41BD6B,
+        0x5BE0CD19137E2179,
+    ];
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Blake2sVariant;
+
+impl Variant for Blake2sVariant {
+    type Word = u32;
+
+    const ROUNDS: usize = 10;
+    const ROTATIONS: [u32; 4] = [16, 12, 8, 7];
+    const IV: [u32; 8] = [
+        0x6A09E667, 0xBB67AE85, 0x3C6EF372, 0xA54FF53A, 0x510E527F, 0x9B05688C, 0x1F83D9AB,
+        0x5BE0CD19,
+    ];
+}
+
+// Configuration folded into the initial chaining value. Empty key, salt and
+// personalization are the same as leaving them out.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Params {
+    pub output_length: usize,
+    pub key: Vec<u8>,
+    pub salt: Vec<u8>,
+    pub personalization: Vec<u8>,
+}
+
+impl Params {
+    pub fn new(output_length: usize) -> Self {
+        Params {
+            output_length,
+            key: vec![],
+            salt: vec![],
+            personalization: vec![],
+        }
+    }
+
+    pub fn keyed(key: &[u8], output_length: usize) -> Self {
+        Params {
+            key: key.to_vec(),
+            ..Params::new(output_length)
+        }
+    }
+
+    // Whether every parameter fits variant `V`.
+    fn fits<V>(&self) -> bool
+    where
+        V: Variant,
+    {
+        self.output_length > 0
+            && self.output_length <= V::MAX_OUTPUT_SIZE
+            && self.key.len() <= V::MAX_OUTPUT_SIZE
+            && self.salt.len() <= V::SALT_SIZE
+            && self.personalization.len() <= V::SALT_SIZE
+    }
+}
+
+fn mix<V>(v: &mut [V::Word; 16], indices: [usize; 4], x: V::Word, y: V::Word)
+where
+    V: Variant,
+{
+    let [a, b, c, d] = indices;
+    let [r1, r2, r3, r4] = V::ROTATIONS;
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(x);
+    v[d] = (v[d] ^ v[a]).rotate_right(r1);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(r2);
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(y);
+    v[d] = (v[d] ^ v[a]).rotate_right(r3);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(r4);
+}
+
+// Names of the working vector words in traces.
+const STATE_NAMES: [&str; 16] = [
+    "v0", "v1", "v2", "v3", "v4", "v5", "v6", "v7", "v8", "v9", "v10", "v11", "v12", "v13", "v14",
+    "v15",
+];
+
+// Compresses one block into `h`. `counter` is the number of message bytes
+// hashed so far including this block.
+pub fn compress<V>(h: &mut [V::Word; 8], block: &[u8], counter: u128, last: bool)
+where
+    V: Variant,
+{
+    compress_with::<V, _>(h, block, counter, last, &mut NoTrace);
+}
+
+// `compress`, reporting the message words and the working vector after every
+// G to `trace`. Step 8 * r + i is the i-th G of round r, columns first.
+pub fn compress_traced<V>(
+    h: &mut [V::Word; 8],
+    block: &[u8],
+    counter: u128,
+    last: bool,
+    trace: &mut dyn Trace<V::Word>,
+) where
+    V: Variant,
+{
+    compress_with::<V, _>(h, block, counter, last, trace);
+}
+
+fn compress_with<V, T>(h: &mut [V::Word; 8], block: &[u8], counter: u128, last: bool, trace: &mut T)
+where
+    V: Variant,
+    T: Trace<V::Word> + ?Sized,
+{
+    let word_size = <V::Word as Word>::SIZE;
+    let mut m = [V::Word::default(); 16];
+    for (word, bytes) in m.iter_mut().zip(block.chunks(word_size)) {
+        *word = V::Word::from_bytes(bytes, Endian::Little);
+    }
+    trace.record(Event::ExtendedWords(&m));
+
+    let mut v = [V::Word::default(); 16];
+    v[..8].copy_from_slice(h);
+    v[8..].copy_from_slice(&V::IV);
+    v[12] = v[12] ^ V::Word::truncate(counter);
+    v[13] = v[13] ^ V::Word::truncate(counter >> (8 * word_size));
+    if last {
+        v[14] = !v[14];
+    }
+
+    // Columns, then diagonals.
+    const INDICES: [[usize; 4]; 8] = [
+        [0, 4, 8, 12],
+        [1, 5, 9, 13],
+        [2, 6, 10, 14],
+        [3, 7, 11, 15],
+        [0, 5, 10, 15],
+        [1, 6, 11, 12],
+        [2, 7, 8, 13],
+        [3, 4, 9, 14],
+    ];
+    for round in 0..V::ROUNDS {
+        let s = &SIGMA[round % 10];
+        for (i, indices) in INDICES.into_iter().enumerate() {
+            mix::<V>(&mut v, indices, m[s[2 * i]], m[s[2 * i + 1]]);
+            trace.record(Event::Registers {
+                step: 8 * round + i,
+                names: &STATE_NAMES,
+                values: &v,
+            });
+        }
+    }
+
+    for i in 0..8 {
+        h[i] = h[i] ^ v[i] ^ v[i + 8];
+    }
+}
+
+// Chaining value and number of bytes compressed into it. The parameters are
+// part of the state: the output length changes the final truncation, and
+// `reset` starts over with the same key, salt and personalization.
+pub struct State<V>
+where
+    V: Variant,
+{
+    pub chaining_value: [V::Word; 8],
+    pub counter: u128,
+    params: Params,
+}
+
+impl<V> Clone for State<V>
+where
+    V: Variant,
+{
+    fn clone(&self) -> Self {
+        State {
+            chaining_value: self.chaining_value,
+            counter: self.counter,
+            params: self.params.clone(),
+        }
+    }
+}
+
+impl<V> State<V>
+where
+    V: Variant,
+{
+    // Returns `None` when a parameter doesn't fit the variant.
+    pub fn new(chaining_value: [V::Word; 8], counter: u128, params: Params) -> Option<Self> {
+        if !params.fits::<V>() {
+            return None;
+        }
+        Some(State {
+            chaining_value,
+            counter,
+            params,
+        })
+    }
+
+    pub fn params(&self) -> &Params {
+        &self.params
+    }
+}
+
+pub struct Blake2<V>
+where
+    V: Variant,
+{
+    h: [V::Word; 8],
+    // Up to a full block, as the last block has to be compressed with the
+    // finalization flag and there's no telling which one it is until then.
+    buffer: Vec<u8>,
+    counter: u128,
+    // Kept around for the output length and `reset`.
+    params: Params,
+    _variant: PhantomData<V>,
+}
+
+pub type Blake2b = Blake2<Blake2bVariant>;
+pub type Blake2s = Blake2<Blake2sVariant>;
+
+impl<V> Clone for Blake2<V>
+where
+    V: Variant,
+{
+    fn clone(&self) -> Self {
+        Blake2 {
+            h: self.h,
+            buffer: self.buffer.clone(),
+            counter: self.counter,
+            params: self.params.clone(),
+            _variant: PhantomData,
+        }
+    }
+}
+
+impl<V> Blake2<V>
+where
+    V: Variant,
+{
+    // Returns `None` when a parameter doesn't fit the variant.
+    pub fn with_params(params: &Params) -> Option<Self> {
+        if !params.fits::<V>() {
+            return None;
+        }
+
+        // Parameter block: digest length, key length, fanout and depth of 1
+        // for sequential hashing, tree fields left at zero, then the salt and
+        // personalization.
+        let mut parameter_block = vec![0; V::MAX_OUTPUT_SIZE];
+        parameter_block[0] = params.output_length as u8;
+        parameter_block[1] = params.key.len() as u8;
+        parameter_block[2] = 1;
+        parameter_block[3] = 1;
+        let salt_start = 2 * V::SALT_SIZE;
+        let personalization_start = salt_start + V::SALT_SIZE;
+        parameter_block[salt_start..salt_start + params.salt.len()].copy_from_slice(&params.salt);
+        parameter_block
+            [personalization_start..personalization_start + params.personalization.len()]
+            .copy_from_slice(&params.personalization);
+
+        let mut h = V::IV;
+        let word_size = <V::Word as Word>::SIZE;
+        for (word, bytes) in h.iter_mut().zip(parameter_block.chunks(word_size)) {
+            *word = *word ^ V::Word::from_bytes(bytes, Endian::Little);
+        }
+
+        let mut hasher = Blake2 {
+            h,
+            buffer: Vec::with_capacity(V::BLOCK_SIZE),
+            counter: 0,
+            params: params.clone(),
+            _variant: PhantomData,
+        };
+
+        // A key is hashed as a full first block.
+        if !params.key.is_empty() {
+            let mut key_block = params.key.clone();
+            key_block.resize(V::BLOCK_SIZE, 0);
+            hasher.update(key_block.as_slice());
+        }
+        Some(hasher)
+    }
+
+    pub fn with_output_length(output_length: usize) -> Option<Self> {
+        Blake2::with_params(&Params::new(output_length))
+    }
+
+    pub fn keyed(key: &[u8], output_length: usize) -> Option<Self> {
+        Blake2::with_params(&Params::keyed(key, output_length))
+    }
+
+    // State after the last compressed block. The buffered bytes, of which
+    // there is always at least one once anything was hashed, have to be
+    // replayed after `from_state`.
+    pub fn state(&self) -> State<V> {
+        State {
+            chaining_value: self.h,
+            counter: self.counter,
+            params: self.params.clone(),
+        }
+    }
+
+    pub fn buffered(&self) -> &[u8] {
+        &self.buffer
+    }
+
+    // Hashes `message` and finalizes, reporting the chaining value going into
+    // every block and each compression's steps to `trace`. A key block is
+    // block 0.
+    pub fn traced<T>(mut self, message: T, trace: &mut dyn Trace<V::Word>) -> Vec<u8>
+    where
+        T: AsBytes,
+    {
+        self.update_with(message.as_bytes(), trace);
+        self.finalize_with(trace)
+    }
+
+    fn compress_buffer<T>(&mut self, last: bool, trace: &mut T)
+    where
+        T: Trace<V::Word> + ?Sized,
+    {
+        // Only the last block can be partial, so the counter still counts
+        // whole blocks here.
+        let block = (self.counter / V::BLOCK_SIZE as u128) as usize;
+        trace.record(Event::ChainingValue {
+            block,
+            value: &self.h,
+        });
+        self.counter += self.buffer.len() as u128;
+        self.buffer.resize(V::BLOCK_SIZE, 0);
+        compress_with::<V, T>(&mut self.h, &self.buffer, self.counter, last, trace);
+        self.buffer.clear();
+        if last {
+            trace.record(Event::ChainingValue {
+                block: block + 1,
+                value: &self.h,
+            });
+        }
+    }
+
+    fn update_with<T>(&mut self, mut bytes: &[u8], trace: &mut T)
+    where
+        T: Trace<V::Word> + ?Sized,
+    {
+        while !bytes.is_empty() {
+            // Only compress a full buffer once more input shows it isn't the
+            // last block.
+            if self.buffer.len() == V::BLOCK_SIZE {
+                self.compress_buffer(false, trace);
+            }
+
+            let taken = (V::BLOCK_SIZE - self.buffer.len()).min(bytes.len());
+            self.buffer.extend(&bytes[..taken]);
+            bytes = &bytes[taken..];
+        }
+    }
+
+    fn finalize_with<T>(mut self, trace: &mut T) -> Vec<u8>
+    where
+        T: Trace<V::Word> + ?Sized,
+    {
+        self.compress_buffer(true, trace);
+
+        let mut output = vec![];
+        for word in self.h {
+            word.extend_bytes(&mut output, Endian::Little);
+        }
+        output.truncate(self.params.output_length);
+        output
+    }
+}
+
+impl<V> Hasher for Blake2<V>
+where
+    V: Variant,
+{
+    const BLOCK_SIZE: usize = V::BLOCK_SIZE;
+
+    type State = State<V>;
+    type Digest = Vec<u8>;
+
+    // Unkeyed with the longest output.
+    fn new() -> Self {
+        Blake2::with_output_length(V::MAX_OUTPUT_SIZE).unwrap()
+    }
+
+    fn from_state(state: Self::State) -> Self {
+        Blake2 {
+            h: state.chaining_value,
+            buffer: Vec::with_capacity(V::BLOCK_SIZE),
+            counter: state.counter,
+            params: state.params,
+            _variant: PhantomData,
+        }
+    }
+
+    fn update<T>(&mut self, message: T)
+    where
+        T: AsBytes,
+    {
+        self.update_with(message.as_bytes(), &mut NoTrace);
+    }
+
+    fn finalize(self) -> Self::Digest {
+        self.finalize_with(&mut NoTrace)
+    }
+
+    fn reset(&mut self) {
+        *self = match Blake2::with_params(&self.params) {
+            Some(hasher) => hasher,
+            None => unreachable!("parameters are checked on the way in"),
+        };
+    }
+}
+
+pub fn blake2b<T>(message: T, output_length: usize) -> Vec<u8>
+where
+    T: AsBytes,
+{
+    let mut hasher = Blake2b::with_output_length(output_length).unwrap();
+    hasher.update(message);
+    hasher.finalize()
+}
+
+pub fn blake2s<T>(message: T, output_length: usize) -> Vec<u8>
+where
+    T: AsBytes,
+{
+    let mut hasher = Blake2s::with_output_length(output_length).unwrap();
+    hasher.update(message);
+    hasher.finalize()
+}
+
+// `blake2b`, reporting every block's message words and G steps to `trace`.
+pub fn blake2b_traced<T>(message: T, output_length: usize, trace: &mut dyn Trace<u64>) -> Vec<u8>
+where
+    T: AsBytes,
+{
+    Blake2b::with_output_length(output_length)
+        .unwrap()
+        .traced(message, trace)
+}
+
+// `blake2s`, reporting every block's message words and G steps to `trace`.
+pub fn blake2s_traced<T>(message: T, output_length: usize, trace: &mut dyn Trace<u32>) -> Vec<u8>
+where
+    T: AsBytes,
+{
+    Blake2s::with_output_length(output_length)
+        .unwrap()
+        .traced(message, trace)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FOX: &str = "The quick brown fox jumps over the lazy dog";
+
+    #[test]
+    fn test_blake2b() {
+        // Example from RFC 7693 appendix A.
+        let expected_abc: [u8; 64] = [
+            0xBA, 0x80, 0xA5, 0x3F, 0x98, 0x1C, 0x4D, 0x0D, 0x6A, 0x27, 0x97, 0xB6, 0x9F, 0x12,
+            0xF6, 0xE9, 0x4C, 0x21, 0x2F, 0x14, 0x68, 0x5A, 0xC4, 0xB7, 0x4B, 0x12, 0xBB, 0x6F,
+            0xDB, 0xFF, 0xA2, 0xD1, 0x7D, 0x87, 0xC5, 0x39, 0x2A, 0xAB, 0x79, 0x2D, 0xC2, 0x52,
+            0xD5, 0xDE, 0x45, 0x33, 0xCC, 0x95, 0x18, 0xD3, 0x8A, 0xA8, 0xDB, 0xF1, 0x92, 0x5A,
+            0xB9, 0x23, 0x86, 0xED, 0xD4, 0x00, 0x99, 0x23,
+        ];
+        let expected_empty: [u8; 64] = [
+            0x78, 0x6A, 0x02, 0xF7, 0x42, 0x01, 0x59, 0x03, 0xC6, 0xC6, 0xFD, 0x85, 0x25, 0x52,
+            0xD2, 0x72, 0x91, 0x2F, 0x47, 0x40, 0xE1, 0x58, 0x47, 0x61, 0x8A, 0x86, 0xE2, 0x17,
+            0xF7, 0x1F, 0x54, 0x19, 0xD2, 0x5E, 0x10, 0x31, 0xAF, 0xEE, 0x58, 0x53, 0x13, 0x89,
+            0x64, 0x44, 0x93, 0x4E, 0xB0, 0x4B, 0x90, 0x3A, 0x68, 0x5B, 0x14, 0x48, 0xB7, 0x55,
+            0xD5, 0x6F, 0x70, 0x1A, 0xFE, 0x9B, 0xE2, 0xCE,
+        ];
+        let expected_fox_160: [u8; 20] = [
+            0x3C, 0x52, 0x3E, 0xD1, 0x02, 0xAB, 0x45, 0xA3, 0x7D, 0x54, 0xF5, 0x61, 0x0D, 0x5A,
+            0x98, 0x31, 0x62, 0xFD, 0xE8, 0x4F,
+        ];
+
+        assert_eq!(blake2b("abc", 64), expected_abc);
+        assert_eq!(blake2b("", 64), expected_empty);
+        assert_eq!(blake2b(FOX, 20), expected_fox_160);
+    }
+
+    #[test]
+    fn test_blake2s() {
+        // Example from RFC 7693 appendix B.
+        let expected_abc: [u8; 32] = [
+            0x50, 0x8C, 0x5E, 0x8C, 0x32, 0x7C, 0x14, 0xE2, 0xE1, 0xA7, 0x2B, 0xA3, 0x4E, 0xEB,
+            0x45, 0x2F, 0x37, 0x45, 0x8B, 0x20, 0x9E, 0xD6, 0x3A, 0x29, 0x4D, 0x99, 0x9B, 0x4C,
+            0x86, 0x67, 0x59, 0x82,
+        ];
+        let expected_empty: [u8; 32] = [
+            0x69, 0x21, 0x7A, 0x30, 0x79, 0x90, 0x80, 0x94, 0xE1, 0x11, 0x21, 0xD0, 0x42, 0x35,
+            0x4A, 0x7C, 0x1F, 0x55, 0xB6, 0x48, 0x2C, 0xA1, 0xA5, 0x1E, 0x1B, 0x25, 0x0D, 0xFD,
+            0x1E, 0xD0, 0xEE, 0xF9,
+        ];
+
+        assert_eq!(blake2s("abc", 32), expected_abc);
+        assert_eq!(blake2s("", 32), expected_empty);
+    }
+
+    #[test]
+    fn test_keyed() {
+        // Last entries of the keyed known answer tests from the reference
+        // implementation: key 00 01 02 .., message 00 01 .. FE.
+        let expected_blake2b: [u8; 64] = [
+            0x14, 0x27, 0x09, 0xD6, 0x2E, 0x28, 0xFC, 0xCC, 0xD0, 0xAF, 0x97, 0xFA, 0xD0, 0xF8,
+            0x46, 0x5B, 0x97, 0x1E, 0x82, 0x20, 0x1D, 0xC5, 0x10, 0x70, 0xFA, 0xA0, 0x37, 0x2A,
+            0xA4, 0x3E, 0x92, 0x48, 0x4B, 0xE1, 0xC1, 0xE7, 0x3B, 0xA1, 0x09, 0x06, 0xD5, 0xD1,
+            0x85, 0x3D, 0xB6, 0xA4, 0x10, 0x6E, 0x0A, 0x7B, 0xF9, 0x80, 0x0D, 0x37, 0x3D, 0x6D,
+            0xEE, 0x2D, 0x46, 0xD6, 0x2E, 0xF2, 0xA4, 0x61,
+        ];
+        let expected_blake2s: [u8; 32] = [
+            0x3F, 0xB7, 0x35, 0x06, 0x1A, 0xBC, 0x51, 0x9D, 0xFE, 0x97, 0x9E, 0x54, 0xC1, 0xEE,
+            0x5B, 0xFA, 0xD0, 0xA9, 0xD8, 0x58, 0xB3, 0x31, 0x5B, 0xAD, 0x34, 0xBD, 0xE9, 0x99,
+            0xEF, 0xD7, 0x24, 0xDD,
+        ];
+        // First entry: the key alone still makes up a block.
+        let expected_blake2b_empty: [u8; 64] = [
+            0x10, 0xEB, 0xB6, 0x77, 0x00, 0xB1, 0x86, 0x8E, 0xFB, 0x44, 0x17, 0x98, 0x7A, 0xCF,
+            0x46, 0x90, 0xAE, 0x9D, 0x97, 0x2F, 0xB7, 0xA5, 0x90, 0xC2, 0xF0, 0x28, 0x71, 0x79,
+            0x9A, 0xAA, 0x47, 0x86, 0xB5, 0xE9, 0x96, 0xE8, 0xF0, 0xF4, 0xEB, 0x98, 0x1F, 0xC2,
+            0x14, 0xB0, 0x05, 0xF4, 0x2D, 0x2F, 0xF4, 0x23, 0x34, 0x99, 0x39, 0x16, 0x53, 0xDF,
+            0x7A, 0xEF, 0xCB, 0xC1, 0x3F, 0xC5, 0x15, 0x68,
+        ];
+
+        let message: Vec<u8> = (0x00..=0xFE).collect();
+        let key: Vec<u8> = (0x00..0x40).collect();
+
+        let mut hasher = Blake2b::keyed(&key, 64).unwrap();
+        hasher.update(message.as_slice());
+        assert_eq!(hasher.finalize(), expected_blake2b);
+
+        let mut hasher = Blake2s::keyed(&key[..32], 32).unwrap();
+        hasher.update(message.as_slice());
+        assert_eq!(hasher.finalize(), expected_blake2s);
+
+        let hasher = Blake2b::keyed(&key, 64).unwrap();
+        assert_eq!(hasher.finalize(), expected_blake2b_empty);
+    }
+
+    #[test]
+    fn test_salt_and_personalization() {
+        let expected_blake2b: [u8; 32] = [
+            0xF3, 0x2B, 0x9C, 0x38, 0xCF, 0xB6, 0x55, 0x1E, 0xBF, 0x4B, 0x9E, 0xCB, 0x18, 0x7B,
+            0xC2, 0x6C, 0x1D, 0x45, 0x8E, 0x07, 0xF5, 0x4E, 0xCA, 0xA2, 0x9B, 0x7C, 0xD7, 0xB6,
+            0x61, 0x07, 0x20, 0x7F,
+        ];
+        let expected_blake2s: [u8; 16] = [
+            0xF9, 0xC1, 0xD9, 0xB3, 0x89, 0x7D, 0x7D, 0xDF, 0x58, 0x9F, 0x8C, 0xA8, 0x49, 0x13,
+            0xAB, 0x02,
+        ];
+
+        let mut params = Params::new(32);
+        params.salt = b"brykto salt 0123".to_vec();
+        params.personalization = b"brykto personal!".to_vec();
+        let mut hasher = Blake2b::with_params(&params).unwrap();
+        hasher.update(FOX);
+        assert_eq!(hasher.finalize(), expected_blake2b);
+
+        let mut params = Params::new(16);
+        params.salt = b"saltsalt".to_vec();
+        params.personalization = b"personal".to_vec();
+        let mut hasher = Blake2s::with_params(&params).unwrap();
+        hasher.update(FOX);
+        assert_eq!(hasher.finalize(), expected_blake2s);
+
+        // Too long for BLAKE2s.
+        params.salt = b"brykto salt 0123".to_vec();
+        assert!(Blake2s::with_params(&params).is_none());
+    }
+
+    #[test]
+    fn test_invalid_params() {
+        assert!(Blake2b::with_output_length(0).is_none());
+        assert!(Blake2b::with_output_length(65).is_none());
+        assert!(Blake2s::with_output_length(33).is_none());
+        assert!(Blake2s::keyed(&[0; 33], 32).is_none());
+    }
+
+    #[test]
+    fn test_blake2_streaming() {
+        let message = FOX.repeat(10);
+
+        // Includes pieces that end exactly on a block boundary.
+        for piece_size in [1, 7, 63, 64, 65, 128, 200] {
+            let mut hasher = Blake2b::new();
+            for piece in message.as_bytes().chunks(piece_size) {
+                hasher.update(piece);
+            }
+            assert_eq!(hasher.finalize(), blake2b(message.as_str(), 64));
+
+            let mut hasher = Blake2s::new();
+            for piece in message.as_bytes().chunks(piece_size) {
+                hasher.update(piece);
+            }
+            assert_eq!(hasher.finalize(), blake2s(message.as_str(), 32));
+        }
+    }
+
+    #[test]
+    fn test_blake2_state() {
+        let message = FOX.repeat(10);
+
+        let mut hasher = Blake2b::keyed(b"key", 48).unwrap();
+        hasher.update(&message.as_bytes()[..300]);
+        let mut resumed = Blake2b::from_state(hasher.state());
+        resumed.update(hasher.buffered());
+        resumed.update(&message.as_bytes()[300..]);
+
+        let mut full = Blake2b::keyed(b"key", 48).unwrap();
+        full.update(message.as_str());
+        assert_eq!(resumed.finalize(), full.finalize());
+
+        let mut hasher = Blake2s::keyed(b"key", 16).unwrap();
+        hasher.update("garbage");
+        hasher.reset();
+        hasher.update(FOX);
+        let mut fresh = Blake2s::keyed(b"key", 16).unwrap();
+        fresh.update(FOX);
+        assert_eq!(hasher.finalize(), fresh.finalize());
+        // A resumed hasher resets to its original key, salt and
+        // personalization.
+        let params = Params {
+            salt: b"salt".to_vec(),
+            personalization: b"me".to_vec(),
+            ..Params::keyed(b"key", 48)
+        };
+        let mut hasher = Blake2b::with_params(&params).unwrap();
+        hasher.update(&message.as_bytes()[..300]);
+        let mut resumed = Blake2b::from_state(hasher.state());
+        resumed.reset();
+        resumed.update(FOX);
+        let mut fresh = Blake2b::with_params(&params).unwrap();
+        fresh.update(FOX);
+        assert_eq!(resumed.finalize(), fresh.finalize());
+
+        let state = hasher.state();
+        assert!(State::<Blake2bVariant>::new(state.chaining_value, 0, Params::new(0)).is_none());
+        assert!(State::<Blake2sVariant>::new([0; 8], 0, Params::new(33)).is_none());
+        assert_eq!(
+            State::<Blake2bVariant>::new(state.chaining_value, 128, params.clone())
+                .unwrap()
+                .params(),
+            &params
+        );
+    }
+
+    #[test]
+    fn test_blake2_traced() {
+        // The key block and the fox, which fits in one BLAKE2s block.
+        let mut steps = 0;
+        let mut chaining_values = vec![];
+        let digest = Blake2s::keyed(b"key", 32)
+            .unwrap()
+            .traced(FOX, &mut |event: Event<'_, u32>| match event {
+                Event::ChainingValue { block, value } => {
+                    chaining_values.push((block, value.to_vec()))
+                }
+                Event::ExtendedWords(words) => assert_eq!(words.len(), 16),
+                Event::Registers { step, .. } => {
+                    assert_eq!(step, steps % 80);
+                    steps += 1;
+                }
+            });
+        let mut hasher = Blake2s::keyed(b"key", 32).unwrap();
+        hasher.update(FOX);
+        assert_eq!(digest, hasher.finalize());
+
+        assert_eq!(steps, 2 * 10 * 8);
+        assert_eq!(chaining_values.len(), 3);
+        assert_eq!(chaining_values[2].0, 2);
+        let output: Vec<u8> = chaining_values[2]
+            .1
+            .iter()
+            .flat_map(|word| word.to_le_bytes())
+            .collect();
+        assert_eq!(output, digest);
+
+        let mut steps = 0;
+        let digest = blake2b_traced("", 64, &mut |event: Event<'_, u64>| {
+            if let Event::Registers { .. } = event {
+                steps += 1;
+            }
+        });
+        assert_eq!(digest, blake2b("", 64));
+        assert_eq!(steps, 12 * 8);
+    }
+}
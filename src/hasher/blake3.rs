@@ -0,0 +1,874 @@
+use super::*;
+use std::io;
+use std::thread;
+
+// BLAKE3. The input is split into 1 KiB chunks, each hashed on its own with a
+// reduced-round BLAKE2s compression, and the chunk chaining values are merged
+// pairwise in a binary Merkle tree. Only the root node sets the ROOT flag, and
+// any amount of output can be read from it by bumping its counter.
+// https://github.com/BLAKE3-team/BLAKE3-specs/blob/master/blake3.pdf
+
+pub const OUT_LEN: usize = 32;
+pub const KEY_LEN: usize = 32;
+const BLOCK_LEN: usize = 64;
+const CHUNK_LEN: usize = 1024;
+
+// Domain separation flags.
+const CHUNK_START: u32 = 1 << 0;
+const CHUNK_END: u32 = 1 << 1;
+const PARENT: u32 = 1 << 2;
+const ROOT: u32 = 1 << 3;
+const KEYED_HASH: u32 = 1 << 4;
+const DERIVE_KEY_CONTEXT: u32 = 1 << 5;
+const DERIVE_KEY_MATERIAL: u32 = 1 << 6;
+
+// Same IV as SHA-256 and BLAKE2s.
+const IV: [u32; 8] = [
+    0x6A09E667, 0xBB67AE85, 0x3C6EF372, 0xA54FF53A, 0x510E527F, 0x9B05688C, 0x1F83D9AB, 0x5BE0CD19,
+];
+
+// Applied to the message words between rounds instead of BLAKE2's SIGMA table.
+const MESSAGE_PERMUTATION: [usize; 16] = [2, 6, 3, 10, 7, 0, 4, 13, 1, 11, 12, 5, 9, 14, 15, 8];
+
+fn g(state: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize, x: u32, y: u32) {
+    state[a] = state[a].wrapping_add(state[b]).wrapping_add(x);
+    state[d] = (state[d] ^ state[a]).rotate_right(16);
+    state[c] = state[c].wrapping_add(state[d]);
+    state[b] = (state[b] ^ state[c]).rotate_right(12);
+    state[a] = state[a].wrapping_add(state[b]).wrapping_add(y);
+    state[d] = (state[d] ^ state[a]).rotate_right(8);
+    state[c] = state[c].wrapping_add(state[d]);
+    state[b] = (state[b] ^ state[c]).rotate_right(7);
+}
+
+// Names of the state words in traces.
+const STATE_NAMES: [&str; 16] = [
+    "v0", "v1", "v2", "v3", "v4", "v5", "v6", "v7", "v8", "v9", "v10", "v11", "v12", "v13", "v14",
+    "v15",
+];
+
+// Columns, then diagonals.
+const G_INDICES: [[usize; 4]; 8] = [
+    [0, 4, 8, 12],
+    [1, 5, 9, 13],
+    [2, 6, 10, 14],
+    [3, 7, 11, 15],
+    [0, 5, 10, 15],
+    [1, 6, 11, 12],
+    [2, 7, 8, 13],
+    [3, 4, 9, 14],
+];
+
+// A trace along with the number of compressions reported to it so far, which
+// numbers the blocks. Chunks, parents and output blocks are all counted, in
+// the order they are compressed.
+struct Compressions<'a, T>
+where
+    T: Trace<u32> + ?Sized,
+{
+    trace: &'a mut T,
+    count: usize,
+}
+
+impl<'a, T> Compressions<'a, T>
+where
+    T: Trace<u32> + ?Sized,
+{
+    fn new(trace: &'a mut T) -> Self {
+        Compressions { trace, count: 0 }
+    }
+}
+
+pub fn compress(
+    chaining_value: &[u32; 8],
+    block_words: &[u32; 16],
+    counter: u64,
+    block_len: u32,
+    flags: u32,
+) -> [u32; 16] {
+    compress_with(
+        chaining_value,
+        block_words,
+        counter,
+        block_len,
+        flags,
+        &mut Compressions::new(&mut NoTrace),
+    )
+}
+
+fn compress_with<T>(
+    chaining_value: &[u32; 8],
+    block_words: &[u32; 16],
+    counter: u64,
+    block_len: u32,
+    flags: u32,
+    compressions: &mut Compressions<'_, T>,
+) -> [u32; 16]
+where
+    T: Trace<u32> + ?Sized,
+{
+    compressions.trace.record(Event::ChainingValue {
+        block: compressions.count,
+        value: chaining_value,
+    });
+    compressions.trace.record(Event::ExtendedWords(block_words));
+    compressions.count += 1;
+
+    let mut state = [
+        chaining_value[0],
+        chaining_value[1],
+        chaining_value[2],
+        chaining_value[3],
+        chaining_value[4],
+        chaining_value[5],
+        chaining_value[6],
+        chaining_value[7],
+        IV[0],
+        IV[1],
+        IV[2],
+        IV[3],
+        counter as u32,
+        (counter >> 32) as u32,
+        block_len,
+        flags,
+    ];
+
+    let mut m = *block_words;
+    for round in 0..7 {
+        for (i, [a, b, c, d]) in G_INDICES.into_iter().enumerate() {
+            g(&mut state, a, b, c, d, m[2 * i], m[2 * i + 1]);
+            compressions.trace.record(Event::Registers {
+                step: 8 * round + i,
+                names: &STATE_NAMES,
+                values: &state,
+            });
+        }
+        if round < 6 {
+            m = MESSAGE_PERMUTATION.map(|j| m[j]);
+        }
+    }
+
+    for i in 0..8 {
+        state[i] ^= state[i + 8];
+        state[i + 8] ^= chaining_value[i];
+    }
+    state
+}
+
+fn words_from_le_bytes(bytes: &[u8], words: &mut [u32]) {
+    for (word, chunk) in words.iter_mut().zip(bytes.chunks_exact(4)) {
+        *word = u32::from_le_bytes(chunk.try_into().unwrap());
+    }
+}
+
+fn first_8_words(words: [u32; 16]) -> [u32; 8] {
+    words[..8].try_into().unwrap()
+}
+
+// A node whose compression hasn't run yet. Depending on whether it turns out
+// to be the root it either yields a chaining value or the final output.
+#[derive(Clone)]
+struct Output {
+    input_chaining_value: [u32; 8],
+    block_words: [u32; 16],
+    counter: u64,
+    block_len: u32,
+    flags: u32,
+}
+
+impl Output {
+    fn chaining_value(&self) -> [u32; 8] {
+        self.chaining_value_with(&mut Compressions::new(&mut NoTrace))
+    }
+
+    fn chaining_value_with<T>(&self, compressions: &mut Compressions<'_, T>) -> [u32; 8]
+    where
+        T: Trace<u32> + ?Sized,
+    {
+        first_8_words(compress_with(
+            &self.input_chaining_value,
+            &self.block_words,
+            self.counter,
+            self.block_len,
+            self.flags,
+            compressions,
+        ))
+    }
+
+    fn root_output_words<T>(
+        &self,
+        counter: u64,
+        compressions: &mut Compressions<'_, T>,
+    ) -> [u32; 16]
+    where
+        T: Trace<u32> + ?Sized,
+    {
+        compress_with(
+            &self.input_chaining_value,
+            &self.block_words,
+            counter,
+            self.block_len,
+            self.flags | ROOT,
+            compressions,
+        )
+    }
+
+    // Root output block number `counter`, 64 bytes each.
+    fn root_output_block(&self, counter: u64) -> [u8; BLOCK_LEN] {
+        let words = self.root_output_words(counter, &mut Compressions::new(&mut NoTrace));
+        let mut bytes = [0; BLOCK_LEN];
+        for (chunk, word) in bytes.chunks_exact_mut(4).zip(words) {
+            chunk.copy_from_slice(&word.to_le_bytes());
+        }
+        bytes
+    }
+}
+
+fn parent_output(left: &[u32; 8], right: &[u32; 8], key: &[u32; 8], flags: u32) -> Output {
+    let mut block_words = [0; 16];
+    block_words[..8].copy_from_slice(left);
+    block_words[8..].copy_from_slice(right);
+    Output {
+        input_chaining_value: *key,
+        block_words,
+        counter: 0,
+        block_len: BLOCK_LEN as u32,
+        flags: PARENT | flags,
+    }
+}
+
+// Hashes the blocks of a single chunk.
+#[derive(Clone)]
+struct ChunkState {
+    chaining_value: [u32; 8],
+    chunk_counter: u64,
+    block: [u8; BLOCK_LEN],
+    block_len: usize,
+    blocks_compressed: usize,
+    flags: u32,
+}
+
+impl ChunkState {
+    fn new(key: &[u32; 8], chunk_counter: u64, flags: u32) -> Self {
+        ChunkState {
+            chaining_value: *key,
+            chunk_counter,
+            block: [0; BLOCK_LEN],
+            block_len: 0,
+            blocks_compressed: 0,
+            flags,
+        }
+    }
+
+    fn len(&self) -> usize {
+        BLOCK_LEN * self.blocks_compressed + self.block_len
+    }
+
+    fn start_flag(&self) -> u32 {
+        if self.blocks_compressed == 0 {
+            CHUNK_START
+        } else {
+            0
+        }
+    }
+
+    fn update(&mut self, input: &[u8]) {
+        self.update_with(input, &mut Compressions::new(&mut NoTrace));
+    }
+
+    fn update_with<T>(&mut self, mut input: &[u8], compressions: &mut Compressions<'_, T>)
+    where
+        T: Trace<u32> + ?Sized,
+    {
+        while !input.is_empty() {
+            // A full block is only compressed once more input arrives, as the
+            // last block of the chunk needs the CHUNK_END flag.
+            if self.block_len == BLOCK_LEN {
+                let mut block_words = [0; 16];
+                words_from_le_bytes(&self.block, &mut block_words);
+                self.chaining_value = first_8_words(compress_with(
+                    &self.chaining_value,
+                    &block_words,
+                    self.chunk_counter,
+                    BLOCK_LEN as u32,
+                    self.flags | self.start_flag(),
+                    compressions,
+                ));
+                self.blocks_compressed += 1;
+                self.block = [0; BLOCK_LEN];
+                self.block_len = 0;
+            }
+
+            let taken = (BLOCK_LEN - self.block_len).min(input.len());
+            self.block[self.block_len..self.block_len + taken].copy_from_slice(&input[..taken]);
+            self.block_len += taken;
+            input = &input[taken..];
+        }
+    }
+
+    fn output(&self) -> Output {
+        let mut block_words = [0; 16];
+        words_from_le_bytes(&self.block, &mut block_words);
+        Output {
+            input_chaining_value: self.chaining_value,
+            block_words,
+            counter: self.chunk_counter,
+            block_len: self.block_len as u32,
+            flags: self.flags | self.start_flag() | CHUNK_END,
+        }
+    }
+}
+
+// Incremental hasher. Completed chunks leave their chaining value on a stack,
+// which is merged whenever a subtree fills up, so memory stays logarithmic in
+// the input length.
+#[derive(Clone)]
+pub struct Blake3 {
+    chunk_state: ChunkState,
+    key: [u32; 8],
+    chaining_value_stack: Vec<[u32; 8]>,
+    flags: u32,
+}
+
+impl Blake3 {
+    fn with_key_and_flags(key: [u32; 8], flags: u32) -> Self {
+        Blake3 {
+            chunk_state: ChunkState::new(&key, 0, flags),
+            key,
+            chaining_value_stack: vec![],
+            flags,
+        }
+    }
+
+    pub fn new_keyed(key: &[u8; KEY_LEN]) -> Self {
+        let mut key_words = [0; 8];
+        words_from_le_bytes(key, &mut key_words);
+        Blake3::with_key_and_flags(key_words, KEYED_HASH)
+    }
+
+    // Key derivation: the context string, which should be hardcoded and
+    // globally unique, is hashed into the key used for the key material.
+    pub fn new_derive_key(context: &str) -> Self {
+        let mut context_hasher = Blake3::with_key_and_flags(IV, DERIVE_KEY_CONTEXT);
+        context_hasher.update(context);
+        let mut context_key = [0; KEY_LEN];
+        context_hasher.finalize_xof().fill(&mut context_key);
+
+        let mut key_words = [0; 8];
+        words_from_le_bytes(&context_key, &mut key_words);
+        Blake3::with_key_and_flags(key_words, DERIVE_KEY_MATERIAL)
+    }
+
+    fn push_chunk_chaining_value<T>(
+        &mut self,
+        mut chaining_value: [u32; 8],
+        total_chunks: u64,
+        compressions: &mut Compressions<'_, T>,
+    ) where
+        T: Trace<u32> + ?Sized,
+    {
+        // Every trailing zero bit in the chunk count marks a completed
+        // subtree whose two halves can be merged.
+        let mut total_chunks = total_chunks;
+        while total_chunks & 1 == 0 {
+            let left = self.chaining_value_stack.pop().unwrap();
+            chaining_value = parent_output(&left, &chaining_value, &self.key, self.flags)
+                .chaining_value_with(compressions);
+            total_chunks >>= 1;
+        }
+        self.chaining_value_stack.push(chaining_value);
+    }
+
+    fn root_output(&self) -> Output {
+        self.root_output_with(&mut Compressions::new(&mut NoTrace))
+    }
+
+    fn root_output_with<T>(&self, compressions: &mut Compressions<'_, T>) -> Output
+    where
+        T: Trace<u32> + ?Sized,
+    {
+        // Merge the stack right to left, the last merge being the root.
+        let mut output = self.chunk_state.output();
+        for left in self.chaining_value_stack.iter().rev() {
+            output = parent_output(
+                left,
+                &output.chaining_value_with(compressions),
+                &self.key,
+                self.flags,
+            );
+        }
+        output
+    }
+
+    fn update_with<T>(&mut self, mut input: &[u8], compressions: &mut Compressions<'_, T>)
+    where
+        T: Trace<u32> + ?Sized,
+    {
+        while !input.is_empty() {
+            // Like blocks within a chunk, a full chunk is only finished once
+            // more input shows it isn't the root.
+            if self.chunk_state.len() == CHUNK_LEN {
+                let chaining_value = self.chunk_state.output().chaining_value_with(compressions);
+                let total_chunks = self.chunk_state.chunk_counter + 1;
+                self.push_chunk_chaining_value(chaining_value, total_chunks, compressions);
+                self.chunk_state = ChunkState::new(&self.key, total_chunks, self.flags);
+            }
+
+            let taken = (CHUNK_LEN - self.chunk_state.len()).min(input.len());
+            self.chunk_state.update_with(&input[..taken], compressions);
+            input = &input[taken..];
+        }
+    }
+
+    // Hashes `message` and finalizes, reporting every compression to
+    // `trace` in the order it runs: chunk blocks, parents and finally the
+    // root. The last chaining value reported is the digest.
+    pub fn traced<T>(mut self, message: T, trace: &mut dyn Trace<u32>) -> [u8; OUT_LEN]
+    where
+        T: AsBytes,
+    {
+        let mut compressions = Compressions::new(trace);
+        self.update_with(message.as_bytes(), &mut compressions);
+        let words = self
+            .root_output_with(&mut compressions)
+            .root_output_words(0, &mut compressions);
+        let digest = first_8_words(words);
+        compressions.trace.record(Event::ChainingValue {
+            block: compressions.count,
+            value: &digest,
+        });
+
+        let mut bytes = [0; OUT_LEN];
+        for (chunk, word) in bytes.chunks_exact_mut(4).zip(digest) {
+            chunk.copy_from_slice(&word.to_le_bytes());
+        }
+        bytes
+    }
+
+    // Extendable output, readable to any length.
+    pub fn finalize_xof(&self) -> OutputReader {
+        OutputReader {
+            output: self.root_output(),
+            position: 0,
+        }
+    }
+}
+
+// The chaining value stack is part of the state, so resuming takes a
+// snapshot of the whole hasher.
+impl Hasher for Blake3 {
+    const BLOCK_SIZE: usize = BLOCK_LEN;
+
+    type State = Blake3;
+    type Digest = [u8; OUT_LEN];
+
+    fn new() -> Self {
+        Blake3::with_key_and_flags(IV, 0)
+    }
+
+    fn from_state(state: Self::State) -> Self {
+        state
+    }
+
+    fn update<T>(&mut self, message: T)
+    where
+        T: AsBytes,
+    {
+        self.update_with(message.as_bytes(), &mut Compressions::new(&mut NoTrace));
+    }
+
+    fn finalize(self) -> Self::Digest {
+        let mut digest = [0; OUT_LEN];
+        self.finalize_xof().fill(&mut digest);
+        digest
+    }
+
+    fn reset(&mut self) {
+        *self = Blake3::with_key_and_flags(self.key, self.flags);
+    }
+}
+
+// Reads the root node's output stream from any position.
+#[derive(Clone)]
+pub struct OutputReader {
+    output: Output,
+    position: u64,
+}
+
+impl OutputReader {
+    pub fn fill(&mut self, buffer: &mut [u8]) {
+        let mut written = 0;
+        while written < buffer.len() {
+            let block = self
+                .output
+                .root_output_block(self.position / BLOCK_LEN as u64);
+            let offset = (self.position % BLOCK_LEN as u64) as usize;
+            let taken = (BLOCK_LEN - offset).min(buffer.len() - written);
+            buffer[written..written + taken].copy_from_slice(&block[offset..offset + taken]);
+            written += taken;
+            self.position += taken as u64;
+        }
+    }
+
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    pub fn set_position(&mut self, position: u64) {
+        self.position = position;
+    }
+}
+
+impl io::Read for OutputReader {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        self.fill(buffer);
+        Ok(buffer.len())
+    }
+}
+
+// Hashes the subtree covering `input`, which starts at chunk `chunk_counter`.
+// The left subtree always holds the largest power of two number of chunks
+// that leaves something for the right one, which gives the same tree as the
+// incremental hasher. Subtrees are handed to new threads while `threads`
+// allows it.
+fn subtree_output(
+    input: &[u8],
+    chunk_counter: u64,
+    key: &[u32; 8],
+    flags: u32,
+    threads: usize,
+) -> Output {
+    if input.len() <= CHUNK_LEN {
+        let mut chunk_state = ChunkState::new(key, chunk_counter, flags);
+        chunk_state.update(input);
+        return chunk_state.output();
+    }
+
+    let chunks = input.len().div_ceil(CHUNK_LEN);
+    let left_chunks = 1 << (usize::BITS - 1 - (chunks - 1).leading_zeros());
+    let (left, right) = input.split_at(left_chunks * CHUNK_LEN);
+    let right_counter = chunk_counter + left_chunks as u64;
+
+    let (left_chaining_value, right_chaining_value) = if threads > 1 {
+        thread::scope(|scope| {
+            let left_thread = scope.spawn(|| {
+                subtree_output(left, chunk_counter, key, flags, threads / 2).chaining_value()
+            });
+            let right_chaining_value =
+                subtree_output(right, right_counter, key, flags, threads - threads / 2)
+                    .chaining_value();
+            (left_thread.join().unwrap(), right_chaining_value)
+        })
+    } else {
+        (
+            subtree_output(left, chunk_counter, key, flags, 1).chaining_value(),
+            subtree_output(right, right_counter, key, flags, 1).chaining_value(),
+        )
+    };
+    parent_output(&left_chaining_value, &right_chaining_value, key, flags)
+}
+
+pub fn hash<T>(message: T) -> [u8; OUT_LEN]
+where
+    T: AsBytes,
+{
+    let mut hasher = Blake3::new();
+    hasher.update(message);
+    hasher.finalize()
+}
+
+pub fn keyed_hash<T>(key: &[u8; KEY_LEN], message: T) -> [u8; OUT_LEN]
+where
+    T: AsBytes,
+{
+    let mut hasher = Blake3::new_keyed(key);
+    hasher.update(message);
+    hasher.finalize()
+}
+
+pub fn derive_key<T>(context: &str, key_material: T) -> [u8; KEY_LEN]
+where
+    T: AsBytes,
+{
+    let mut hasher = Blake3::new_derive_key(context);
+    hasher.update(key_material);
+    hasher.finalize()
+}
+
+// Same digest as `hash`, with subtrees spread over up to `threads` threads.
+pub fn hash_parallel(message: &[u8], threads: usize) -> [u8; OUT_LEN] {
+    let output = subtree_output(message, 0, &IV, 0, threads.max(1));
+    let mut digest = [0; OUT_LEN];
+    OutputReader {
+        output,
+        position: 0,
+    }
+    .fill(&mut digest);
+    digest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    // Inputs from the official test vectors repeat 0, 1, .., 250.
+    fn test_input(length: usize) -> Vec<u8> {
+        (0..length).map(|i| (i % 251) as u8).collect()
+    }
+
+    // Lengths around the chunk and tree boundaries.
+    const TEST_LENGTHS: [usize; 14] = [
+        0, 1, 63, 64, 65, 1023, 1024, 1025, 2048, 2049, 3072, 3073, 8193, 31744,
+    ];
+
+    #[test]
+    fn test_blake3() {
+        let expected_empty: [u8; 32] = [
+            0xAF, 0x13, 0x49, 0xB9, 0xF5, 0xF9, 0xA1, 0xA6, 0xA0, 0x40, 0x4D, 0xEA, 0x36, 0xDC,
+            0xC9, 0x49, 0x9B, 0xCB, 0x25, 0xC9, 0xAD, 0xC1, 0x12, 0xB7, 0xCC, 0x9A, 0x93, 0xCA,
+            0xE4, 0x1F, 0x32, 0x62,
+        ];
+        let expected_abc: [u8; 32] = [
+            0x64, 0x37, 0xB3, 0xAC, 0x38, 0x46, 0x51, 0x33, 0xFF, 0xB6, 0x3B, 0x75, 0x27, 0x3A,
+            0x8D, 0xB5, 0x48, 0xC5, 0x58, 0x46, 0x5D, 0x79, 0xDB, 0x03, 0xFD, 0x35, 0x9C, 0x6C,
+            0xD5, 0xBD, 0x9D, 0x85,
+        ];
+
+        let expected_one_byte: [u8; 32] = [
+            0x2D, 0x3A, 0xDE, 0xDF, 0xF1, 0x1B, 0x61, 0xF1, 0x4C, 0x88, 0x6E, 0x35, 0xAF, 0xA0,
+            0x36, 0x73, 0x6D, 0xCD, 0x87, 0xA7, 0x4D, 0x27, 0xB5, 0xC1, 0x51, 0x02, 0x25, 0xD0,
+            0xF5, 0x92, 0xE2, 0x13,
+        ];
+
+        assert_eq!(hash(""), expected_empty);
+        assert_eq!(hash("abc"), expected_abc);
+        assert_eq!(hash(test_input(1).as_slice()), expected_one_byte);
+    }
+
+    #[test]
+    fn test_blake3_tree() {
+        // Official test vectors across the chunk and parent node boundaries.
+        let expected: [(usize, [u8; 32]); 12] = [
+            (
+                63,
+                [
+                    0xE9, 0xBC, 0x37, 0xA5, 0x94, 0xDA, 0xAD, 0x83, 0xBE, 0x94, 0x70, 0xDF, 0x7F,
+                    0x7B, 0x37, 0x98, 0x29, 0x7C, 0x3D, 0x83, 0x4C, 0xE8, 0x0B, 0xA8, 0x5D, 0x6E,
+                    0x20, 0x76, 0x27, 0xB7, 0xDB, 0x7B,
+                ],
+            ),
+            (
+                64,
+                [
+                    0x4E, 0xED, 0x71, 0x41, 0xEA, 0x4A, 0x5C, 0xD4, 0xB7, 0x88, 0x60, 0x6B, 0xD2,
+                    0x3F, 0x46, 0xE2, 0x12, 0xAF, 0x9C, 0xAC, 0xEB, 0xAC, 0xDC, 0x7D, 0x1F, 0x4C,
+                    0x6D, 0xC7, 0xF2, 0x51, 0x1B, 0x98,
+                ],
+            ),
+            (
+                65,
+                [
+                    0xDE, 0x1E, 0x5F, 0xA0, 0xBE, 0x70, 0xDF, 0x6D, 0x2B, 0xE8, 0xFF, 0xFD, 0x0E,
+                    0x99, 0xCE, 0xAA, 0x8E, 0xB6, 0xE8, 0xC9, 0x3A, 0x63, 0xF2, 0xD8, 0xD1, 0xC3,
+                    0x0E, 0xCB, 0x6B, 0x26, 0x3D, 0xEE,
+                ],
+            ),
+            (
+                1023,
+                [
+                    0x10, 0x10, 0x89, 0x70, 0xEE, 0xDA, 0x3E, 0xB9, 0x32, 0xBA, 0xAC, 0x14, 0x28,
+                    0xC7, 0xA2, 0x16, 0x3B, 0x0E, 0x92, 0x4C, 0x9A, 0x9E, 0x25, 0xB3, 0x5B, 0xBA,
+                    0x72, 0xB2, 0x8F, 0x70, 0xBD, 0x11,
+                ],
+            ),
+            (
+                1024,
+                [
+                    0x42, 0x21, 0x47, 0x39, 0xF0, 0x95, 0xA4, 0x06, 0xF3, 0xFC, 0x83, 0xDE, 0xB8,
+                    0x89, 0x74, 0x4A, 0xC0, 0x0D, 0xF8, 0x31, 0xC1, 0x0D, 0xAA, 0x55, 0x18, 0x9B,
+                    0x5D, 0x12, 0x1C, 0x85, 0x5A, 0xF7,
+                ],
+            ),
+            (
+                1025,
+                [
+                    0xD0, 0x02, 0x78, 0xAE, 0x47, 0xEB, 0x27, 0xB3, 0x4F, 0xAE, 0xCF, 0x67, 0xB4,
+                    0xFE, 0x26, 0x3F, 0x82, 0xD5, 0x41, 0x29, 0x16, 0xC1, 0xFF, 0xD9, 0x7C, 0x8C,
+                    0xB7, 0xFB, 0x81, 0x4B, 0x84, 0x44,
+                ],
+            ),
+            (
+                2048,
+                [
+                    0xE7, 0x76, 0xB6, 0x02, 0x8C, 0x7C, 0xD2, 0x2A, 0x4D, 0x0B, 0xA1, 0x82, 0xA8,
+                    0xBF, 0x62, 0x20, 0x5D, 0x2E, 0xF5, 0x76, 0x46, 0x7E, 0x83, 0x8E, 0xD6, 0xF2,
+                    0x52, 0x9B, 0x85, 0xFB, 0xA2, 0x4A,
+                ],
+            ),
+            (
+                2049,
+                [
+                    0x5F, 0x4D, 0x72, 0xF4, 0x0D, 0x7A, 0x5F, 0x82, 0xB1, 0x5C, 0xA2, 0xB2, 0xE4,
+                    0x4B, 0x1D, 0xE3, 0xC2, 0xEF, 0x86, 0xC4, 0x26, 0xC9, 0x5C, 0x1A, 0xF0, 0xB6,
+                    0x87, 0x95, 0x22, 0x56, 0x30, 0x30,
+                ],
+            ),
+            (
+                3072,
+                [
+                    0xB9, 0x8C, 0xB0, 0xFF, 0x36, 0x23, 0xBE, 0x03, 0x32, 0x6B, 0x37, 0x3D, 0xE6,
+                    0xB9, 0x09, 0x52, 0x18, 0x51, 0x3E, 0x64, 0xF1, 0xEE, 0x2E, 0xDD, 0x25, 0x25,
+                    0xC7, 0xAD, 0x1E, 0x5C, 0xFF, 0xD2,
+                ],
+            ),
+            (
+                3073,
+                [
+                    0x71, 0x24, 0xB4, 0x95, 0x01, 0x01, 0x2F, 0x81, 0xCC, 0x7F, 0x11, 0xCA, 0x06,
+                    0x9E, 0xC9, 0x22, 0x6C, 0xEC, 0xB8, 0xA2, 0xC8, 0x50, 0xCF, 0xE6, 0x44, 0xE3,
+                    0x27, 0xD2, 0x2D, 0x3E, 0x1C, 0xD3,
+                ],
+            ),
+            (
+                8193,
+                [
+                    0xBA, 0xB6, 0xC0, 0x9C, 0xB8, 0xCE, 0x8C, 0xF4, 0x59, 0x26, 0x13, 0x98, 0xD2,
+                    0xE7, 0xAE, 0xF3, 0x57, 0x00, 0xBF, 0x48, 0x81, 0x16, 0xCE, 0xB9, 0x4A, 0x36,
+                    0xD0, 0xF5, 0xF1, 0xB7, 0xBC, 0x3B,
+                ],
+            ),
+            (
+                31744,
+                [
+                    0x62, 0xB6, 0x96, 0x0E, 0x1A, 0x44, 0xBC, 0xC1, 0xEB, 0x1A, 0x61, 0x1A, 0x8D,
+                    0x62, 0x35, 0xB6, 0xB4, 0xB7, 0x8F, 0x32, 0xE7, 0xAB, 0xC4, 0xFB, 0x4C, 0x6C,
+                    0xDC, 0xCE, 0x94, 0x89, 0x5C, 0x47,
+                ],
+            ),
+        ];
+        for (length, digest) in expected {
+            assert_eq!(
+                hash(test_input(length).as_slice()),
+                digest,
+                "length {}",
+                length
+            );
+        }
+    }
+
+    #[test]
+    fn test_blake3_streaming() {
+        let input = test_input(31744);
+        for piece_size in [1, 63, 64, 65, 1023, 1024, 1025, 5000] {
+            let mut hasher = Blake3::new();
+            for piece in input.chunks(piece_size) {
+                hasher.update(piece);
+            }
+            assert_eq!(hasher.finalize(), hash(input.as_slice()));
+        }
+    }
+
+    #[test]
+    fn test_blake3_parallel() {
+        // The recursive tree and the chaining value stack build the same tree.
+        for length in TEST_LENGTHS {
+            let input = test_input(length);
+            for threads in [1, 2, 3, 8] {
+                assert_eq!(hash_parallel(&input, threads), hash(input.as_slice()));
+            }
+        }
+    }
+
+    #[test]
+    fn test_blake3_xof() {
+        let mut hasher = Blake3::new();
+        hasher.update("abc");
+
+        // The default output is a prefix of the extended output.
+        let mut long_output = [0; 200];
+        hasher.finalize_xof().fill(&mut long_output);
+        assert_eq!(long_output[..32], hash("abc"));
+
+        // Reads can resume anywhere.
+        let mut reader = hasher.finalize_xof();
+        reader.set_position(100);
+        let mut tail = [0; 100];
+        reader.read_exact(&mut tail).unwrap();
+        assert_eq!(tail, long_output[100..]);
+        assert_eq!(reader.position(), 200);
+    }
+
+    #[test]
+    fn test_blake3_modes() {
+        let key = *b"whats the Elvish word for friend";
+        let context = "BLAKE3 2019-12-27 16:29:52 test vectors context";
+
+        // Empty input entries of the official test vectors.
+        let expected_keyed_empty: [u8; 32] = [
+            0x92, 0xB2, 0xB7, 0x56, 0x04, 0xED, 0x3C, 0x76, 0x1F, 0x9D, 0x6F, 0x62, 0x39, 0x2C,
+            0x8A, 0x92, 0x27, 0xAD, 0x0E, 0xA3, 0xF0, 0x95, 0x73, 0xE7, 0x83, 0xF1, 0x49, 0x8A,
+            0x4E, 0xD6, 0x0D, 0x26,
+        ];
+        let expected_derived_empty: [u8; 32] = [
+            0x2C, 0xC3, 0x97, 0x83, 0xC2, 0x23, 0x15, 0x4F, 0xEA, 0x8D, 0xFB, 0x7C, 0x1B, 0x16,
+            0x60, 0xF2, 0xAC, 0x2D, 0xCB, 0xD1, 0xC1, 0xDE, 0x82, 0x77, 0xB0, 0xB0, 0xDD, 0x39,
+            0xB7, 0xE5, 0x0D, 0x7D,
+        ];
+        assert_eq!(keyed_hash(&key, ""), expected_keyed_empty);
+        assert_eq!(derive_key(context, ""), expected_derived_empty);
+
+        // The three modes are domain separated from each other.
+        let plain = hash("abc");
+        let keyed = keyed_hash(&key, "abc");
+        let derived = derive_key(context, "abc");
+        assert_ne!(plain, keyed);
+        assert_ne!(plain, derived);
+        assert_ne!(keyed, derived);
+
+        let mut hasher = Blake3::new_keyed(&key);
+        hasher.update("garbage");
+        hasher.reset();
+        hasher.update("abc");
+        assert_eq!(hasher.finalize(), keyed);
+
+        let mut hasher = Blake3::new_derive_key(context);
+        hasher.update("ab");
+        let snapshot = Blake3::from_state(hasher.clone());
+        hasher.update("c");
+        assert_eq!(hasher.finalize(), derived);
+        assert_ne!(snapshot.finalize(), derived);
+    }
+
+    #[test]
+    fn test_blake3_traced() {
+        // Two full chunks of 16 blocks and their parent, the one block of the
+        // third chunk and the root merging it in.
+        let input = test_input(2049);
+        let mut steps = 0;
+        let mut chaining_values = vec![];
+        let digest =
+            Blake3::new().traced(input.as_slice(), &mut |event: Event<'_, u32>| match event {
+                Event::ChainingValue { block, value } => {
+                    chaining_values.push((block, value.to_vec()))
+                }
+                Event::ExtendedWords(words) => assert_eq!(words.len(), 16),
+                Event::Registers { step, .. } => {
+                    assert_eq!(step, steps % 56);
+                    steps += 1;
+                }
+            });
+        assert_eq!(digest, hash(input.as_slice()));
+
+        assert_eq!(steps, 35 * 7 * 8);
+        assert_eq!(chaining_values.len(), 36);
+        assert_eq!(chaining_values[0], (0, IV.to_vec()));
+        assert_eq!(chaining_values[35].0, 35);
+        let output: Vec<u8> = chaining_values[35]
+            .1
+            .iter()
+            .flat_map(|word| word.to_le_bytes())
+            .collect();
+        assert_eq!(output, digest);
+    }
+}
@@ -0,0 +1,123 @@
+use std::hash::Hasher as StdHasher;
+
+// FNV-1a: xor in a byte, multiply by a prime. Fast and well distributed, but
+// unkeyed and trivially invertible, so colliding keys can be precomputed
+// offline, see attacks::hash_flooding.
+// http://www.isthe.com/chongo/tech/comp/fnv/index.html
+
+pub const OFFSET_BASIS_32: u32 = 0x811C9DC5;
+pub const PRIME_32: u32 = 0x01000193;
+
+pub const OFFSET_BASIS_64: u64 = 0xCBF29CE484222325;
+pub const PRIME_64: u64 = 0x00000100000001B3;
+
+// The state is the whole hash, so hashing can resume from any output.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Fnv1a32 {
+    state: u32,
+}
+
+impl Fnv1a32 {
+    pub fn from_state(state: u32) -> Self {
+        Fnv1a32 { state }
+    }
+
+    pub fn state(&self) -> u32 {
+        self.state
+    }
+
+    pub fn update(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.state = (self.state ^ *byte as u32).wrapping_mul(PRIME_32);
+        }
+    }
+}
+
+impl Default for Fnv1a32 {
+    fn default() -> Self {
+        Fnv1a32::from_state(OFFSET_BASIS_32)
+    }
+}
+
+impl StdHasher for Fnv1a32 {
+    fn write(&mut self, bytes: &[u8]) {
+        self.update(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        self.state as u64
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Fnv1a64 {
+    state: u64,
+}
+
+impl Fnv1a64 {
+    pub fn from_state(state: u64) -> Self {
+        Fnv1a64 { state }
+    }
+
+    pub fn state(&self) -> u64 {
+        self.state
+    }
+
+    pub fn update(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.state = (self.state ^ *byte as u64).wrapping_mul(PRIME_64);
+        }
+    }
+}
+
+impl Default for Fnv1a64 {
+    fn default() -> Self {
+        Fnv1a64::from_state(OFFSET_BASIS_64)
+    }
+}
+
+impl StdHasher for Fnv1a64 {
+    fn write(&mut self, bytes: &[u8]) {
+        self.update(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        self.state
+    }
+}
+
+pub fn fnv1a_32(message: &[u8]) -> u32 {
+    let mut hasher = Fnv1a32::default();
+    hasher.update(message);
+    hasher.state()
+}
+
+pub fn fnv1a_64(message: &[u8]) -> u64 {
+    let mut hasher = Fnv1a64::default();
+    hasher.update(message);
+    hasher.state()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Test vectors from the FNV reference code.
+    #[test]
+    fn test_fnv1a() {
+        assert_eq!(fnv1a_32(b""), 0x811C9DC5);
+        assert_eq!(fnv1a_32(b"a"), 0xE40C292C);
+        assert_eq!(fnv1a_32(b"foobar"), 0xBF9CF968);
+
+        assert_eq!(fnv1a_64(b""), 0xCBF29CE484222325);
+        assert_eq!(fnv1a_64(b"a"), 0xAF63DC4C8601EC8C);
+        assert_eq!(fnv1a_64(b"foobar"), 0x85944171F73967E8);
+    }
+
+    #[test]
+    fn test_fnv1a_resume() {
+        let mut hasher = Fnv1a64::from_state(fnv1a_64(b"foo"));
+        hasher.write(b"bar");
+        assert_eq!(hasher.finish(), fnv1a_64(b"foobar"));
+    }
+}
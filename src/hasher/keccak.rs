@@ -0,0 +1,469 @@
+use super::*;
+
+// Width of the Keccak-f[1600] state in bytes.
+pub const STATE_SIZE: usize = 200;
+
+// Domain separation bytes. They hold the suffix bits appended to the message
+// followed by the first bit of the pad10*1 padding.
+pub const DOMAIN_KECCAK: u8 = 0x01;
+pub const DOMAIN_SHA3: u8 = 0x06;
+pub const DOMAIN_SHAKE: u8 = 0x1F;
+pub const DOMAIN_CSHAKE: u8 = 0x04;
+
+// Iota step constants.
+const ROUND_CONSTANTS: [u64; 24] = [
+    0x0000000000000001,
+    0x0000000000008082,
+    0x800000000000808A,
+    0x8000000080008000,
+    0x000000000000808B,
+    0x0000000080000001,
+    0x8000000080008081,
+    0x8000000000008009,
+    0x000000000000008A,
+    0x0000000000000088,
+    0x0000000080008009,
+    0x000000008000000A,
+    0x000000008000808B,
+    0x800000000000008B,
+    0x8000000000008089,
+    0x8000000000008003,
+    0x8000000000008002,
+    0x8000000000000080,
+    0x000000000000800A,
+    0x800000008000000A,
+    0x8000000080008081,
+    0x8000000000008080,
+    0x0000000080000001,
+    0x8000000080008008,
+];
+
+// Rho step rotation offsets, indexed by x + 5 * y.
+const ROTATION_OFFSETS: [u32; 25] = [
+    0, 1, 62, 28, 27, //
+    36, 44, 6, 55, 20, //
+    3, 10, 43, 25, 39, //
+    41, 45, 15, 21, 8, //
+    18, 2, 61, 56, 14,
+];
+
+// Lane names for traces, by x and y.
+const LANE_NAMES: [&str; 25] = [
+    "a0,0", "a1,0", "a2,0", "a3,0", "a4,0", //
+    "a0,1", "a1,1", "a2,1", "a3,1", "a4,1", //
+    "a0,2", "a1,2", "a2,2", "a3,2", "a4,2", //
+    "a0,3", "a1,3", "a2,3", "a3,3", "a4,3", //
+    "a0,4", "a1,4", "a2,4", "a3,4", "a4,4",
+];
+
+// The Keccak-f[1600] permutation. Lanes are indexed by x + 5 * y.
+// https://keccak.team/keccak_specs_summary.html
+pub fn keccak_f1600(state: &mut [u64; 25]) {
+    permute(state, &mut NoTrace);
+}
+
+// `keccak_f1600`, reporting the lanes after every round to `trace`.
+pub fn keccak_f1600_traced(state: &mut [u64; 25], trace: &mut dyn Trace<u64>) {
+    permute(state, trace);
+}
+
+fn permute<T>(state: &mut [u64; 25], trace: &mut T)
+where
+    T: Trace<u64> + ?Sized,
+{
+    for (round, round_constant) in ROUND_CONSTANTS.into_iter().enumerate() {
+        // Theta.
+        let mut c: [u64; 5] = [0; 5];
+        for x in 0..5 {
+            c[x] = state[x] ^ state[x + 5] ^ state[x + 10] ^ state[x + 15] ^ state[x + 20];
+        }
+        for x in 0..5 {
+            let d = c[(x + 4) % 5] ^ c[(x + 1) % 5].rotate_left(1);
+            for y in 0..5 {
+                state[x + 5 * y] ^= d;
+            }
+        }
+
+        // Rho and pi.
+        let mut b: [u64; 25] = [0; 25];
+        for x in 0..5 {
+            for y in 0..5 {
+                b[y + 5 * ((2 * x + 3 * y) % 5)] =
+                    state[x + 5 * y].rotate_left(ROTATION_OFFSETS[x + 5 * y]);
+            }
+        }
+
+        // Chi.
+        for x in 0..5 {
+            for y in 0..5 {
+                state[x + 5 * y] =
+                    b[x + 5 * y] ^ ((!b[(x + 1) % 5 + 5 * y]) & b[(x + 2) % 5 + 5 * y]);
+            }
+        }
+
+        // Iota.
+        state[0] ^= round_constant;
+
+        trace.record(Event::Registers {
+            step: round,
+            names: &LANE_NAMES,
+            values: state,
+        });
+    }
+}
+
+// A sponge over Keccak-f[1600]. Unlike the Merkle–Damgård hashers there is
+// no length field: the message is padded with the domain byte and pad10*1,
+// and output is squeezed out of the first `rate` bytes of the state.
+#[derive(Clone)]
+pub struct Sponge {
+    state: [u64; 25],
+    rate: usize,
+    domain: u8,
+    // Absorb position while absorbing, squeeze position while squeezing.
+    position: usize,
+    squeezing: bool,
+    // Permutations run so far, which number the blocks in traces.
+    permutations: usize,
+}
+
+impl Sponge {
+    // Rate and capacity are in bytes and must add up to the state size.
+    pub fn new(rate: usize, capacity: usize, domain: u8) -> Self {
+        Sponge::from_state([0; 25], rate, capacity, domain)
+    }
+
+    pub fn from_state(state: [u64; 25], rate: usize, capacity: usize, domain: u8) -> Self {
+        assert_eq!(rate + capacity, STATE_SIZE);
+        assert!(rate > 0 && rate < STATE_SIZE);
+        Sponge {
+            state,
+            rate,
+            domain,
+            position: 0,
+            squeezing: false,
+            permutations: 0,
+        }
+    }
+
+    pub fn rate(&self) -> usize {
+        self.rate
+    }
+
+    pub fn state(&self) -> [u64; 25] {
+        self.state
+    }
+
+    fn xor_byte(&mut self, index: usize, byte: u8) {
+        self.state[index / 8] ^= (byte as u64) << (8 * (index % 8));
+    }
+
+    fn byte(&self, index: usize) -> u8 {
+        (self.state[index / 8] >> (8 * (index % 8))) as u8
+    }
+
+    pub fn absorb(&mut self, bytes: &[u8]) {
+        self.absorb_with(bytes, &mut NoTrace);
+    }
+
+    // `absorb`, reporting the state going into every permutation and the
+    // lanes after every round to `trace`.
+    pub fn absorb_traced(&mut self, bytes: &[u8], trace: &mut dyn Trace<u64>) {
+        self.absorb_with(bytes, trace);
+    }
+
+    fn absorb_with<T>(&mut self, bytes: &[u8], trace: &mut T)
+    where
+        T: Trace<u64> + ?Sized,
+    {
+        assert!(!self.squeezing, "cannot absorb after squeezing");
+
+        for byte in bytes {
+            self.xor_byte(self.position, *byte);
+            self.position += 1;
+            if self.position == self.rate {
+                self.permute(trace);
+                self.position = 0;
+            }
+        }
+    }
+
+    fn permute<T>(&mut self, trace: &mut T)
+    where
+        T: Trace<u64> + ?Sized,
+    {
+        trace.record(Event::ChainingValue {
+            block: self.permutations,
+            value: &self.state,
+        });
+        permute(&mut self.state, trace);
+        self.permutations += 1;
+    }
+
+    // Pads the last block and switches the sponge to squeezing.
+    fn pad<T>(&mut self, trace: &mut T)
+    where
+        T: Trace<u64> + ?Sized,
+    {
+        self.xor_byte(self.position, self.domain);
+        self.xor_byte(self.rate - 1, 0x80);
+        self.permute(trace);
+        self.position = 0;
+        self.squeezing = true;
+    }
+
+    // Fills `output` with the next bytes of the sponge output. Can be called
+    // repeatedly to read an arbitrarily long output.
+    pub fn squeeze(&mut self, output: &mut [u8]) {
+        self.squeeze_with(output, &mut NoTrace);
+    }
+
+    // `squeeze`, reporting like `absorb_traced`. The last state reported is
+    // the one the final output bytes were read from.
+    pub fn squeeze_traced(&mut self, output: &mut [u8], trace: &mut dyn Trace<u64>) {
+        self.squeeze_with(output, trace);
+    }
+
+    fn squeeze_with<T>(&mut self, output: &mut [u8], trace: &mut T)
+    where
+        T: Trace<u64> + ?Sized,
+    {
+        if !self.squeezing {
+            self.pad(trace);
+        }
+
+        for byte in output.iter_mut() {
+            if self.position == self.rate {
+                self.permute(trace);
+                self.position = 0;
+            }
+            *byte = self.byte(self.position);
+            self.position += 1;
+        }
+        trace.record(Event::ChainingValue {
+            block: self.permutations,
+            value: &self.state,
+        });
+    }
+}
+
+// Fixed output length hashes. `N` is the digest size in bytes and the
+// capacity is twice that.
+#[derive(Clone)]
+pub struct KeccakHasher<const N: usize, const DOMAIN: u8> {
+    sponge: Sponge,
+}
+
+pub type Sha3_224 = KeccakHasher<28, DOMAIN_SHA3>;
+pub type Sha3_256 = KeccakHasher<32, DOMAIN_SHA3>;
+pub type Sha3_384 = KeccakHasher<48, DOMAIN_SHA3>;
+pub type Sha3_512 = KeccakHasher<64, DOMAIN_SHA3>;
+pub type Keccak256 = KeccakHasher<32, DOMAIN_KECCAK>;
+
+impl<const N: usize, const DOMAIN: u8> Hasher for KeccakHasher<N, DOMAIN> {
+    const BLOCK_SIZE: usize = STATE_SIZE - 2 * N;
+
+    type State = [u64; 25];
+    type Digest = [u8; N];
+
+    fn new() -> Self {
+        KeccakHasher::from_state([0; 25])
+    }
+
+    fn from_state(state: Self::State) -> Self {
+        KeccakHasher {
+            sponge: Sponge::from_state(state, Self::BLOCK_SIZE, 2 * N, DOMAIN),
+        }
+    }
+
+    fn update<T>(&mut self, message: T)
+    where
+        T: AsBytes,
+    {
+        self.sponge.absorb(message.as_bytes());
+    }
+
+    fn finalize(mut self) -> Self::Digest {
+        let mut output: [u8; N] = [0; N];
+        self.sponge.squeeze(&mut output);
+        output
+    }
+
+    fn reset(&mut self) {
+        *self = KeccakHasher::new();
+    }
+}
+
+impl<const N: usize, const DOMAIN: u8> KeccakHasher<N, DOMAIN> {
+    // Hashes `message`, reporting the state going into every permutation and
+    // the lanes after every round to `trace`.
+    pub fn traced<T>(message: T, trace: &mut dyn Trace<u64>) -> [u8; N]
+    where
+        T: AsBytes,
+    {
+        let mut sponge = KeccakHasher::<N, DOMAIN>::new().sponge;
+        sponge.absorb_traced(message.as_bytes(), trace);
+        let mut output: [u8; N] = [0; N];
+        sponge.squeeze_traced(&mut output, trace);
+        output
+    }
+}
+
+fn hash<H, T>(message: T) -> H::Digest
+where
+    H: Hasher,
+    T: AsBytes,
+{
+    let mut hasher = H::new();
+    hasher.update(message);
+    hasher.finalize()
+}
+
+pub fn sha3_224<T>(message: T) -> [u8; 28]
+where
+    T: AsBytes,
+{
+    hash::<Sha3_224, T>(message)
+}
+
+pub fn sha3_256<T>(message: T) -> [u8; 32]
+where
+    T: AsBytes,
+{
+    hash::<Sha3_256, T>(message)
+}
+
+pub fn sha3_384<T>(message: T) -> [u8; 48]
+where
+    T: AsBytes,
+{
+    hash::<Sha3_384, T>(message)
+}
+
+pub fn sha3_512<T>(message: T) -> [u8; 64]
+where
+    T: AsBytes,
+{
+    hash::<Sha3_512, T>(message)
+}
+
+// Keccak as submitted to the SHA-3 competition, before NIST changed the
+// padding. This is the hash Ethereum calls keccak256.
+pub fn keccak256<T>(message: T) -> [u8; 32]
+where
+    T: AsBytes,
+{
+    hash::<Keccak256, T>(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sha3() {
+        let expected_empty_224: [u8; 28] = [
+            0x6B, 0x4E, 0x03, 0x42, 0x36, 0x67, 0xDB, 0xB7, 0x3B, 0x6E, 0x15, 0x45, 0x4F, 0x0E,
+            0xB1, 0xAB, 0xD4, 0x59, 0x7F, 0x9A, 0x1B, 0x07, 0x8E, 0x3F, 0x5B, 0x5A, 0x6B, 0xC7,
+        ];
+        let expected_abc_256: [u8; 32] = [
+            0x3A, 0x98, 0x5D, 0xA7, 0x4F, 0xE2, 0x25, 0xB2, 0x04, 0x5C, 0x17, 0x2D, 0x6B, 0xD3,
+            0x90, 0xBD, 0x85, 0x5F, 0x08, 0x6E, 0x3E, 0x9D, 0x52, 0x5B, 0x46, 0xBF, 0xE2, 0x45,
+            0x11, 0x43, 0x15, 0x32,
+        ];
+        let expected_abc_384: [u8; 48] = [
+            0xEC, 0x01, 0x49, 0x82, 0x88, 0x51, 0x6F, 0xC9, 0x26, 0x45, 0x9F, 0x58, 0xE2, 0xC6,
+            0xAD, 0x8D, 0xF9, 0xB4, 0x73, 0xCB, 0x0F, 0xC0, 0x8C, 0x25, 0x96, 0xDA, 0x7C, 0xF0,
+            0xE4, 0x9B, 0xE4, 0xB2, 0x98, 0xD8, 0x8C, 0xEA, 0x92, 0x7A, 0xC7, 0xF5, 0x39, 0xF1,
+            0xED, 0xF2, 0x28, 0x37, 0x6D, 0x25,
+        ];
+        let expected_abc_512: [u8; 64] = [
+            0xB7, 0x51, 0x85, 0x0B, 0x1A, 0x57, 0x16, 0x8A, 0x56, 0x93, 0xCD, 0x92, 0x4B, 0x6B,
+            0x09, 0x6E, 0x08, 0xF6, 0x21, 0x82, 0x74, 0x44, 0xF7, 0x0D, 0x88, 0x4F, 0x5D, 0x02,
+            0x40, 0xD2, 0x71, 0x2E, 0x10, 0xE1, 0x16, 0xE9, 0x19, 0x2A, 0xF3, 0xC9, 0x1A, 0x7E,
+            0xC5, 0x76, 0x47, 0xE3, 0x93, 0x40, 0x57, 0x34, 0x0B, 0x4C, 0xF4, 0x08, 0xD5, 0xA5,
+            0x65, 0x92, 0xF8, 0x27, 0x4E, 0xEC, 0x53, 0xF0,
+        ];
+
+        assert_eq!(sha3_224(""), expected_empty_224);
+        assert_eq!(sha3_256("abc"), expected_abc_256);
+        assert_eq!(sha3_384("abc"), expected_abc_384);
+        assert_eq!(sha3_512("abc"), expected_abc_512);
+    }
+
+    #[test]
+    fn test_sha3_multi_block() {
+        // 1600 bits of 0xA3 from the NIST example values, which spans
+        // several blocks at every rate.
+        let message = [0xA3; 200];
+
+        let expected_256: [u8; 32] = [
+            0x79, 0xF3, 0x8A, 0xDE, 0xC5, 0xC2, 0x03, 0x07, 0xA9, 0x8E, 0xF7, 0x6E, 0x83, 0x24,
+            0xAF, 0xBF, 0xD4, 0x6C, 0xFD, 0x81, 0xB2, 0x2E, 0x39, 0x73, 0xC6, 0x5F, 0xA1, 0xBD,
+            0x9D, 0xE3, 0x17, 0x87,
+        ];
+        assert_eq!(sha3_256(message.as_slice()), expected_256);
+
+        let mut hasher = Sha3_256::new();
+        for piece in message.chunks(33) {
+            hasher.update(piece);
+        }
+        assert_eq!(hasher.finalize(), expected_256);
+    }
+
+    #[test]
+    fn test_keccak256() {
+        let expected_empty: [u8; 32] = [
+            0xC5, 0xD2, 0x46, 0x01, 0x86, 0xF7, 0x23, 0x3C, 0x92, 0x7E, 0x7D, 0xB2, 0xDC, 0xC7,
+            0x03, 0xC0, 0xE5, 0x00, 0xB6, 0x53, 0xCA, 0x82, 0x27, 0x3B, 0x7B, 0xFA, 0xD8, 0x04,
+            0x5D, 0x85, 0xA4, 0x70,
+        ];
+        let expected_hello_world: [u8; 32] = [
+            0x47, 0x17, 0x32, 0x85, 0xA8, 0xD7, 0x34, 0x1E, 0x5E, 0x97, 0x2F, 0xC6, 0x77, 0x28,
+            0x63, 0x84, 0xF8, 0x02, 0xF8, 0xEF, 0x42, 0xA5, 0xEC, 0x5F, 0x03, 0xBB, 0xFA, 0x25,
+            0x4C, 0xB0, 0x1F, 0xAD,
+        ];
+
+        assert_eq!(keccak256(""), expected_empty);
+        assert_eq!(keccak256("hello world"), expected_hello_world);
+    }
+
+    #[test]
+    fn test_keccak_f1600() {
+        // Permuting the all zero state, first lane of the output from the
+        // Keccak team's intermediate values.
+        let mut state: [u64; 25] = [0; 25];
+        keccak_f1600(&mut state);
+        assert_eq!(state[0], 0xF1258F7940E1DDE7);
+    }
+
+    #[test]
+    fn test_keccak_traced() {
+        // 200 bytes at the SHA3-256 rate of 136 bytes absorb one full block,
+        // and padding the rest takes a second permutation.
+        let message = [0xA3; 200];
+        let mut rounds = 0;
+        let mut states = vec![];
+        let digest = Sha3_256::traced(
+            message.as_slice(),
+            &mut |event: Event<'_, u64>| match event {
+                Event::ChainingValue { block, value } => states.push((block, value.to_vec())),
+                Event::Registers { step, .. } => {
+                    assert_eq!(step, rounds % 24);
+                    rounds += 1;
+                }
+                Event::ExtendedWords(_) => unreachable!(),
+            },
+        );
+        assert_eq!(digest, sha3_256(message.as_slice()));
+
+        assert_eq!(rounds, 2 * 24);
+        assert_eq!(states.len(), 3);
+        assert_eq!(states[2].0, 2);
+        let output: Vec<u8> = states[2].1[..4]
+            .iter()
+            .flat_map(|lane| lane.to_le_bytes())
+            .collect();
+        assert_eq!(output, digest);
+    }
+}
@@ -0,0 +1,373 @@
+use super::*;
+use std::marker::PhantomData;
+
+// IV from the spec.
+// https://datatracker.ietf.org/doc/html/rfc1320#section-3-3
+pub const IV: [u32; 4] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476];
+
+#[allow(non_snake_case)]
+fn F(x: u32, y: u32, z: u32) -> u32 {
+    (x & y) | ((!x) & z)
+}
+
+#[allow(non_snake_case)]
+fn G(x: u32, y: u32, z: u32) -> u32 {
+    (x & y) | (x & z) | (y & z)
+}
+
+#[allow(non_snake_case)]
+fn H(x: u32, y: u32, z: u32) -> u32 {
+    x ^ y ^ z
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn round(
+    a: &mut u32,
+    b: u32,
+    c: u32,
+    d: u32,
+    func: BooleanFunction,
+    word: u32,
+    s: u32,
+    constant: u32,
+) {
+    *a = ((*a)
+        .wrapping_add(func(b, c, d))
+        .wrapping_add(word)
+        .wrapping_add(constant))
+    .rotate_left(s);
+}
+
+// Boolean functions of the three rounds.
+pub type BooleanFunction = fn(u32, u32, u32) -> u32;
+
+// Message word order and rotations of each round.
+pub(crate) const WORD_ORDER: [[usize; 16]; 3] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [0, 4, 8, 12, 1, 5, 9, 13, 2, 6, 10, 14, 3, 7, 11, 15],
+    [0, 8, 4, 12, 2, 10, 6, 14, 1, 9, 5, 13, 3, 11, 7, 15],
+];
+pub(crate) const SHIFTS: [[u32; 4]; 3] = [[3, 7, 11, 19], [3, 5, 9, 13], [3, 9, 11, 15]];
+
+const STEPS: usize = 48;
+pub(crate) const STEPS_PER_ROUND: usize = 16;
+
+// Internals of the compression function, for experimenting with weakened
+// variants: fewer steps, other round constants or other boolean functions.
+// Each round covers 16 steps however many are run. Every change goes
+// through a builder method that marks the result `NonStandard`.
+#[derive(Clone, Copy, Debug)]
+pub struct Params<S = Standard> {
+    steps: usize,
+    constants: [u32; 3],
+    functions: [BooleanFunction; 3],
+    _conformance: PhantomData<S>,
+}
+
+pub(crate) const STANDARD: Params = Params {
+    steps: STEPS,
+    constants: [0, 0x5A827999, 0x6ED9EBA1],
+    functions: [F, G, H],
+    _conformance: PhantomData,
+};
+
+impl Params {
+    pub fn new() -> Self {
+        STANDARD
+    }
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Params::new()
+    }
+}
+
+impl<S> Params<S> {
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    pub fn constants(&self) -> [u32; 3] {
+        self.constants
+    }
+
+    pub fn functions(&self) -> [BooleanFunction; 3] {
+        self.functions
+    }
+
+    fn non_standard(self) -> Params<NonStandard> {
+        Params {
+            steps: self.steps,
+            constants: self.constants,
+            functions: self.functions,
+            _conformance: PhantomData,
+        }
+    }
+
+    // Runs the first `steps` steps only. None beyond 48.
+    pub fn with_steps(self, steps: usize) -> Option<Params<NonStandard>> {
+        if steps > STEPS {
+            return None;
+        }
+        Some(Params {
+            steps,
+            ..self.non_standard()
+        })
+    }
+
+    pub fn with_constants(self, constants: [u32; 3]) -> Params<NonStandard> {
+        Params {
+            constants,
+            ..self.non_standard()
+        }
+    }
+
+    pub fn with_functions(self, functions: [BooleanFunction; 3]) -> Params<NonStandard> {
+        Params {
+            functions,
+            ..self.non_standard()
+        }
+    }
+}
+
+const REGISTER_NAMES: [&str; 4] = ["a", "b", "c", "d"];
+
+fn compress<S, T>(state: &mut [u32; 4], extended_words: &[u32], params: &Params<S>, trace: &mut T)
+where
+    T: Trace<u32> + ?Sized,
+{
+    trace.record(Event::ExtendedWords(extended_words));
+    let mut registers = *state;
+
+    for i in 0..params.steps {
+        let round_index = i / STEPS_PER_ROUND;
+
+        // Steps update a, d, c, b in turn, each from the three others.
+        let target = [0, 3, 2, 1][i % 4];
+        let [x, y, z] = [1, 2, 3].map(|offset| registers[(target + offset) % 4]);
+        round(
+            &mut registers[target],
+            x,
+            y,
+            z,
+            params.functions[round_index],
+            extended_words[WORD_ORDER[round_index][i % 16]],
+            SHIFTS[round_index][i % 4],
+            params.constants[round_index],
+        );
+        trace.record(Event::Registers {
+            step: i,
+            names: &REGISTER_NAMES,
+            values: &registers,
+        });
+    }
+
+    for (word, value) in state.iter_mut().zip(registers) {
+        *word = word.wrapping_add(value);
+    }
+}
+
+pub struct Md4Compression;
+
+impl CompressionFunction for Md4Compression {
+    type Word = u32;
+    type ChainingValue = [u32; 4];
+    type Digest = [u8; 16];
+
+    const BLOCK_SIZE: usize = 64;
+    const LENGTH_SIZE: usize = 8;
+    const ENDIAN: Endian = Endian::Little;
+    const IV: Self::ChainingValue = IV;
+
+    fn compress(state: &mut Self::ChainingValue, words: &[u32]) {
+        compress(state, words, &STANDARD, &mut NoTrace);
+    }
+
+    fn compress_traced(state: &mut Self::ChainingValue, words: &[u32], trace: &mut dyn Trace<u32>) {
+        compress(state, words, &STANDARD, trace);
+    }
+}
+
+pub type Md4 = MerkleDamgard<Md4Compression>;
+pub type State = merkle_damgard::State<Md4Compression>;
+
+pub fn core<T>(message: T, total_length: usize, iv: (u32, u32, u32, u32)) -> [u8; 16]
+where
+    T: AsBytes,
+{
+    let bytes = message.as_bytes();
+    let iv = [iv.0, iv.1, iv.2, iv.3];
+    let state = State::for_suffix(iv, total_length, bytes.len());
+    let mut hasher = Md4::from_state(state);
+    hasher.update(bytes);
+    hasher.finalize()
+}
+
+pub fn default<T>(message: T) -> [u8; 16]
+where
+    T: AsBytes,
+{
+    let mut hasher = Md4::new();
+    hasher.update(message);
+    hasher.finalize()
+}
+
+// `default`, reporting every block's schedule and registers to `trace`.
+pub fn traced<T>(message: T, trace: &mut dyn Trace<u32>) -> [u8; 16]
+where
+    T: AsBytes,
+{
+    merkle_damgard::hash_traced::<Md4Compression>(State::new(IV, 0), message.as_bytes(), trace)
+}
+
+// `core`, reporting every block's schedule and registers to `trace`.
+pub fn core_traced<T>(
+    message: T,
+    total_length: usize,
+    iv: (u32, u32, u32, u32),
+    trace: &mut dyn Trace<u32>,
+) -> [u8; 16]
+where
+    T: AsBytes,
+{
+    let bytes = message.as_bytes();
+    let iv = [iv.0, iv.1, iv.2, iv.3];
+    let state = State::for_suffix(iv, total_length, bytes.len());
+    merkle_damgard::hash_traced::<Md4Compression>(state, bytes, trace)
+}
+
+impl<S> Params<S> {
+    // Compresses one block of 16 words into `state`.
+    pub fn compress(&self, state: &mut [u32; 4], words: &[u32]) {
+        compress(state, words, self, &mut NoTrace);
+    }
+
+    // MD4 with this compression function, padding and IV unchanged.
+    pub fn hash<T>(&self, message: T) -> [u8; 16]
+    where
+        T: AsBytes,
+    {
+        self.traced(message, &mut NoTrace)
+    }
+
+    pub fn traced<T>(&self, message: T, trace: &mut dyn Trace<u32>) -> [u8; 16]
+    where
+        T: AsBytes,
+    {
+        merkle_damgard::hash_with::<Md4Compression, _>(
+            State::new(IV, 0),
+            message.as_bytes(),
+            trace,
+            |state, words, trace| compress(state, words, self, trace),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_md4_streaming() {
+        let message = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789".repeat(5);
+
+        // Feed the message in uneven pieces that straddle block boundaries.
+        for piece_size in [1, 7, 63, 64, 65, 200] {
+            let mut hasher = Md4::new();
+            for piece in message.as_bytes().chunks(piece_size) {
+                hasher.update(piece);
+            }
+            assert_eq!(hasher.finalize(), default(message.as_str()));
+        }
+    }
+
+    #[test]
+    fn test_md4_reset() {
+        let mut hasher = Md4::new();
+        hasher.update("garbage");
+        hasher.reset();
+        hasher.update("a");
+        assert_eq!(hasher.finalize(), default("a"));
+    }
+
+    #[test]
+    fn test_md4_from_state() {
+        let message = [0x61; 200];
+
+        // Compress the first two blocks, then resume from the state.
+        let mut hasher = Md4::new();
+        hasher.update(&message[..128]);
+
+        let mut hasher = Md4::from_state(hasher.state());
+        hasher.update(&message[128..]);
+        assert_eq!(hasher.finalize(), default(message.as_slice()));
+    }
+
+    #[test]
+    fn test_md4_params() {
+        // Test vector from the RFC.
+        let params = Params::new();
+        let expected: [u8; 16] = [
+            0xA4, 0x48, 0x01, 0x7A, 0xAF, 0x21, 0xD8, 0x52, 0x5F, 0xC1, 0x0A, 0xE8, 0x7A, 0xA6,
+            0x72, 0x9D,
+        ];
+        assert_eq!(params.hash("abc"), expected);
+        assert!(params.with_steps(49).is_none());
+
+        let message = [0x61; 100];
+        let two_rounds = params.with_steps(32).unwrap();
+        assert_ne!(
+            two_rounds.hash(message.as_slice()),
+            default(message.as_slice())
+        );
+        let mut steps = 0;
+        two_rounds.traced(message.as_slice(), &mut |event: Event<'_, u32>| {
+            if let Event::Registers { .. } = event {
+                steps += 1;
+            }
+        });
+        assert_eq!(steps, 2 * 32);
+
+        // Swapping in the standard constants and functions again gives MD4
+        // back, typed as non-standard.
+        let rebuilt: Params<NonStandard> = Params::new()
+            .with_constants(params.constants())
+            .with_functions(params.functions());
+        assert_eq!(
+            rebuilt.hash(message.as_slice()),
+            default(message.as_slice())
+        );
+        assert_ne!(
+            params.with_constants([0; 3]).hash(message.as_slice()),
+            default(message.as_slice())
+        );
+    }
+
+    #[test]
+    fn test_md4_traced() {
+        let message = [0x61; 100];
+        let mut steps = 0;
+        let mut chaining_values = vec![];
+        let digest = traced(
+            message.as_slice(),
+            &mut |event: Event<'_, u32>| match event {
+                Event::ChainingValue { value, .. } => chaining_values.push(value.to_vec()),
+                Event::Registers { .. } => steps += 1,
+                Event::ExtendedWords(words) => assert_eq!(words.len(), 16),
+            },
+        );
+        assert_eq!(digest, default(message.as_slice()));
+
+        // Two blocks of 48 steps, and the IV, the value between the blocks and
+        // the output.
+        assert_eq!(steps, 2 * 48);
+        assert_eq!(chaining_values.len(), 3);
+        assert_eq!(chaining_values[0], IV);
+        let output: Vec<u8> = chaining_values[2]
+            .iter()
+            .flat_map(|word| word.to_le_bytes())
+            .collect();
+        assert_eq!(output, digest);
+    }
+}
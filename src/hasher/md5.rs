@@ -0,0 +1,227 @@
+use super::*;
+
+// IV from the spec. Same as md4.
+// https://www.rfc-editor.org/rfc/rfc1321#section-3.3
+pub const IV: [u32; 4] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476];
+
+// Integer parts of abs(sin(i + 1)) * 2^32.
+const K: [u32; 64] = [
+    0xD76AA478, 0xE8C7B756, 0x242070DB, 0xC1BDCEEE, 0xF57C0FAF, 0x4787C62A, 0xA8304613, 0xFD469501,
+    0x698098D8, 0x8B44F7AF, 0xFFFF5BB1, 0x895CD7BE, 0x6B901122, 0xFD987193, 0xA679438E, 0x49B40821,
+    0xF61E2562, 0xC040B340, 0x265E5A51, 0xE9B6C7AA, 0xD62F105D, 0x02441453, 0xD8A1E681, 0xE7D3FBC8,
+    0x21E1CDE6, 0xC33707D6, 0xF4D50D87, 0x455A14ED, 0xA9E3E905, 0xFCEFA3F8, 0x676F02D9, 0x8D2A4C8A,
+    0xFFFA3942, 0x8771F681, 0x6D9D6122, 0xFDE5380C, 0xA4BEEA44, 0x4BDECFA9, 0xF6BB4B60, 0xBEBFBC70,
+    0x289B7EC6, 0xEAA127FA, 0xD4EF3085, 0x04881D05, 0xD9D4D039, 0xE6DB99E5, 0x1FA27CF8, 0xC4AC5665,
+    0xF4292244, 0x432AFF97, 0xAB9423A7, 0xFC93A039, 0x655B59C3, 0x8F0CCC92, 0xFFEFF47D, 0x85845DD1,
+    0x6FA87E4F, 0xFE2CE6E0, 0xA3014314, 0x4E0811A1, 0xF7537E82, 0xBD3AF235, 0x2AD7D2BB, 0xEB86D391,
+];
+
+// Per round shift amounts, each repeated four times within a round.
+const SHIFTS: [[u32; 4]; 4] = [
+    [7, 12, 17, 22],
+    [5, 9, 14, 20],
+    [4, 11, 16, 23],
+    [6, 10, 15, 21],
+];
+
+#[allow(non_snake_case)]
+fn F(x: u32, y: u32, z: u32) -> u32 {
+    (x & y) | ((!x) & z)
+}
+
+#[allow(non_snake_case)]
+fn G(x: u32, y: u32, z: u32) -> u32 {
+    (x & z) | (y & (!z))
+}
+
+#[allow(non_snake_case)]
+fn H(x: u32, y: u32, z: u32) -> u32 {
+    x ^ y ^ z
+}
+
+#[allow(non_snake_case)]
+fn I(x: u32, y: u32, z: u32) -> u32 {
+    y ^ (x | (!z))
+}
+
+const REGISTER_NAMES: [&str; 4] = ["a", "b", "c", "d"];
+
+fn compress<T>(state: &mut [u32; 4], extended_words: &[u32], trace: &mut T)
+where
+    T: Trace<u32> + ?Sized,
+{
+    trace.record(Event::ExtendedWords(extended_words));
+    let [mut a, mut b, mut c, mut d] = *state;
+
+    for (i, k) in K.iter().enumerate() {
+        // Each round uses its own boolean function and message word order.
+        let round = i / 16;
+        let (func, word): (fn(u32, u32, u32) -> u32, usize) = match round {
+            0 => (F, i),
+            1 => (G, (5 * i + 1) % 16),
+            2 => (H, (3 * i + 5) % 16),
+            _ => (I, (7 * i) % 16),
+        };
+
+        let temp = a
+            .wrapping_add(func(b, c, d))
+            .wrapping_add(extended_words[word])
+            .wrapping_add(*k)
+            .rotate_left(SHIFTS[round][i % 4]);
+        a = d;
+        d = c;
+        c = b;
+        b = b.wrapping_add(temp);
+        trace.record(Event::Registers {
+            step: i,
+            names: &REGISTER_NAMES,
+            values: &[a, b, c, d],
+        });
+    }
+
+    state[0] = state[0].wrapping_add(a);
+    state[1] = state[1].wrapping_add(b);
+    state[2] = state[2].wrapping_add(c);
+    state[3] = state[3].wrapping_add(d);
+}
+
+pub struct Md5Compression;
+
+impl CompressionFunction for Md5Compression {
+    type Word = u32;
+    type ChainingValue = [u32; 4];
+    type Digest = [u8; 16];
+
+    const BLOCK_SIZE: usize = 64;
+    const LENGTH_SIZE: usize = 8;
+    const ENDIAN: Endian = Endian::Little;
+    const IV: Self::ChainingValue = IV;
+
+    fn compress(state: &mut Self::ChainingValue, words: &[u32]) {
+        compress(state, words, &mut NoTrace);
+    }
+
+    fn compress_traced(state: &mut Self::ChainingValue, words: &[u32], trace: &mut dyn Trace<u32>) {
+        compress(state, words, trace);
+    }
+}
+
+pub type Md5 = MerkleDamgard<Md5Compression>;
+pub type State = merkle_damgard::State<Md5Compression>;
+
+pub fn core<T>(message: T, total_length: usize, iv: (u32, u32, u32, u32)) -> [u8; 16]
+where
+    T: AsBytes,
+{
+    let bytes = message.as_bytes();
+    let iv = [iv.0, iv.1, iv.2, iv.3];
+    let state = State::for_suffix(iv, total_length, bytes.len());
+    let mut hasher = Md5::from_state(state);
+    hasher.update(bytes);
+    hasher.finalize()
+}
+
+pub fn default<T>(message: T) -> [u8; 16]
+where
+    T: AsBytes,
+{
+    let mut hasher = Md5::new();
+    hasher.update(message);
+    hasher.finalize()
+}
+
+// `default`, reporting every block's schedule and registers to `trace`.
+pub fn traced<T>(message: T, trace: &mut dyn Trace<u32>) -> [u8; 16]
+where
+    T: AsBytes,
+{
+    merkle_damgard::hash_traced::<Md5Compression>(State::new(IV, 0), message.as_bytes(), trace)
+}
+
+// `core`, reporting every block's schedule and registers to `trace`.
+pub fn core_traced<T>(
+    message: T,
+    total_length: usize,
+    iv: (u32, u32, u32, u32),
+    trace: &mut dyn Trace<u32>,
+) -> [u8; 16]
+where
+    T: AsBytes,
+{
+    let bytes = message.as_bytes();
+    let iv = [iv.0, iv.1, iv.2, iv.3];
+    let state = State::for_suffix(iv, total_length, bytes.len());
+    merkle_damgard::hash_traced::<Md5Compression>(state, bytes, trace)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Test suite from
+    // https://www.rfc-editor.org/rfc/rfc1321#appendix-A.5
+    #[test]
+    fn test_md5() {
+        let empty_output = default("");
+        let a_output = default("a");
+        let abc_output = default("abc");
+        let message_digest_output = default("message digest");
+        let alphabet_output = default("abcdefghijklmnopqrstuvwxyz");
+        let alphanumeric_output =
+            default("ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789");
+        let numeric_output = default(
+            "1234567890123456789012345678901234567890\
+            1234567890123456789012345678901234567890",
+        );
+
+        let expected_empty_output: [u8; 16] = [
+            0xD4, 0x1D, 0x8C, 0xD9, 0x8F, 0x00, 0xB2, 0x04, 0xE9, 0x80, 0x09, 0x98, 0xEC, 0xF8,
+            0x42, 0x7E,
+        ];
+        let expected_a_output: [u8; 16] = [
+            0x0C, 0xC1, 0x75, 0xB9, 0xC0, 0xF1, 0xB6, 0xA8, 0x31, 0xC3, 0x99, 0xE2, 0x69, 0x77,
+            0x26, 0x61,
+        ];
+        let expected_abc_output: [u8; 16] = [
+            0x90, 0x01, 0x50, 0x98, 0x3C, 0xD2, 0x4F, 0xB0, 0xD6, 0x96, 0x3F, 0x7D, 0x28, 0xE1,
+            0x7F, 0x72,
+        ];
+        let expected_message_digest_output: [u8; 16] = [
+            0xF9, 0x6B, 0x69, 0x7D, 0x7C, 0xB7, 0x93, 0x8D, 0x52, 0x5A, 0x2F, 0x31, 0xAA, 0xF1,
+            0x61, 0xD0,
+        ];
+        let expected_alphabet_output: [u8; 16] = [
+            0xC3, 0xFC, 0xD3, 0xD7, 0x61, 0x92, 0xE4, 0x00, 0x7D, 0xFB, 0x49, 0x6C, 0xCA, 0x67,
+            0xE1, 0x3B,
+        ];
+        let expected_alphanumeric_output: [u8; 16] = [
+            0xD1, 0x74, 0xAB, 0x98, 0xD2, 0x77, 0xD9, 0xF5, 0xA5, 0x61, 0x1C, 0x2C, 0x9F, 0x41,
+            0x9D, 0x9F,
+        ];
+        let expected_numeric_output: [u8; 16] = [
+            0x57, 0xED, 0xF4, 0xA2, 0x2B, 0xE3, 0xC9, 0x55, 0xAC, 0x49, 0xDA, 0x2E, 0x21, 0x07,
+            0xB6, 0x7A,
+        ];
+
+        assert_eq!(empty_output, expected_empty_output);
+        assert_eq!(a_output, expected_a_output);
+        assert_eq!(abc_output, expected_abc_output);
+        assert_eq!(message_digest_output, expected_message_digest_output);
+        assert_eq!(alphabet_output, expected_alphabet_output);
+        assert_eq!(alphanumeric_output, expected_alphanumeric_output);
+        assert_eq!(numeric_output, expected_numeric_output);
+    }
+
+    #[test]
+    fn test_md5_streaming() {
+        let message = "1234567890".repeat(30);
+
+        for piece_size in [1, 7, 63, 64, 65, 200] {
+            let mut hasher = Md5::new();
+            for piece in message.as_bytes().chunks(piece_size) {
+                hasher.update(piece);
+            }
+            assert_eq!(hasher.finalize(), default(message.as_str()));
+        }
+    }
+}
@@ -0,0 +1,537 @@
+use super::trace::{Event, Trace};
+use super::*;
+use std::fmt;
+
+// Largest block the generic construction can parse, in words.
+const MAX_BLOCK_WORDS: usize = 32;
+
+// Machine words a compression function operates on.
+pub trait Word: Copy + Default {
+    const SIZE: usize;
+
+    fn from_bytes(bytes: &[u8], endian: Endian) -> Self;
+
+    fn extend_bytes(self, output: &mut Vec<u8>, endian: Endian);
+}
+
+impl Word for u32 {
+    const SIZE: usize = 4;
+
+    fn from_bytes(bytes: &[u8], endian: Endian) -> Self {
+        let to_32_bit: [u8; 4] = [bytes[0], bytes[1], bytes[2], bytes[3]];
+        match endian {
+            Endian::Big => u32::from_be_bytes(to_32_bit),
+            Endian::Little => u32::from_le_bytes(to_32_bit),
+        }
+    }
+
+    fn extend_bytes(self, output: &mut Vec<u8>, endian: Endian) {
+        match endian {
+            Endian::Big => output.extend(self.to_be_bytes()),
+            Endian::Little => output.extend(self.to_le_bytes()),
+        }
+    }
+}
+
+impl Word for u64 {
+    const SIZE: usize = 8;
+
+    fn from_bytes(bytes: &[u8], endian: Endian) -> Self {
+        let to_64_bit: [u8; 8] = bytes[..8].try_into().unwrap();
+        match endian {
+            Endian::Big => u64::from_be_bytes(to_64_bit),
+            Endian::Little => u64::from_le_bytes(to_64_bit),
+        }
+    }
+
+    fn extend_bytes(self, output: &mut Vec<u8>, endian: Endian) {
+        match endian {
+            Endian::Big => output.extend(self.to_be_bytes()),
+            Endian::Little => output.extend(self.to_le_bytes()),
+        }
+    }
+}
+
+// Everything that distinguishes one Merkle–Damgård hash from another. The
+// construction takes care of buffering, padding, parsing blocks into words
+// and serializing the final state.
+pub trait CompressionFunction {
+    type Word: Word;
+
+    // Chaining value as an array of words, e.g. `[u32; 5]` for sha1.
+    type ChainingValue: Copy + fmt::Debug + PartialEq + AsRef<[Self::Word]> + AsMut<[Self::Word]>;
+
+    // Byte array holding the digest. When it is shorter than the chaining
+    // value, the serialized chaining value is truncated.
+    type Digest: Copy + AsRef<[u8]> + for<'a> TryFrom<&'a [u8]>;
+
+    const BLOCK_SIZE: usize;
+
+    // Size of the message length field appended by the padding in bytes.
+    const LENGTH_SIZE: usize;
+
+    // Byte order used to parse block words, encode the length and serialize
+    // the digest.
+    const ENDIAN: Endian;
+
+    const IV: Self::ChainingValue;
+
+    // Mixes one block, already parsed into words, into the chaining value.
+    fn compress(state: &mut Self::ChainingValue, words: &[Self::Word]);
+
+    // `compress`, reporting its internals to `trace`. Compression functions
+    // that aren't instrumented report nothing.
+    fn compress_traced(
+        state: &mut Self::ChainingValue,
+        words: &[Self::Word],
+        trace: &mut dyn Trace<Self::Word>,
+    ) {
+        let _ = trace;
+        Self::compress(state, words);
+    }
+}
+
+// Parses a block into words and runs the compression function over it.
+pub fn compress_block<C>(state: &mut C::ChainingValue, block: &[u8])
+where
+    C: CompressionFunction,
+{
+    let (words, word_count) = parse_block::<C>(block);
+    C::compress(state, &words[..word_count]);
+}
+
+fn parse_block<C>(block: &[u8]) -> ([C::Word; MAX_BLOCK_WORDS], usize)
+where
+    C: CompressionFunction,
+{
+    let word_count = C::BLOCK_SIZE / <C::Word as Word>::SIZE;
+    assert!(word_count <= MAX_BLOCK_WORDS);
+
+    let mut words = [C::Word::default(); MAX_BLOCK_WORDS];
+    for (word, bytes) in words.iter_mut().zip(block.chunks(<C::Word as Word>::SIZE)) {
+        *word = C::Word::from_bytes(bytes, C::ENDIAN);
+    }
+    (words, word_count)
+}
+
+// Hashes `message` from `state` like `MerkleDamgard`, reporting the chaining
+// value before every block, the output of the last one and whatever the
+// compression function reports in between. Blocks are numbered from the
+// start of this call.
+pub fn hash_traced<C>(state: State<C>, message: &[u8], trace: &mut dyn Trace<C::Word>) -> C::Digest
+where
+    C: CompressionFunction,
+{
+    hash_with::<C, _>(state, message, trace, C::compress_traced)
+}
+
+// `hash_traced` with `compress` in place of the compression function, which
+// lets variants configured at runtime, e.g. reduced-round ones, share the
+// padding and serialization of the hash they are derived from.
+pub fn hash_with<C, F>(
+    state: State<C>,
+    message: &[u8],
+    trace: &mut dyn Trace<C::Word>,
+    compress: F,
+) -> C::Digest
+where
+    C: CompressionFunction,
+    F: Fn(&mut C::ChainingValue, &[C::Word], &mut dyn Trace<C::Word>),
+{
+    let padding = md_padding(
+        C::BLOCK_SIZE,
+        C::LENGTH_SIZE,
+        state.processed_length + message.len(),
+        C::ENDIAN,
+    );
+    let padded = [message, padding.as_slice()].concat();
+
+    let mut chaining_value = state.chaining_value;
+    let mut blocks = 0;
+    for (block, bytes) in padded.chunks(C::BLOCK_SIZE).enumerate() {
+        trace.record(Event::ChainingValue {
+            block,
+            value: chaining_value.as_ref(),
+        });
+        let (words, word_count) = parse_block::<C>(bytes);
+        compress(&mut chaining_value, &words[..word_count], trace);
+        blocks += 1;
+    }
+    trace.record(Event::ChainingValue {
+        block: blocks,
+        value: chaining_value.as_ref(),
+    });
+    chaining_value_to_digest::<C>(&chaining_value)
+}
+
+fn chaining_value_to_bytes<C>(chaining_value: &C::ChainingValue) -> Vec<u8>
+where
+    C: CompressionFunction,
+{
+    let mut bytes = vec![];
+    for word in chaining_value.as_ref() {
+        word.extend_bytes(&mut bytes, C::ENDIAN);
+    }
+    bytes
+}
+
+// Parses bytes back into a chaining value. Returns `None` unless `bytes`
+// holds exactly one serialized chaining value.
+fn chaining_value_from_bytes<C>(bytes: &[u8]) -> Option<C::ChainingValue>
+where
+    C: CompressionFunction,
+{
+    let mut chaining_value = C::IV;
+    if bytes.len() != chaining_value.as_ref().len() * <C::Word as Word>::SIZE {
+        return None;
+    }
+
+    for (word, chunk) in chaining_value
+        .as_mut()
+        .iter_mut()
+        .zip(bytes.chunks(<C::Word as Word>::SIZE))
+    {
+        *word = C::Word::from_bytes(chunk, C::ENDIAN);
+    }
+    Some(chaining_value)
+}
+
+// Serializes a chaining value into a digest.
+pub fn chaining_value_to_digest<C>(chaining_value: &C::ChainingValue) -> C::Digest
+where
+    C: CompressionFunction,
+{
+    let bytes = chaining_value_to_bytes::<C>(chaining_value);
+
+    // Digests are byte arrays, so their size is their length.
+    let digest_size = std::mem::size_of::<C::Digest>();
+    let digest = match C::Digest::try_from(&bytes[..digest_size]) {
+        Ok(digest) => digest,
+        Err(_) => unreachable!("digest is larger than the chaining value"),
+    };
+    digest
+}
+
+// Everything needed to resume a hashing session: the chaining value after
+// the last compressed block and how many bytes went into it.
+pub struct State<C>
+where
+    C: CompressionFunction,
+{
+    pub chaining_value: C::ChainingValue,
+    pub processed_length: usize,
+}
+
+impl<C> State<C>
+where
+    C: CompressionFunction,
+{
+    pub fn new(chaining_value: C::ChainingValue, processed_length: usize) -> Self {
+        State {
+            chaining_value,
+            processed_length,
+        }
+    }
+
+    // State to hash the last `suffix_length` bytes of a `total_length` byte
+    // message from, the bytes before them already compressed into
+    // `chaining_value`. Panics if the suffix is longer than the message,
+    // whose padding would then encode a length that was never asked for.
+    pub fn for_suffix(
+        chaining_value: C::ChainingValue,
+        total_length: usize,
+        suffix_length: usize,
+    ) -> Self {
+        assert!(
+            suffix_length <= total_length,
+            "message of {} bytes is longer than the total length {}",
+            suffix_length,
+            total_length
+        );
+        State::new(chaining_value, total_length - suffix_length)
+    }
+
+    // Seeds a state from a digest, e.g. a leaked MAC. `processed_length` is
+    // the length of the message the digest was computed over, including its
+    // padding. Returns `None` for truncated digests such as SHA-224, which
+    // don't hold the whole chaining value.
+    pub fn from_digest(digest: &C::Digest, processed_length: usize) -> Option<Self> {
+        let chaining_value = chaining_value_from_bytes::<C>(digest.as_ref())?;
+        Some(State::new(chaining_value, processed_length))
+    }
+
+    // The digest a session would output if its message ended right here,
+    // padding included.
+    pub fn to_digest(&self) -> C::Digest {
+        chaining_value_to_digest::<C>(&self.chaining_value)
+    }
+
+    // Serialized as the chaining value followed by the processed length as a
+    // big-endian 64-bit number.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = chaining_value_to_bytes::<C>(&self.chaining_value);
+        bytes.extend((self.processed_length as u64).to_be_bytes());
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let split = bytes.len().checked_sub(8)?;
+        let chaining_value = chaining_value_from_bytes::<C>(&bytes[..split])?;
+        let processed_length = u64::from_be_bytes(bytes[split..].try_into().unwrap());
+        Some(State::new(chaining_value, processed_length as usize))
+    }
+}
+
+// The trait implementations below are written by hand so the compression
+// function marker type doesn't need to implement them itself.
+impl<C> Clone for State<C>
+where
+    C: CompressionFunction,
+{
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<C> Copy for State<C> where C: CompressionFunction {}
+
+impl<C> PartialEq for State<C>
+where
+    C: CompressionFunction,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.chaining_value == other.chaining_value
+            && self.processed_length == other.processed_length
+    }
+}
+
+impl<C> fmt::Debug for State<C>
+where
+    C: CompressionFunction,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("State")
+            .field("chaining_value", &self.chaining_value)
+            .field("processed_length", &self.processed_length)
+            .finish()
+    }
+}
+
+pub struct MerkleDamgard<C>
+where
+    C: CompressionFunction,
+{
+    chaining_value: C::ChainingValue,
+    // Partially filled block between calls to `update`.
+    buffer: Vec<u8>,
+    // Total number of bytes fed in, including the buffered ones.
+    length: usize,
+}
+
+impl<C> Clone for MerkleDamgard<C>
+where
+    C: CompressionFunction,
+{
+    fn clone(&self) -> Self {
+        MerkleDamgard {
+            chaining_value: self.chaining_value,
+            buffer: self.buffer.clone(),
+            length: self.length,
+        }
+    }
+}
+
+impl<C> MerkleDamgard<C>
+where
+    C: CompressionFunction,
+{
+    // State after the last complete block. Together with `buffered` this is
+    // enough to suspend a session and resume it later.
+    pub fn state(&self) -> State<C> {
+        State::new(self.chaining_value, self.length - self.buffer.len())
+    }
+
+    // Bytes waiting for a block to fill up.
+    pub fn buffered(&self) -> &[u8] {
+        &self.buffer
+    }
+
+    // Appends bytes to the buffer, compressing every completed block.
+    fn absorb(&mut self, mut bytes: &[u8]) {
+        if !self.buffer.is_empty() {
+            let taken = (C::BLOCK_SIZE - self.buffer.len()).min(bytes.len());
+            self.buffer.extend(&bytes[..taken]);
+            bytes = &bytes[taken..];
+
+            if self.buffer.len() < C::BLOCK_SIZE {
+                return;
+            }
+            compress_block::<C>(&mut self.chaining_value, &self.buffer);
+            self.buffer.clear();
+        }
+
+        let mut chunks = bytes.chunks_exact(C::BLOCK_SIZE);
+        for chunk in &mut chunks {
+            compress_block::<C>(&mut self.chaining_value, chunk);
+        }
+        self.buffer.extend(chunks.remainder());
+    }
+}
+
+impl<C> Hasher for MerkleDamgard<C>
+where
+    C: CompressionFunction,
+{
+    const BLOCK_SIZE: usize = C::BLOCK_SIZE;
+
+    type State = State<C>;
+    type Digest = C::Digest;
+
+    fn new() -> Self {
+        MerkleDamgard::from_state(State::new(C::IV, 0))
+    }
+
+    fn from_state(state: Self::State) -> Self {
+        MerkleDamgard {
+            chaining_value: state.chaining_value,
+            buffer: Vec::with_capacity(C::BLOCK_SIZE),
+            length: state.processed_length,
+        }
+    }
+
+    fn update<T>(&mut self, message: T)
+    where
+        T: AsBytes,
+    {
+        let bytes = message.as_bytes();
+        self.length += bytes.len();
+        self.absorb(bytes);
+    }
+
+    fn finalize(mut self) -> Self::Digest {
+        let padding = md_padding(C::BLOCK_SIZE, C::LENGTH_SIZE, self.length, C::ENDIAN);
+        self.absorb(&padding);
+        chaining_value_to_digest::<C>(&self.chaining_value)
+    }
+
+    fn reset(&mut self) {
+        *self = MerkleDamgard::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A deliberately weak hash: 16 byte blocks, a 64-bit state and a single
+    // add-rotate-xor pass.
+    struct ToyCompression;
+
+    impl CompressionFunction for ToyCompression {
+        type Word = u32;
+        type ChainingValue = [u32; 2];
+        type Digest = [u8; 8];
+
+        const BLOCK_SIZE: usize = 16;
+        const LENGTH_SIZE: usize = 8;
+        const ENDIAN: Endian = Endian::Big;
+        const IV: Self::ChainingValue = [0x01234567, 0x89ABCDEF];
+
+        fn compress(state: &mut Self::ChainingValue, words: &[u32]) {
+            for word in words {
+                state[0] = state[0].wrapping_add(*word).rotate_left(7) ^ state[1];
+                state[1] = state[1].rotate_left(13).wrapping_add(state[0]);
+            }
+        }
+    }
+
+    type Toy = MerkleDamgard<ToyCompression>;
+
+    #[test]
+    fn test_toy_hash() {
+        let message = "a toy Merkle-Damgard hash";
+
+        // By hand: pad, split into blocks, compress, serialize.
+        let padded = [
+            message.as_bytes(),
+            md_padding(16, 8, message.len(), Endian::Big).as_slice(),
+        ]
+        .concat();
+        let mut state = ToyCompression::IV;
+        for block in padded.chunks(16) {
+            compress_block::<ToyCompression>(&mut state, block);
+        }
+        let expected: [u8; 8] = chaining_value_to_digest::<ToyCompression>(&state);
+
+        let mut hasher = Toy::new();
+        for piece in message.as_bytes().chunks(5) {
+            hasher.update(piece);
+        }
+        assert_eq!(hasher.finalize(), expected);
+    }
+
+    #[test]
+    fn test_state_from_digest() {
+        let mut hasher = Toy::new();
+        hasher.update("abc");
+        let digest = hasher.finalize();
+
+        let state = State::<ToyCompression>::from_digest(&digest, 16).unwrap();
+        assert_eq!(state.processed_length, 16);
+        assert_eq!(state.to_digest(), digest);
+
+        // Resuming from the digest is the same as hashing "abc" || padding.
+        let padded = [b"abc".as_slice(), &md_padding(16, 8, 3, Endian::Big)].concat();
+        let mut resumed = Toy::from_state(state);
+        resumed.update("more");
+        let mut full = Toy::new();
+        full.update(padded.as_slice());
+        full.update("more");
+        assert_eq!(resumed.finalize(), full.finalize());
+    }
+
+    #[test]
+    fn test_state_for_suffix() {
+        let state = State::<ToyCompression>::for_suffix(ToyCompression::IV, 48, 16);
+        assert_eq!(state.processed_length, 32);
+    }
+
+    #[test]
+    #[should_panic(expected = "longer than the total length")]
+    fn test_state_for_long_suffix() {
+        State::<ToyCompression>::for_suffix(ToyCompression::IV, 10, 16);
+    }
+
+    #[test]
+    fn test_state_bytes() {
+        let mut hasher = Toy::new();
+        hasher.update([0x42; 40].as_slice());
+        let state = hasher.state();
+        assert_eq!(state.processed_length, 32);
+
+        let bytes = state.to_bytes();
+        assert_eq!(bytes.len(), 16);
+        assert_eq!(State::<ToyCompression>::from_bytes(&bytes), Some(state));
+        assert_eq!(State::<ToyCompression>::from_bytes(&bytes[1..]), None);
+    }
+
+    #[test]
+    fn test_state_and_resume() {
+        let message = [0x42; 100];
+
+        let mut hasher = Toy::new();
+        hasher.update(&message[..50]);
+
+        // Suspend mid-block: the state covers whole blocks and the rest is
+        // replayed from the buffer.
+        let state = hasher.state();
+        assert_eq!(state.processed_length, 48);
+        let mut resumed = Toy::from_state(state);
+        resumed.update(hasher.buffered());
+        resumed.update(&message[50..]);
+
+        let mut full = Toy::new();
+        full.update(message.as_slice());
+        assert_eq!(resumed.finalize(), full.finalize());
+    }
+}
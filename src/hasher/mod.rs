@@ -0,0 +1,391 @@
+use crate::common::{AsBytes, Endian};
+
+pub mod blake2;
+pub mod blake3;
+pub mod fnv;
+pub mod keccak;
+pub mod md4;
+pub mod md5;
+pub mod merkle_damgard;
+pub mod pedersen;
+pub mod poseidon;
+pub mod ripemd160;
+pub mod sha1;
+pub mod sha256;
+pub mod sha512;
+pub mod shake;
+pub mod siphash;
+pub mod trace;
+
+pub use merkle_damgard::{CompressionFunction, MerkleDamgard, State};
+pub use trace::{Event, NoTrace, Trace};
+
+// Type markers for primitives with configurable internals. Anything other
+// than the specified configuration is `NonStandard`, so a weakened variant
+// can't be passed off as the real thing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Standard;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NonStandard;
+
+// Streaming interface shared by the hashers. Input is buffered into whole
+// blocks and padding is only applied on `finalize`, so a message never has
+// to be held in memory at once.
+pub trait Hasher {
+    // Size of a compression function block in bytes.
+    const BLOCK_SIZE: usize;
+
+    // Everything carried between blocks, e.g. the chaining value and the
+    // number of bytes compressed so far.
+    type State;
+    type Digest;
+
+    fn new() -> Self;
+
+    // Resumes a session from a state taken on a block boundary.
+    fn from_state(state: Self::State) -> Self;
+
+    fn update<T>(&mut self, message: T)
+    where
+        T: AsBytes;
+
+    fn finalize(self) -> Self::Digest;
+
+    fn reset(&mut self);
+}
+
+pub(crate) fn md_padding(
+    block_size: usize,
+    length_size: usize,
+    message_length: usize,
+    endian: Endian,
+) -> Vec<u8> {
+    // Message length is in bytes. The length field is `length_size` bytes
+    // wide: 64 bits for 64 byte blocks and 128 bits for 128 byte blocks.
+    let length_in_bits: u128 = (message_length as u128) * 8;
+    let length_in_bytes: Vec<u8> = match endian {
+        Endian::Big => length_in_bits.to_be_bytes()[16 - length_size..].to_vec(),
+        Endian::Little => length_in_bits.to_le_bytes()[..length_size].to_vec(),
+    };
+
+    // Padding in bytes.
+    let remainder = (message_length + 1 + length_size) % block_size;
+    let mut zeros_to_pad = 0;
+
+    // Checks edge case if there are no zeros to pad when the message length and
+    // appended bit fill available slots.
+    if remainder > 0 {
+        zeros_to_pad = block_size - remainder;
+    }
+
+    // Append 1 bit and then add the rest of the padding.
+    let mut preprocessed_bytes: Vec<u8> = vec![1 << 7];
+    preprocessed_bytes.extend(vec![0; zeros_to_pad]);
+    preprocessed_bytes.extend(length_in_bytes);
+    preprocessed_bytes
+}
+
+pub fn md_padding_64(message_length: usize, endian: Endian) -> Vec<u8> {
+    md_padding(64, 8, message_length, endian)
+}
+
+pub fn md_padding_128(message_length: usize, endian: Endian) -> Vec<u8> {
+    md_padding(128, 16, message_length, endian)
+}
+
+// First `bits` bits of a digest, the last byte zero padded. None if the
+// digest is shorter than that.
+pub fn truncate(digest: &[u8], bits: usize) -> Option<Vec<u8>> {
+    if bits > 8 * digest.len() {
+        return None;
+    }
+
+    let mut truncated = digest[..bits.div_ceil(8)].to_vec();
+    if !bits.is_multiple_of(8) {
+        *truncated.last_mut().unwrap() &= 0xFF << (8 - bits % 8);
+    }
+    Some(truncated)
+}
+
+// SHA-512 cut down to its first n bytes, at most the full 64.
+pub fn sha512_n(bytes: &[u8], n: usize) -> Vec<u8> {
+    let digest = sha512::default(bytes);
+    truncate(&digest, 8 * n.min(digest.len())).unwrap()
+}
+
+pub fn sha1<T>(message: T) -> [u8; 20]
+where
+    T: AsBytes,
+{
+    sha1::default(message)
+}
+
+// SHA-1 with collision detection: None for messages carrying a block of a
+// known collision attack.
+pub fn sha1_checked<T>(message: T) -> Option<[u8; 20]>
+where
+    T: AsBytes,
+{
+    sha1::checked(message)
+}
+
+pub fn sha256<T>(message: T) -> [u8; 32]
+where
+    T: AsBytes,
+{
+    sha256::default(message)
+}
+
+pub fn sha224<T>(message: T) -> [u8; 28]
+where
+    T: AsBytes,
+{
+    sha256::default_224(message)
+}
+
+pub fn sha512<T>(message: T) -> [u8; 64]
+where
+    T: AsBytes,
+{
+    sha512::default(message)
+}
+
+pub fn sha384<T>(message: T) -> [u8; 48]
+where
+    T: AsBytes,
+{
+    sha512::default_384(message)
+}
+
+pub fn sha3_224<T>(message: T) -> [u8; 28]
+where
+    T: AsBytes,
+{
+    keccak::sha3_224(message)
+}
+
+pub fn sha3_256<T>(message: T) -> [u8; 32]
+where
+    T: AsBytes,
+{
+    keccak::sha3_256(message)
+}
+
+pub fn sha3_384<T>(message: T) -> [u8; 48]
+where
+    T: AsBytes,
+{
+    keccak::sha3_384(message)
+}
+
+pub fn sha3_512<T>(message: T) -> [u8; 64]
+where
+    T: AsBytes,
+{
+    keccak::sha3_512(message)
+}
+
+pub fn keccak256<T>(message: T) -> [u8; 32]
+where
+    T: AsBytes,
+{
+    keccak::keccak256(message)
+}
+
+pub fn blake2b<T>(message: T) -> [u8; 64]
+where
+    T: AsBytes,
+{
+    blake2::blake2b(message, 64).try_into().unwrap()
+}
+
+pub fn blake2s<T>(message: T) -> [u8; 32]
+where
+    T: AsBytes,
+{
+    blake2::blake2s(message, 32).try_into().unwrap()
+}
+
+pub fn md4<T>(message: T) -> [u8; 16]
+where
+    T: AsBytes,
+{
+    md4::default(message)
+}
+
+pub fn md5<T>(message: T) -> [u8; 16]
+where
+    T: AsBytes,
+{
+    md5::default(message)
+}
+
+pub fn pedersen<T>(message: T) -> [u8; 32]
+where
+    T: AsBytes,
+{
+    pedersen::default(message)
+}
+
+pub fn ripemd160<T>(message: T) -> [u8; 20]
+where
+    T: AsBytes,
+{
+    ripemd160::default(message)
+}
+
+// Bitcoin's HASH160, used to turn public keys and scripts into addresses.
+pub fn hash160<T>(message: T) -> [u8; 20]
+where
+    T: AsBytes,
+{
+    ripemd160(sha256(message).as_slice())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sha1() {
+        let empty_output = sha1("");
+        let dog_output = sha1("The quick brown fox jumps over the lazy dog");
+        let cog_output = sha1("The quick brown fox jumps over the lazy cog");
+        let long_output = sha1(
+            "abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmn\
+            hijklmnoijklmnopjklmnopqklmnopqrlmnopqrsmnopqrstnopqrstu",
+        );
+        let edge_case_output = sha1(
+            [
+                99, 26, 143, 85, 115, 125, 249, 19, 75, 135, 222, 119, 109, 98, 105, 96, 2, 41,
+                217, 139, 169, 160, 123, 50, 113, 131, 231, 95, 116, 91, 204, 125, 57, 255, 248,
+                23, 109, 181, 125, 51, 155, 185, 229, 133, 69, 150, 225, 19, 208, 36, 183, 214, 2,
+                145, 252,
+            ]
+            .as_slice(),
+        );
+
+        let expected_empty_output: [u8; 20] = [
+            0xDA, 0x39, 0xA3, 0xEE, 0x5E, 0x6B, 0x4B, 0x0D, 0x32, 0x55, 0xBF, 0xEF, 0x95, 0x60,
+            0x18, 0x90, 0xAF, 0xD8, 0x07, 0x09,
+        ];
+        let expected_dog_output: [u8; 20] = [
+            0x2F, 0xD4, 0xE1, 0xC6, 0x7A, 0x2D, 0x28, 0xFC, 0xED, 0x84, 0x9E, 0xE1, 0xBB, 0x76,
+            0xE7, 0x39, 0x1B, 0x93, 0xEB, 0x12,
+        ];
+        let expected_cog_output: [u8; 20] = [
+            0xDE, 0x9F, 0x2C, 0x7F, 0xD2, 0x5E, 0x1B, 0x3A, 0xFA, 0xD3, 0xE8, 0x5A, 0x0B, 0xD1,
+            0x7D, 0x9B, 0x10, 0x0D, 0xB4, 0xB3,
+        ];
+        let expected_long_output: [u8; 20] = [
+            0xA4, 0x9B, 0x24, 0x46, 0xA0, 0x2C, 0x64, 0x5B, 0xF4, 0x19, 0xF9, 0x95, 0xB6, 0x70,
+            0x91, 0x25, 0x3A, 0x04, 0xA2, 0x59,
+        ];
+        let expected_edge_case_output: [u8; 20] = [
+            0x03, 0x17, 0xE3, 0x8D, 0x99, 0xCD, 0xBA, 0x10, 0xF6, 0x05, 0x77, 0x6B, 0xF3, 0xCF,
+            0xCD, 0x89, 0xBC, 0xDE, 0x76, 0xBB,
+        ];
+
+        assert_eq!(empty_output, expected_empty_output);
+        assert_eq!(dog_output, expected_dog_output);
+        assert_eq!(cog_output, expected_cog_output);
+        assert_eq!(long_output, expected_long_output);
+        assert_eq!(edge_case_output, expected_edge_case_output);
+    }
+
+    #[test]
+    fn test_sha512_n() {
+        let test_vec: Vec<u8> = vec![1];
+        let hash_of_test_vec = sha512_n(test_vec.as_slice(), 10);
+        assert_eq!(hash_of_test_vec.len(), 10);
+
+        let other_hash_of_test_vec = sha512_n(test_vec.as_slice(), 6);
+        let another_hash = sha512_n(vec![2].as_slice(), 6);
+
+        assert_eq!(hash_of_test_vec[..6], other_hash_of_test_vec);
+        assert_eq!(other_hash_of_test_vec, sha512(test_vec.as_slice())[..6]);
+        assert_ne!(another_hash, other_hash_of_test_vec);
+        assert_eq!(sha512_n(test_vec.as_slice(), 100).len(), 64);
+    }
+
+    #[test]
+    fn test_truncate() {
+        let digest = [0xAB, 0xCD, 0xEF];
+        assert_eq!(truncate(&digest, 0), Some(vec![]));
+        assert_eq!(truncate(&digest, 4), Some(vec![0xA0]));
+        assert_eq!(truncate(&digest, 12), Some(vec![0xAB, 0xC0]));
+        assert_eq!(truncate(&digest, 16), Some(vec![0xAB, 0xCD]));
+        assert_eq!(truncate(&digest, 24), Some(digest.to_vec()));
+        assert_eq!(truncate(&digest, 25), None);
+    }
+
+    #[test]
+    fn test_hash160() {
+        let hello_output = hash160("hello");
+        // Uncompressed public key from the Bitcoin wiki address walkthrough.
+        let public_key_output = hash160(
+            [
+                0x04, 0x50, 0x86, 0x3A, 0xD6, 0x4A, 0x87, 0xAE, 0x8A, 0x2F, 0xE8, 0x3C, 0x1A, 0xF1,
+                0xA8, 0x40, 0x3C, 0xB5, 0x3F, 0x53, 0xE4, 0x86, 0xD8, 0x51, 0x1D, 0xAD, 0x8A, 0x04,
+                0x88, 0x7E, 0x5B, 0x23, 0x52, 0x2C, 0xD4, 0x70, 0x24, 0x34, 0x53, 0xA2, 0x99, 0xFA,
+                0x9E, 0x77, 0x23, 0x77, 0x16, 0x10, 0x3A, 0xBC, 0x11, 0xA1, 0xDF, 0x38, 0x85, 0x5E,
+                0xD6, 0xF2, 0xEE, 0x18, 0x7E, 0x9C, 0x58, 0x2B, 0xA6,
+            ]
+            .as_slice(),
+        );
+
+        let expected_hello_output: [u8; 20] = [
+            0xB6, 0xA9, 0xC8, 0xC2, 0x30, 0x72, 0x2B, 0x7C, 0x74, 0x83, 0x31, 0xA8, 0xB4, 0x50,
+            0xF0, 0x55, 0x66, 0xDC, 0x7D, 0x0F,
+        ];
+        let expected_public_key_output: [u8; 20] = [
+            0x01, 0x09, 0x66, 0x77, 0x60, 0x06, 0x95, 0x3D, 0x55, 0x67, 0x43, 0x9E, 0x5E, 0x39,
+            0xF8, 0x6A, 0x0D, 0x27, 0x3B, 0xEE,
+        ];
+
+        assert_eq!(hello_output, expected_hello_output);
+        assert_eq!(public_key_output, expected_public_key_output);
+    }
+
+    #[test]
+    fn test_md4() {
+        let empty_output = md4("");
+        let a_output = md4("a");
+        let medium_output = md4("abcdefghijklmnopqrstuvwxyz");
+        let long_output = md4("ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789");
+        let edge_case_output = md4([
+            99, 26, 143, 85, 115, 125, 249, 19, 75, 135, 222, 119, 109, 98, 105, 96, 2, 41, 217,
+            139, 169, 160, 123, 50, 113, 131, 231, 95, 116, 91, 204, 125, 57, 255, 248, 23, 109,
+            181, 125, 51, 155, 185, 229, 133, 69, 150, 225, 19, 208, 36, 183, 214, 2, 145, 252,
+        ]
+        .as_slice());
+
+        let expected_empty_output: [u8; 16] = [
+            0x31, 0xD6, 0xCF, 0xE0, 0xD1, 0x6A, 0xE9, 0x31, 0xB7, 0x3C, 0x59, 0xD7, 0xE0, 0xC0,
+            0x89, 0xC0,
+        ];
+        let expected_a_output: [u8; 16] = [
+            0xBD, 0xE5, 0x2C, 0xB3, 0x1D, 0xE3, 0x3E, 0x46, 0x24, 0x5E, 0x05, 0xFB, 0xDB, 0xD6,
+            0xFB, 0x24,
+        ];
+        let expected_medium_output: [u8; 16] = [
+            0xD7, 0x9E, 0x1C, 0x30, 0x8A, 0xA5, 0xBB, 0xCD, 0xEE, 0xA8, 0xED, 0x63, 0xDF, 0x41,
+            0x2D, 0xA9,
+        ];
+        let expected_long_output: [u8; 16] = [
+            0x04, 0x3F, 0x85, 0x82, 0xF2, 0x41, 0xDB, 0x35, 0x1C, 0xE6, 0x27, 0xE1, 0x53, 0xE7,
+            0xF0, 0xE4,
+        ];
+        let expected_edge_case_output: [u8; 16] = [
+            0x67, 0x04, 0xB8, 0x49, 0x3D, 0xDC, 0x39, 0x94, 0x82, 0xF0, 0x48, 0x5F, 0x73, 0x1E,
+            0x64, 0x63,
+        ];
+
+        assert_eq!(empty_output, expected_empty_output);
+        assert_eq!(a_output, expected_a_output);
+        assert_eq!(medium_output, expected_medium_output);
+        assert_eq!(long_output, expected_long_output);
+        assert_eq!(edge_case_output, expected_edge_case_output);
+    }
+}
@@ -0,0 +1,439 @@
+use super::blake2::{Blake2s, Params};
+use super::*;
+use crate::math::curve::{BabyJubjub, BabyJubjubPoint, BabyJubjubScalar, TwistedEdwards};
+use crate::math::field::Bn254Fr;
+use crate::math::uint::U256;
+use std::sync::RwLock;
+
+// Pedersen hash over Baby Jubjub, following the construction Zcash Sapling
+// uses on Jubjub. The message is split into segments of 3-bit windows, each
+// window is encoded as a small signed digit and every segment is a scalar
+// for its own generator:
+//
+//   H(m) = sum_i <segment_i> * G_i
+//   <segment> = sum_j enc(window_j) * 2^(4 * j)
+//   enc(s0, s1, s2) = (1 - 2 * s2) * (1 + s0 + 2 * s1)
+//
+// Collision resistance reduces to the discrete log relation problem between
+// the generators, so they are derived by hashing to the curve and nobody
+// knows their logs with respect to each other.
+// https://zips.z.cash/protocol/protocol.pdf#concretepedersenhash
+
+pub type Point = BabyJubjubPoint;
+pub type Scalar = BabyJubjubScalar;
+
+pub const DEFAULT_PERSONALIZATION: &[u8] = b"brykto_PedersenHash";
+
+// Domain separation for the BLAKE2s calls behind group_hash.
+const GROUP_HASH_PERSONALIZATION: &[u8] = b"brykt_GH";
+
+const WINDOW_BITS: usize = 3;
+
+// Largest segment whose scalars stay below half the subgroup order, so
+// distinct segments can't wrap onto the same scalar.
+const WINDOWS_PER_SEGMENT: usize = 62;
+
+pub const SEGMENT_BITS: usize = WINDOW_BITS * WINDOWS_PER_SEGMENT;
+
+// Deterministic generator for (personalization, index): try-and-increment
+// hashing into the curve, then clearing the cofactor.
+pub fn group_hash(personalization: &[u8], index: u32) -> Point {
+    let params = Params {
+        personalization: GROUP_HASH_PERSONALIZATION.to_vec(),
+        ..Params::new(32)
+    };
+
+    for counter in 0..=u8::MAX {
+        let mut hasher = Blake2s::with_params(&params).unwrap();
+        hasher.update(personalization);
+        hasher.update(index.to_le_bytes().as_slice());
+        hasher.update([counter].as_slice());
+        let mut digest: [u8; 32] = hasher.finalize().try_into().unwrap();
+
+        // Top bit picks the sign of x, the rest is the candidate y.
+        let x_is_odd = digest[31] >> 7 == 1;
+        digest[31] &= 0x7F;
+        let y = Bn254Fr::reduce(U256::from_le_bytes(digest));
+
+        // Solve a * x^2 + y^2 = 1 + d * x^2 * y^2 for x.
+        let y2 = y.square();
+        let a = Bn254Fr::reduce(BabyJubjub::A);
+        let d = Bn254Fr::reduce(BabyJubjub::D);
+        let x2 = match (a - d * y2).inverse() {
+            Some(denominator) => (Bn254Fr::one() - y2) * denominator,
+            None => continue,
+        };
+        let mut x = match x2.sqrt() {
+            Some(x) => x,
+            None => continue,
+        };
+        if x.is_odd() != x_is_odd {
+            x = -x;
+        }
+
+        let point = Point::from_affine(x, y).unwrap().clear_cofactor();
+        if !point.is_identity() {
+            return point;
+        }
+    }
+    // Each attempt succeeds with probability about 1/2.
+    unreachable!("no generator found in 256 attempts")
+}
+
+// Encodes up to SEGMENT_BITS bits as a scalar, zero padding the last window.
+fn segment_scalar(bits: &[bool]) -> Scalar {
+    let mut scalar = Scalar::zero();
+    let mut power = Scalar::one();
+    let sixteen = Scalar::from_u64(16);
+
+    for window in bits.chunks(WINDOW_BITS) {
+        let bit = |i: usize| window.get(i).copied().unwrap_or(false) as u64;
+        let magnitude = Scalar::from_u64(1 + bit(0) + 2 * bit(1)) * power;
+        if bit(2) == 1 {
+            scalar = scalar - magnitude;
+        } else {
+            scalar = scalar + magnitude;
+        }
+        power = power * sixteen;
+    }
+    scalar
+}
+
+// Least significant bit of each byte first.
+pub fn bytes_to_bits(bytes: &[u8]) -> Vec<bool> {
+    bytes
+        .iter()
+        .flat_map(|byte| (0..8).map(move |i| (byte >> i) & 1 == 1))
+        .collect()
+}
+
+// Reference implementation: one scalar multiplication per segment.
+pub fn hash_bits_naive(personalization: &[u8], bits: &[bool]) -> Point {
+    bits.chunks(SEGMENT_BITS)
+        .enumerate()
+        .fold(Point::identity(), |sum, (i, segment)| {
+            sum + group_hash(personalization, i as u32).mul_scalar(&segment_scalar(segment))
+        })
+}
+
+// Precomputed multiples of one generator: windows[j][k] = (k + 1) * 16^j * G,
+// so hashing a window is a table lookup and an addition.
+struct GeneratorTable {
+    windows: Vec<[Point; 4]>,
+}
+
+impl GeneratorTable {
+    fn new(generator: Point) -> Self {
+        let mut windows = Vec::with_capacity(WINDOWS_PER_SEGMENT);
+        let mut base = generator;
+        for _ in 0..WINDOWS_PER_SEGMENT {
+            let double = base.double();
+            let quadruple = double.double();
+            windows.push([base, double, double + base, quadruple]);
+            base = quadruple.double().double();
+        }
+        GeneratorTable { windows }
+    }
+
+    fn hash_segment(&self, bits: &[bool]) -> Point {
+        let mut sum = Point::identity();
+        for (window, multiples) in bits.chunks(WINDOW_BITS).zip(&self.windows) {
+            let bit = |i: usize| window.get(i).copied().unwrap_or(false) as usize;
+            let multiple = multiples[bit(0) + 2 * bit(1)];
+            if bit(2) == 1 {
+                sum = sum - multiple;
+            } else {
+                sum = sum + multiple;
+            }
+        }
+        sum
+    }
+}
+
+// Pedersen hash for one personalization, with generator tables for messages
+// of up to max_bits bits.
+pub struct PedersenHasher {
+    personalization: Vec<u8>,
+    tables: Vec<GeneratorTable>,
+}
+
+impl PedersenHasher {
+    pub fn new(personalization: &[u8], max_bits: usize) -> Self {
+        let mut hasher = PedersenHasher {
+            personalization: personalization.to_vec(),
+            tables: vec![],
+        };
+        hasher.reserve(max_bits);
+        hasher
+    }
+
+    // Adds the tables missing for messages of up to max_bits bits.
+    pub fn reserve(&mut self, max_bits: usize) {
+        for i in self.tables.len()..max_bits.div_ceil(SEGMENT_BITS) {
+            let generator = group_hash(&self.personalization, i as u32);
+            self.tables.push(GeneratorTable::new(generator));
+        }
+    }
+
+    pub fn max_bits(&self) -> usize {
+        self.tables.len() * SEGMENT_BITS
+    }
+
+    // None if the message is longer than the tables cover. Messages are zero
+    // padded to whole windows, so callers should fix the message length.
+    pub fn hash_bits(&self, bits: &[bool]) -> Option<Point> {
+        if bits.len() > self.max_bits() {
+            return None;
+        }
+        Some(
+            bits.chunks(SEGMENT_BITS)
+                .zip(&self.tables)
+                .fold(Point::identity(), |sum, (segment, table)| {
+                    sum + table.hash_segment(segment)
+                }),
+        )
+    }
+
+    pub fn hash<T>(&self, message: T) -> Option<Point>
+    where
+        T: AsBytes,
+    {
+        self.hash_bits(&bytes_to_bits(message.as_bytes()))
+    }
+}
+
+// Tables for the default personalization, built on first use and grown to
+// the longest message `default` has seen, up to MAX_CACHED_BITS.
+static DEFAULT_HASHER: RwLock<Option<PedersenHasher>> = RwLock::new(None);
+
+// Each segment's table is about 32 KB, so the shared tables stop at 8
+// segments, enough for hashing pairs of field elements.
+const MAX_CACHED_BITS: usize = 8 * SEGMENT_BITS;
+
+// The x coordinate of the hash, big endian.
+pub fn default<T>(message: T) -> [u8; 32]
+where
+    T: AsBytes,
+{
+    let bits = bytes_to_bits(message.as_bytes());
+    let cached = DEFAULT_HASHER
+        .read()
+        .unwrap()
+        .as_ref()
+        .and_then(|hasher| hasher.hash_bits(&bits));
+    let point = cached.unwrap_or_else(|| {
+        // Longer messages would pin their tables for good, so they take one
+        // scalar multiplication per segment instead.
+        if bits.len() > MAX_CACHED_BITS {
+            return hash_bits_naive(DEFAULT_PERSONALIZATION, &bits);
+        }
+        let mut cache = DEFAULT_HASHER.write().unwrap();
+        let hasher = cache.get_or_insert_with(|| PedersenHasher::new(DEFAULT_PERSONALIZATION, 0));
+        hasher.reserve(bits.len());
+        hasher.hash_bits(&bits).unwrap()
+    });
+    let (x, _) = point.to_affine();
+    x.to_be_bytes()
+}
+
+// Pedersen commitment value * G + blinding * H. The blinding makes it
+// perfectly hiding: every value has some blinding that opens a given
+// commitment. Binding is only computational, opening to a second value means
+// knowing the discrete log of H to base G. The hash above is the unblinded
+// version: binding, but not hiding when the message space is small.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Commitment {
+    pub value_generator: Point,
+    pub blinding_generator: Point,
+}
+
+impl Commitment {
+    pub fn new(personalization: &[u8]) -> Self {
+        Commitment {
+            value_generator: group_hash(personalization, 0),
+            blinding_generator: group_hash(personalization, 1),
+        }
+    }
+
+    // Lets tests build generators with a known discrete log.
+    pub fn with_generators(value_generator: Point, blinding_generator: Point) -> Self {
+        Commitment {
+            value_generator,
+            blinding_generator,
+        }
+    }
+
+    pub fn commit(&self, value: &Scalar, blinding: &Scalar) -> Point {
+        self.value_generator.mul_scalar(value) + self.blinding_generator.mul_scalar(blinding)
+    }
+
+    pub fn verify(&self, commitment: &Point, value: &Scalar, blinding: &Scalar) -> bool {
+        self.commit(value, blinding) == *commitment
+    }
+
+    // Given H = trapdoor * G, finds the blinding that opens the commitment to
+    // (value, blinding) as new_value instead:
+    // new_blinding = blinding + (value - new_value) / trapdoor.
+    pub fn equivocate(
+        value: &Scalar,
+        blinding: &Scalar,
+        new_value: &Scalar,
+        trapdoor: &Scalar,
+    ) -> Option<Scalar> {
+        Some(*blinding + (*value - *new_value) * trapdoor.inverse()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_group_hash() {
+        let g0 = group_hash(DEFAULT_PERSONALIZATION, 0);
+        let g1 = group_hash(DEFAULT_PERSONALIZATION, 1);
+        let other = group_hash(b"other", 0);
+
+        assert!(g0.is_in_prime_subgroup());
+        assert!(g1.is_in_prime_subgroup());
+        assert_eq!(g0, group_hash(DEFAULT_PERSONALIZATION, 0));
+        assert_ne!(g0, g1);
+        assert_ne!(g0, other);
+    }
+
+    #[test]
+    fn test_segment_encoding() {
+        // Windows (s0, s1, s2) = 100, 010, 111 encode to 2, 3 and -4.
+        let bits = [true, false, false, false, true, false, true, true, true];
+        let expected = Scalar::from_u64(2) + Scalar::from_u64(3 * 16) - Scalar::from_u64(4 * 256);
+        assert_eq!(segment_scalar(&bits), expected);
+
+        // The last window is zero padded.
+        assert_eq!(segment_scalar(&[true]), Scalar::from_u64(2));
+        assert_eq!(segment_scalar(&[]), Scalar::zero());
+    }
+
+    #[test]
+    fn test_pedersen_tables_match_naive() {
+        let message = b"The quick brown fox jumps over the lazy dog".as_slice();
+        let bits = bytes_to_bits(message);
+        assert!(bits.len() > SEGMENT_BITS);
+
+        let hasher = PedersenHasher::new(b"test", bits.len());
+        assert_eq!(hasher.max_bits(), 2 * SEGMENT_BITS);
+        assert_eq!(
+            hasher.hash_bits(&bits).unwrap(),
+            hash_bits_naive(b"test", &bits)
+        );
+        assert_eq!(hasher.hash_bits(&vec![true; 2 * SEGMENT_BITS + 1]), None);
+    }
+
+    #[test]
+    fn test_pedersen_hash() {
+        let hasher = PedersenHasher::new(DEFAULT_PERSONALIZATION, 256);
+        let a = hasher.hash("a").unwrap();
+        let b = hasher.hash("b").unwrap();
+
+        assert!(a.is_in_prime_subgroup());
+        assert_ne!(a, b);
+        assert_eq!(a.to_affine().0.to_be_bytes(), default("a"));
+        assert_eq!(hasher.hash("").unwrap(), Point::identity());
+
+        // The personalization changes every generator.
+        let other = PedersenHasher::new(b"other", 256);
+        assert_ne!(other.hash("a").unwrap(), a);
+    }
+
+    #[test]
+    fn test_pedersen_reserve() {
+        let message = [0xA5; 100];
+        let mut hasher = PedersenHasher::new(DEFAULT_PERSONALIZATION, 8);
+        assert_eq!(hasher.hash(message.as_slice()), None);
+
+        // Grown tables are the ones a new hasher would build.
+        hasher.reserve(800);
+        let expected = PedersenHasher::new(DEFAULT_PERSONALIZATION, 800);
+        assert_eq!(hasher.max_bits(), expected.max_bits());
+        let point = hasher.hash(message.as_slice()).unwrap();
+        assert_eq!(point, expected.hash(message.as_slice()).unwrap());
+
+        // `default` grows its shared tables the same way.
+        assert_eq!(
+            point.to_affine().0.to_be_bytes(),
+            default(message.as_slice())
+        );
+        assert_eq!(
+            hasher.hash("a").unwrap().to_affine().0.to_be_bytes(),
+            default("a")
+        );
+
+        // Beyond the cap the shared tables don't grow.
+        let long_message = [0x5A; MAX_CACHED_BITS / 8 + 1];
+        let expected = hash_bits_naive(
+            DEFAULT_PERSONALIZATION,
+            &bytes_to_bits(long_message.as_slice()),
+        );
+        assert_eq!(
+            default(long_message.as_slice()),
+            expected.to_affine().0.to_be_bytes()
+        );
+        let cache = DEFAULT_HASHER.read().unwrap();
+        assert!(cache.as_ref().unwrap().max_bits() <= MAX_CACHED_BITS);
+    }
+
+    #[test]
+    fn test_pedersen_hash_is_not_hiding() {
+        // Without blinding a small message space is searched exhaustively.
+        let hasher = PedersenHasher::new(DEFAULT_PERSONALIZATION, 8);
+        let vote = 7u8;
+        let digest = hasher.hash([vote].as_slice()).unwrap();
+
+        let recovered =
+            (0..=u8::MAX).find(|guess| hasher.hash([*guess].as_slice()) == Some(digest));
+        assert_eq!(recovered, Some(vote));
+    }
+
+    #[test]
+    fn test_commitment_hiding_and_binding() {
+        let scheme = Commitment::new(b"commitment");
+        let value = Scalar::from_u64(7);
+        let blinding = Scalar::from_u64(123456789);
+        let commitment = scheme.commit(&value, &blinding);
+
+        assert!(scheme.verify(&commitment, &value, &blinding));
+        assert!(!scheme.verify(&commitment, &Scalar::from_u64(8), &blinding));
+
+        // Fresh blinding, fresh commitment: the same value can't be spotted.
+        let other_blinding = Scalar::from_u64(987654321);
+        assert_ne!(scheme.commit(&value, &other_blinding), commitment);
+
+        // With H = t * G the committer can open to anything, so binding rests
+        // entirely on t being unknown.
+        let trapdoor = Scalar::from_u64(42);
+        let g = scheme.value_generator;
+        let rigged = Commitment::with_generators(g, g.mul_scalar(&trapdoor));
+        let commitment = rigged.commit(&value, &blinding);
+
+        let new_value = Scalar::from_u64(1000);
+        let new_blinding =
+            Commitment::equivocate(&value, &blinding, &new_value, &trapdoor).unwrap();
+        assert!(rigged.verify(&commitment, &value, &blinding));
+        assert!(rigged.verify(&commitment, &new_value, &new_blinding));
+        assert_eq!(
+            Commitment::equivocate(&value, &blinding, &new_value, &Scalar::zero()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_commitment_is_homomorphic() {
+        let scheme = Commitment::new(b"commitment");
+        let (v1, r1) = (Scalar::from_u64(10), Scalar::from_u64(111));
+        let (v2, r2) = (Scalar::from_u64(32), Scalar::from_u64(222));
+
+        assert_eq!(
+            scheme.commit(&v1, &r1) + scheme.commit(&v2, &r2),
+            scheme.commit(&(v1 + v2), &(r1 + r2))
+        );
+    }
+}
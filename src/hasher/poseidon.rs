@@ -0,0 +1,426 @@
+use super::trace::{Event, NoTrace, Trace};
+use crate::math::field::{Bn254Fr, Bn254FrParams, FieldParams, Fp};
+use crate::math::uint::U256;
+use std::collections::VecDeque;
+use std::sync::OnceLock;
+
+// Poseidon, a hash built from few field multiplications so it stays cheap
+// inside arithmetic circuits. The permutation is a substitution-permutation
+// network over `width` field elements: every round adds constants, applies
+// x^alpha to all elements (full rounds) or only the first one (partial
+// rounds), then mixes with an MDS matrix. Full rounds are split evenly
+// before and after the partial ones.
+// https://eprint.iacr.org/2019/458
+// https://extgit.iaik.tugraz.at/krypto/hadeshash
+
+// Round counts for alpha = 5 over 254-bit fields, indexed by width - 2,
+// as used by the reference and circomlib.
+const BN254_PARTIAL_ROUNDS: [usize; 16] = [
+    56, 57, 56, 60, 60, 63, 64, 63, 60, 66, 60, 65, 70, 60, 64, 68,
+];
+pub const BN254_FULL_ROUNDS: usize = 8;
+
+// State element names in traces, enough for the standard widths. Elements
+// of wider instances go unnamed.
+const STATE_NAMES: [&str; 17] = [
+    "s0", "s1", "s2", "s3", "s4", "s5", "s6", "s7", "s8", "s9", "s10", "s11", "s12", "s13", "s14",
+    "s15", "s16",
+];
+
+// Self-shrinking Grain LFSR from the reference parameter script, seeded
+// with the instance description so every parameter set gets its own
+// constants.
+struct Grain {
+    state: VecDeque<bool>,
+}
+
+impl Grain {
+    fn new(field_size: usize, width: usize, full_rounds: usize, partial_rounds: usize) -> Self {
+        let mut state = VecDeque::with_capacity(80);
+        // Field type 1 (prime field) in 2 bits, S-box type 0 (x^alpha) in 4.
+        let fields = [
+            (1, 2),
+            (0, 4),
+            (field_size, 12),
+            (width, 12),
+            (full_rounds, 10),
+            (partial_rounds, 10),
+        ];
+        for (value, bits) in fields {
+            for i in (0..bits).rev() {
+                state.push_back((value >> i) & 1 == 1);
+            }
+        }
+        state.extend([true; 30]);
+
+        let mut grain = Grain { state };
+        for _ in 0..160 {
+            grain.step();
+        }
+        grain
+    }
+
+    fn step(&mut self) -> bool {
+        let s = &self.state;
+        let bit = s[62] ^ s[51] ^ s[38] ^ s[23] ^ s[13] ^ s[0];
+        self.state.pop_front();
+        self.state.push_back(bit);
+        bit
+    }
+
+    // Bits come in pairs, the second is kept only when the first is set.
+    fn next_bit(&mut self) -> bool {
+        while !self.step() {
+            self.step();
+        }
+        self.step()
+    }
+
+    // Most significant bit first.
+    fn next_uint(&mut self, bits: usize) -> U256 {
+        let mut bytes = [0u8; 32];
+        for i in (0..bits).rev() {
+            if self.next_bit() {
+                bytes[31 - i / 8] |= 1 << (i % 8);
+            }
+        }
+        U256::from_be_bytes(bytes)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Params<P>
+where
+    P: FieldParams,
+{
+    pub width: usize,
+    pub alpha: u64,
+    pub full_rounds: usize,
+    pub partial_rounds: usize,
+    // width constants per round.
+    pub round_constants: Vec<Fp<P>>,
+    pub mds: Vec<Vec<Fp<P>>>,
+}
+
+impl<P> Params<P>
+where
+    P: FieldParams,
+{
+    // Generates round constants and MDS matrix the way the reference does.
+    // None unless x^alpha is a permutation and full rounds split evenly.
+    pub fn new(
+        width: usize,
+        alpha: u64,
+        full_rounds: usize,
+        partial_rounds: usize,
+    ) -> Option<Self> {
+        if width < 2 || !full_rounds.is_multiple_of(2) || alpha < 3 {
+            return None;
+        }
+        // x^alpha permutes the field iff gcd(alpha, p - 1) = 1.
+        let p_minus_one = P::MODULUS.overflowing_sub(&U256::ONE).0;
+        let (mut a, mut b) = (alpha, p_minus_one.rem_u64(alpha));
+        while b != 0 {
+            (a, b) = (b, a % b);
+        }
+        if a != 1 {
+            return None;
+        }
+
+        let field_size = P::MODULUS.bits();
+        let mut grain = Grain::new(field_size, width, full_rounds, partial_rounds);
+
+        // Rejection sampling, so constants are uniform in the field.
+        let round_constants = (0..(full_rounds + partial_rounds) * width)
+            .map(|_| loop {
+                if let Some(constant) = Fp::new(grain.next_uint(field_size)) {
+                    break constant;
+                }
+            })
+            .collect();
+
+        // Cauchy matrix 1 / (x_i + y_j) over distinct x and y. The reference
+        // additionally screens for infinitely long invariant subspace trails,
+        // which the standard instances pass on the first draw.
+        let mds = loop {
+            let elements: Vec<Fp<P>> = (0..2 * width)
+                .map(|_| Fp::reduce(grain.next_uint(field_size)))
+                .collect();
+            let distinct = (0..elements.len()).all(|i| (0..i).all(|j| elements[i] != elements[j]));
+            if !distinct {
+                continue;
+            }
+
+            let (xs, ys) = elements.split_at(width);
+            let mds: Option<Vec<Vec<Fp<P>>>> = xs
+                .iter()
+                .map(|x| ys.iter().map(|y| (*x + *y).inverse()).collect())
+                .collect();
+            if let Some(mds) = mds {
+                break mds;
+            }
+        };
+
+        Some(Params {
+            width,
+            alpha,
+            full_rounds,
+            partial_rounds,
+            round_constants,
+            mds,
+        })
+    }
+}
+
+impl Params<Bn254FrParams> {
+    // Standard x^5 instance over the BN254 scalar field, width 2 to 17.
+    pub fn bn254(width: usize) -> Option<Self> {
+        let partial_rounds = *BN254_PARTIAL_ROUNDS.get(width.checked_sub(2)?)?;
+        Params::new(width, 5, BN254_FULL_ROUNDS, partial_rounds)
+    }
+}
+
+pub struct Poseidon<P>
+where
+    P: FieldParams,
+{
+    params: Params<P>,
+}
+
+impl<P> Poseidon<P>
+where
+    P: FieldParams,
+{
+    pub fn new(params: Params<P>) -> Self {
+        Poseidon { params }
+    }
+
+    pub fn params(&self) -> &Params<P> {
+        &self.params
+    }
+
+    // None if the state isn't exactly width elements.
+    pub fn permute(&self, state: &[Fp<P>]) -> Option<Vec<Fp<P>>> {
+        self.permute_with(state, &mut NoTrace)
+    }
+
+    // `permute`, reporting the state after every round to `trace`. The
+    // input state is reported as block 0 and the output as block 1.
+    pub fn permute_traced(
+        &self,
+        state: &[Fp<P>],
+        trace: &mut dyn Trace<Fp<P>>,
+    ) -> Option<Vec<Fp<P>>> {
+        self.permute_with(state, trace)
+    }
+
+    fn permute_with<T>(&self, state: &[Fp<P>], trace: &mut T) -> Option<Vec<Fp<P>>>
+    where
+        T: Trace<Fp<P>> + ?Sized,
+    {
+        let params = &self.params;
+        if state.len() != params.width {
+            return None;
+        }
+        trace.record(Event::ChainingValue {
+            block: 0,
+            value: state,
+        });
+        let names = &STATE_NAMES[..params.width.min(STATE_NAMES.len())];
+
+        let alpha = U256::from_u64(params.alpha);
+        let half_full_rounds = params.full_rounds / 2;
+        let mut state = state.to_vec();
+
+        for (round, constants) in params.round_constants.chunks(params.width).enumerate() {
+            for (element, constant) in state.iter_mut().zip(constants) {
+                *element = *element + *constant;
+            }
+
+            let is_partial =
+                round >= half_full_rounds && round < half_full_rounds + params.partial_rounds;
+            if is_partial {
+                state[0] = state[0].pow(&alpha);
+            } else {
+                for element in state.iter_mut() {
+                    *element = element.pow(&alpha);
+                }
+            }
+
+            state = params
+                .mds
+                .iter()
+                .map(|row| {
+                    row.iter()
+                        .zip(&state)
+                        .fold(Fp::zero(), |sum, (entry, element)| sum + *entry * *element)
+                })
+                .collect();
+            trace.record(Event::Registers {
+                step: round,
+                names,
+                values: &state,
+            });
+        }
+        trace.record(Event::ChainingValue {
+            block: 1,
+            value: &state,
+        });
+        Some(state)
+    }
+
+    // Fixed length hash of width - 1 inputs: the state is a zero capacity
+    // element followed by the inputs, the output is the first element after
+    // the permutation. Matches circomlib's poseidon.
+    pub fn hash(&self, inputs: &[Fp<P>]) -> Option<Fp<P>> {
+        self.hash_with(inputs, &mut NoTrace)
+    }
+
+    // `hash`, reporting the permutation like `permute_traced`.
+    pub fn hash_traced(&self, inputs: &[Fp<P>], trace: &mut dyn Trace<Fp<P>>) -> Option<Fp<P>> {
+        self.hash_with(inputs, trace)
+    }
+
+    fn hash_with<T>(&self, inputs: &[Fp<P>], trace: &mut T) -> Option<Fp<P>>
+    where
+        T: Trace<Fp<P>> + ?Sized,
+    {
+        if inputs.len() + 1 != self.params.width {
+            return None;
+        }
+        let mut state = vec![Fp::zero()];
+        state.extend_from_slice(inputs);
+        Some(self.permute_with(&state, trace)?[0])
+    }
+}
+
+// Standard BN254 instances indexed by width - 2, generated on first use.
+// Generating the constants and MDS matrix costs far more than a hash.
+static BN254_INSTANCES: [OnceLock<Poseidon<Bn254FrParams>>; 16] = [const { OnceLock::new() }; 16];
+
+// Hash of 1 to 16 BN254 scalars with the standard parameters for that width.
+pub fn hash_bn254(inputs: &[Bn254Fr]) -> Option<Bn254Fr> {
+    let instance = BN254_INSTANCES.get(inputs.len().checked_sub(1)?)?;
+    let poseidon = instance.get_or_init(|| match Params::bn254(inputs.len() + 1) {
+        Some(params) => Poseidon::new(params),
+        None => unreachable!("standard widths go up to 17"),
+    });
+    poseidon.hash(inputs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fr(hex: &str) -> Bn254Fr {
+        Bn254Fr::new(U256::from_hex(hex)).unwrap()
+    }
+
+    fn frs(values: &[u64]) -> Vec<Bn254Fr> {
+        values
+            .iter()
+            .map(|value| Bn254Fr::from_u64(*value))
+            .collect()
+    }
+
+    #[test]
+    fn test_grain_parameters() {
+        // First round constant and MDS entry of poseidonperm_x5_254_3.
+        let params = Params::bn254(3).unwrap();
+        assert_eq!(params.round_constants.len(), 65 * 3);
+        assert_eq!(
+            params.round_constants[0],
+            fr("0EE9A592BA9A9518D05986D656F40C2114C4993C11BB29938D21D47304CD8E6E")
+        );
+        assert_eq!(
+            params.mds[0][0],
+            fr("109B7F411BA0E4C9B2B70CAF5C36A7B194BE7C11AD24378BFEDB68592BA8118B")
+        );
+    }
+
+    // Test vectors from the reference implementation.
+    // https://extgit.iaik.tugraz.at/krypto/hadeshash/-/blob/master/code/test_vectors.txt
+    #[test]
+    fn test_poseidon_bn254_width_3() {
+        let poseidon = Poseidon::new(Params::bn254(3).unwrap());
+        let output = poseidon.permute(&frs(&[0, 1, 2])).unwrap();
+
+        let expected_output = [
+            fr("115CC0F5E7D690413DF64C6B9662E9CF2A3617F2743245519E19607A4417189A"),
+            fr("0FCA49B798923AB0239DE1C9E7A4A9A2210312B6A2F616D18B5A87F9B628AE29"),
+            fr("0E7AE82E40091E63CBD4F16A6D16310B3729D4B6E138FCF54110E2867045A30C"),
+        ];
+        assert_eq!(output, expected_output);
+
+        // The circomlib hash of [1, 2] is the first element.
+        assert_eq!(hash_bn254(&frs(&[1, 2])), Some(expected_output[0]));
+        // Later calls reuse the instance built by the first one.
+        assert_eq!(
+            BN254_INSTANCES[1].get().unwrap().params(),
+            poseidon.params()
+        );
+        assert_eq!(hash_bn254(&frs(&[1, 2])), Some(expected_output[0]));
+    }
+
+    #[test]
+    fn test_poseidon_bn254_width_5() {
+        let poseidon = Poseidon::new(Params::bn254(5).unwrap());
+        let output = poseidon.permute(&frs(&[0, 1, 2, 3, 4])).unwrap();
+
+        let expected_output = [
+            fr("299C867DB6C1FDD79DCEFA40E4510B9837E60EBB1CE0663DBAA525DF65250465"),
+            fr("1148AAEF609AA338B27DAFD89BB98862D8BB2B429ACEAC47D86206154FFE053D"),
+            fr("24FEBB87FED7462E23F6665FF9A0111F4044C38EE1672C1AC6B0637D34F24907"),
+            fr("0EB08F6D809668A981C186BEAF6110060707059576406B248E5D9CF6E78B3D3E"),
+            fr("07748BC6877C9B82C8B98666EE9D0626EC7F5BE4205F79EE8528EF1C4A376FC7"),
+        ];
+        assert_eq!(output, expected_output);
+    }
+
+    #[test]
+    fn test_poseidon_params() {
+        // 3 divides p - 1 for BN254, so x^3 isn't a permutation.
+        assert_eq!(Params::<Bn254FrParams>::new(3, 3, 8, 57), None);
+        assert_eq!(Params::<Bn254FrParams>::new(3, 5, 7, 57), None);
+        assert_eq!(Params::<Bn254FrParams>::new(1, 5, 8, 57), None);
+        assert_eq!(Params::bn254(18), None);
+        assert_eq!(hash_bn254(&[]), None);
+        assert_eq!(hash_bn254(&[Bn254Fr::one(); 17]), None);
+
+        let poseidon = Poseidon::new(Params::bn254(3).unwrap());
+        assert_eq!(poseidon.permute(&frs(&[1, 2])), None);
+        assert_eq!(poseidon.hash(&frs(&[1, 2, 3])), None);
+        assert_ne!(poseidon.hash(&frs(&[1, 2])), poseidon.hash(&frs(&[2, 1])));
+    }
+
+    #[test]
+    fn test_poseidon_traced() {
+        // 8 full and 57 partial rounds, the last state being the output.
+        let poseidon = Poseidon::new(Params::bn254(3).unwrap());
+        let mut rounds = 0;
+        let mut states = vec![];
+        let output =
+            poseidon.hash_traced(
+                &frs(&[1, 2]),
+                &mut |event: Event<'_, Bn254Fr>| match event {
+                    Event::ChainingValue { block, value } => states.push((block, value.to_vec())),
+                    Event::Registers {
+                        step,
+                        names,
+                        values,
+                    } => {
+                        assert_eq!(step, rounds);
+                        assert_eq!(names, ["s0", "s1", "s2"]);
+                        assert_eq!(values.len(), 3);
+                        rounds += 1;
+                    }
+                    Event::ExtendedWords(_) => unreachable!(),
+                },
+            );
+        assert_eq!(output, hash_bn254(&frs(&[1, 2])));
+        assert_eq!(rounds, 65);
+        assert_eq!(states.len(), 2);
+        assert_eq!(states[0], (0, frs(&[0, 1, 2])));
+        assert_eq!(Some(states[1].1[0]), output);
+    }
+}
@@ -0,0 +1,273 @@
+use super::*;
+
+// IV from the spec. Same as sha1.
+// https://homes.esat.kuleuven.be/~bosselae/ripemd160/pdf/AB-9601/AB-9601.pdf
+pub const IV: [u32; 5] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0];
+
+// Message word selection for the left and right lines.
+const R_LEFT: [usize; 80] = [
+    0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, //
+    7, 4, 13, 1, 10, 6, 15, 3, 12, 0, 9, 5, 2, 14, 11, 8, //
+    3, 10, 14, 4, 9, 15, 8, 1, 2, 7, 0, 6, 13, 11, 5, 12, //
+    1, 9, 11, 10, 0, 8, 12, 4, 13, 3, 7, 15, 14, 5, 6, 2, //
+    4, 0, 5, 9, 7, 12, 2, 10, 14, 1, 3, 8, 11, 6, 15, 13,
+];
+const R_RIGHT: [usize; 80] = [
+    5, 14, 7, 0, 9, 2, 11, 4, 13, 6, 15, 8, 1, 10, 3, 12, //
+    6, 11, 3, 7, 0, 13, 5, 10, 14, 15, 8, 12, 4, 9, 1, 2, //
+    15, 5, 1, 3, 7, 14, 6, 9, 11, 8, 12, 2, 10, 0, 4, 13, //
+    8, 6, 4, 1, 3, 11, 15, 0, 5, 12, 2, 13, 9, 7, 10, 14, //
+    12, 15, 10, 4, 1, 5, 8, 7, 6, 2, 13, 14, 0, 3, 9, 11,
+];
+
+// Rotation amounts for the left and right lines.
+const S_LEFT: [u32; 80] = [
+    11, 14, 15, 12, 5, 8, 7, 9, 11, 13, 14, 15, 6, 7, 9, 8, //
+    7, 6, 8, 13, 11, 9, 7, 15, 7, 12, 15, 9, 11, 7, 13, 12, //
+    11, 13, 6, 7, 14, 9, 13, 15, 14, 8, 13, 6, 5, 12, 7, 5, //
+    11, 12, 14, 15, 14, 15, 9, 8, 9, 14, 5, 6, 8, 6, 5, 12, //
+    9, 15, 5, 11, 6, 8, 13, 12, 5, 12, 13, 14, 11, 8, 5, 6,
+];
+const S_RIGHT: [u32; 80] = [
+    8, 9, 9, 11, 13, 15, 15, 5, 7, 7, 8, 11, 14, 14, 12, 6, //
+    9, 13, 15, 7, 12, 8, 9, 11, 7, 7, 12, 7, 6, 15, 13, 11, //
+    9, 7, 15, 11, 8, 6, 6, 14, 12, 13, 5, 14, 13, 13, 7, 5, //
+    15, 5, 8, 11, 14, 14, 6, 14, 6, 9, 12, 9, 12, 5, 15, 8, //
+    8, 5, 12, 9, 12, 5, 14, 6, 8, 13, 6, 5, 15, 13, 11, 11,
+];
+
+// Per round constants: integer parts of 2^30 * sqrt(2, 3, 5, 7) on the left
+// and 2^30 * cbrt(2, 3, 5, 7) on the right.
+const K_LEFT: [u32; 5] = [0x00000000, 0x5A827999, 0x6ED9EBA1, 0x8F1BBCDC, 0xA953FD4E];
+const K_RIGHT: [u32; 5] = [0x50A28BE6, 0x5C4DD124, 0x6D703EF3, 0x7A6D76E9, 0x00000000];
+
+// Boolean functions by round. The right line uses them in reverse order.
+fn f(round: usize, x: u32, y: u32, z: u32) -> u32 {
+    match round {
+        0 => x ^ y ^ z,
+        1 => (x & y) | ((!x) & z),
+        2 => (x | (!y)) ^ z,
+        3 => (x & z) | (y & (!z)),
+        _ => x ^ (y | (!z)),
+    }
+}
+
+const REGISTER_NAMES: [&str; 10] = ["al", "bl", "cl", "dl", "el", "ar", "br", "cr", "dr", "er"];
+
+fn compress<T>(state: &mut [u32; 5], extended_words: &[u32], trace: &mut T)
+where
+    T: Trace<u32> + ?Sized,
+{
+    trace.record(Event::ExtendedWords(extended_words));
+    let [mut al, mut bl, mut cl, mut dl, mut el] = *state;
+    let [mut ar, mut br, mut cr, mut dr, mut er] = *state;
+
+    // The two lines run independently over the same block and are only
+    // combined at the end.
+    for i in 0..80 {
+        let round = i / 16;
+
+        let temp = al
+            .wrapping_add(f(round, bl, cl, dl))
+            .wrapping_add(extended_words[R_LEFT[i]])
+            .wrapping_add(K_LEFT[round])
+            .rotate_left(S_LEFT[i])
+            .wrapping_add(el);
+        al = el;
+        el = dl;
+        dl = cl.rotate_left(10);
+        cl = bl;
+        bl = temp;
+
+        let temp = ar
+            .wrapping_add(f(4 - round, br, cr, dr))
+            .wrapping_add(extended_words[R_RIGHT[i]])
+            .wrapping_add(K_RIGHT[round])
+            .rotate_left(S_RIGHT[i])
+            .wrapping_add(er);
+        ar = er;
+        er = dr;
+        dr = cr.rotate_left(10);
+        cr = br;
+        br = temp;
+        trace.record(Event::Registers {
+            step: i,
+            names: &REGISTER_NAMES,
+            values: &[al, bl, cl, dl, el, ar, br, cr, dr, er],
+        });
+    }
+
+    let temp = state[1].wrapping_add(cl).wrapping_add(dr);
+    state[1] = state[2].wrapping_add(dl).wrapping_add(er);
+    state[2] = state[3].wrapping_add(el).wrapping_add(ar);
+    state[3] = state[4].wrapping_add(al).wrapping_add(br);
+    state[4] = state[0].wrapping_add(bl).wrapping_add(cr);
+    state[0] = temp;
+}
+
+pub struct Ripemd160Compression;
+
+impl CompressionFunction for Ripemd160Compression {
+    type Word = u32;
+    type ChainingValue = [u32; 5];
+    type Digest = [u8; 20];
+
+    const BLOCK_SIZE: usize = 64;
+    const LENGTH_SIZE: usize = 8;
+    const ENDIAN: Endian = Endian::Little;
+    const IV: Self::ChainingValue = IV;
+
+    fn compress(state: &mut Self::ChainingValue, words: &[u32]) {
+        compress(state, words, &mut NoTrace);
+    }
+
+    fn compress_traced(state: &mut Self::ChainingValue, words: &[u32], trace: &mut dyn Trace<u32>) {
+        compress(state, words, trace);
+    }
+}
+
+pub type Ripemd160 = MerkleDamgard<Ripemd160Compression>;
+pub type State = merkle_damgard::State<Ripemd160Compression>;
+
+pub fn core<T>(message: T, total_length: usize, iv: (u32, u32, u32, u32, u32)) -> [u8; 20]
+where
+    T: AsBytes,
+{
+    let bytes = message.as_bytes();
+    let iv = [iv.0, iv.1, iv.2, iv.3, iv.4];
+    let state = State::for_suffix(iv, total_length, bytes.len());
+    let mut hasher = Ripemd160::from_state(state);
+    hasher.update(bytes);
+    hasher.finalize()
+}
+
+pub fn default<T>(message: T) -> [u8; 20]
+where
+    T: AsBytes,
+{
+    let mut hasher = Ripemd160::new();
+    hasher.update(message);
+    hasher.finalize()
+}
+
+// `default`, reporting every block's schedule and registers to `trace`.
+pub fn traced<T>(message: T, trace: &mut dyn Trace<u32>) -> [u8; 20]
+where
+    T: AsBytes,
+{
+    merkle_damgard::hash_traced::<Ripemd160Compression>(
+        State::new(IV, 0),
+        message.as_bytes(),
+        trace,
+    )
+}
+
+// `core`, reporting every block's schedule and registers to `trace`.
+pub fn core_traced<T>(
+    message: T,
+    total_length: usize,
+    iv: (u32, u32, u32, u32, u32),
+    trace: &mut dyn Trace<u32>,
+) -> [u8; 20]
+where
+    T: AsBytes,
+{
+    let bytes = message.as_bytes();
+    let iv = [iv.0, iv.1, iv.2, iv.3, iv.4];
+    let state = State::for_suffix(iv, total_length, bytes.len());
+    merkle_damgard::hash_traced::<Ripemd160Compression>(state, bytes, trace)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Test suite from
+    // https://homes.esat.kuleuven.be/~bosselae/ripemd160.html
+    #[test]
+    fn test_ripemd160() {
+        let empty_output = default("");
+        let a_output = default("a");
+        let abc_output = default("abc");
+        let message_digest_output = default("message digest");
+        let alphabet_output = default("abcdefghijklmnopqrstuvwxyz");
+        let long_output = default("abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq");
+        let alphanumeric_output =
+            default("ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789");
+        let numeric_output = default(
+            "1234567890123456789012345678901234567890\
+            1234567890123456789012345678901234567890",
+        );
+
+        let expected_empty_output: [u8; 20] = [
+            0x9C, 0x11, 0x85, 0xA5, 0xC5, 0xE9, 0xFC, 0x54, 0x61, 0x28, 0x08, 0x97, 0x7E, 0xE8,
+            0xF5, 0x48, 0xB2, 0x25, 0x8D, 0x31,
+        ];
+        let expected_a_output: [u8; 20] = [
+            0x0B, 0xDC, 0x9D, 0x2D, 0x25, 0x6B, 0x3E, 0xE9, 0xDA, 0xAE, 0x34, 0x7B, 0xE6, 0xF4,
+            0xDC, 0x83, 0x5A, 0x46, 0x7F, 0xFE,
+        ];
+        let expected_abc_output: [u8; 20] = [
+            0x8E, 0xB2, 0x08, 0xF7, 0xE0, 0x5D, 0x98, 0x7A, 0x9B, 0x04, 0x4A, 0x8E, 0x98, 0xC6,
+            0xB0, 0x87, 0xF1, 0x5A, 0x0B, 0xFC,
+        ];
+        let expected_message_digest_output: [u8; 20] = [
+            0x5D, 0x06, 0x89, 0xEF, 0x49, 0xD2, 0xFA, 0xE5, 0x72, 0xB8, 0x81, 0xB1, 0x23, 0xA8,
+            0x5F, 0xFA, 0x21, 0x59, 0x5F, 0x36,
+        ];
+        let expected_alphabet_output: [u8; 20] = [
+            0xF7, 0x1C, 0x27, 0x10, 0x9C, 0x69, 0x2C, 0x1B, 0x56, 0xBB, 0xDC, 0xEB, 0x5B, 0x9D,
+            0x28, 0x65, 0xB3, 0x70, 0x8D, 0xBC,
+        ];
+        let expected_long_output: [u8; 20] = [
+            0x12, 0xA0, 0x53, 0x38, 0x4A, 0x9C, 0x0C, 0x88, 0xE4, 0x05, 0xA0, 0x6C, 0x27, 0xDC,
+            0xF4, 0x9A, 0xDA, 0x62, 0xEB, 0x2B,
+        ];
+        let expected_alphanumeric_output: [u8; 20] = [
+            0xB0, 0xE2, 0x0B, 0x6E, 0x31, 0x16, 0x64, 0x02, 0x86, 0xED, 0x3A, 0x87, 0xA5, 0x71,
+            0x30, 0x79, 0xB2, 0x1F, 0x51, 0x89,
+        ];
+        let expected_numeric_output: [u8; 20] = [
+            0x9B, 0x75, 0x2E, 0x45, 0x57, 0x3D, 0x4B, 0x39, 0xF4, 0xDB, 0xD3, 0x32, 0x3C, 0xAB,
+            0x82, 0xBF, 0x63, 0x32, 0x6B, 0xFB,
+        ];
+
+        assert_eq!(empty_output, expected_empty_output);
+        assert_eq!(a_output, expected_a_output);
+        assert_eq!(abc_output, expected_abc_output);
+        assert_eq!(message_digest_output, expected_message_digest_output);
+        assert_eq!(alphabet_output, expected_alphabet_output);
+        assert_eq!(long_output, expected_long_output);
+        assert_eq!(alphanumeric_output, expected_alphanumeric_output);
+        assert_eq!(numeric_output, expected_numeric_output);
+    }
+
+    #[test]
+    fn test_ripemd160_million_a() {
+        let piece = [b'a'; 1000];
+        let mut hasher = Ripemd160::new();
+        for _ in 0..1000 {
+            hasher.update(piece.as_slice());
+        }
+
+        let expected_output: [u8; 20] = [
+            0x52, 0x78, 0x32, 0x43, 0xC1, 0x69, 0x7B, 0xDB, 0xE1, 0x6D, 0x37, 0xF9, 0x7F, 0x68,
+            0xF0, 0x83, 0x25, 0xDC, 0x15, 0x28,
+        ];
+
+        assert_eq!(hasher.finalize(), expected_output);
+    }
+
+    #[test]
+    fn test_ripemd160_streaming() {
+        let message = "1234567890".repeat(30);
+
+        for piece_size in [1, 7, 63, 64, 65, 200] {
+            let mut hasher = Ripemd160::new();
+            for piece in message.as_bytes().chunks(piece_size) {
+                hasher.update(piece);
+            }
+            assert_eq!(hasher.finalize(), default(message.as_str()));
+        }
+    }
+}
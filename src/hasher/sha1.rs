@@ -0,0 +1,723 @@
+use super::*;
+use std::marker::PhantomData;
+
+// IV from the spec.
+// https://www.rfc-editor.org/rfc/rfc3174#section-6.1
+pub const IV: [u32; 5] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0];
+
+fn expand(words: &[u32]) -> [u32; 80] {
+    let mut extended_words: [u32; 80] = [0; 80];
+    extended_words[..16].copy_from_slice(words);
+
+    for i in 16..80 {
+        extended_words[i] = extended_words[i - 3]
+            ^ extended_words[i - 8]
+            ^ extended_words[i - 14]
+            ^ extended_words[i - 16];
+        extended_words[i] = extended_words[i].rotate_left(1);
+    }
+    extended_words
+}
+
+// Boolean functions of the four rounds.
+pub type BooleanFunction = fn(u32, u32, u32) -> u32;
+
+fn choose(b: u32, c: u32, d: u32) -> u32 {
+    (b & c) ^ ((!b) & d)
+}
+
+fn parity(b: u32, c: u32, d: u32) -> u32 {
+    b ^ c ^ d
+}
+
+fn majority(b: u32, c: u32, d: u32) -> u32 {
+    (b & c) ^ (b & d) ^ (c & d)
+}
+
+const STEPS: usize = 80;
+const STEPS_PER_ROUND: usize = 20;
+
+// Configurable compression function for reduced-round and tweaked SHA-1,
+// e.g. the first 20 steps only, or zero constants for rotational
+// properties. Rounds stay 20 steps long when fewer are run. `Params::new()`
+// is SHA-1 itself; any builder method yields a `NonStandard` type.
+#[derive(Clone, Copy, Debug)]
+pub struct Params<S = Standard> {
+    steps: usize,
+    constants: [u32; 4],
+    functions: [BooleanFunction; 4],
+    _conformance: PhantomData<S>,
+}
+
+const STANDARD: Params = Params {
+    steps: STEPS,
+    constants: [0x5A827999, 0x6ED9EBA1, 0x8F1BBCDC, 0xCA62C1D6],
+    functions: [choose, parity, majority, parity],
+    _conformance: PhantomData,
+};
+
+impl Params {
+    pub fn new() -> Self {
+        STANDARD
+    }
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Params::new()
+    }
+}
+
+impl<S> Params<S> {
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    pub fn constants(&self) -> [u32; 4] {
+        self.constants
+    }
+
+    pub fn functions(&self) -> [BooleanFunction; 4] {
+        self.functions
+    }
+
+    fn non_standard(self) -> Params<NonStandard> {
+        Params {
+            steps: self.steps,
+            constants: self.constants,
+            functions: self.functions,
+            _conformance: PhantomData,
+        }
+    }
+
+    // Runs the first `steps` steps only. None beyond 80.
+    pub fn with_steps(self, steps: usize) -> Option<Params<NonStandard>> {
+        if steps > STEPS {
+            return None;
+        }
+        Some(Params {
+            steps,
+            ..self.non_standard()
+        })
+    }
+
+    pub fn with_constants(self, constants: [u32; 4]) -> Params<NonStandard> {
+        Params {
+            constants,
+            ..self.non_standard()
+        }
+    }
+
+    pub fn with_functions(self, functions: [BooleanFunction; 4]) -> Params<NonStandard> {
+        Params {
+            functions,
+            ..self.non_standard()
+        }
+    }
+
+    // Boolean function and constant of step i.
+    fn round_function(&self, i: usize, b: u32, c: u32, d: u32) -> (u32, u32) {
+        let round = i / STEPS_PER_ROUND;
+        (self.functions[round](b, c, d), self.constants[round])
+    }
+}
+
+const REGISTER_NAMES: [&str; 5] = ["a", "b", "c", "d", "e"];
+
+fn compress<S, T>(state: &mut [u32; 5], words: &[u32], params: &Params<S>, trace: &mut T)
+where
+    T: Trace<u32> + ?Sized,
+{
+    let extended_words = expand(words);
+    trace.record(Event::ExtendedWords(&extended_words));
+
+    let [mut a, mut b, mut c, mut d, mut e] = *state;
+
+    for (i, word) in extended_words.iter().enumerate().take(params.steps) {
+        let (f, k) = params.round_function(i, b, c, d);
+
+        let temp = a
+            .rotate_left(5)
+            .wrapping_add(f)
+            .wrapping_add(e)
+            .wrapping_add(k)
+            .wrapping_add(*word);
+        e = d;
+        d = c;
+        c = b.rotate_left(30);
+        b = a;
+        a = temp;
+        trace.record(Event::Registers {
+            step: i,
+            names: &REGISTER_NAMES,
+            values: &[a, b, c, d, e],
+        });
+    }
+
+    state[0] = state[0].wrapping_add(a);
+    state[1] = state[1].wrapping_add(b);
+    state[2] = state[2].wrapping_add(c);
+    state[3] = state[3].wrapping_add(d);
+    state[4] = state[4].wrapping_add(e);
+}
+
+pub struct Sha1Compression;
+
+impl CompressionFunction for Sha1Compression {
+    type Word = u32;
+    type ChainingValue = [u32; 5];
+    type Digest = [u8; 20];
+
+    const BLOCK_SIZE: usize = 64;
+    const LENGTH_SIZE: usize = 8;
+    const ENDIAN: Endian = Endian::Big;
+    const IV: Self::ChainingValue = IV;
+
+    fn compress(state: &mut Self::ChainingValue, words: &[u32]) {
+        compress(state, words, &STANDARD, &mut NoTrace);
+    }
+
+    fn compress_traced(state: &mut Self::ChainingValue, words: &[u32], trace: &mut dyn Trace<u32>) {
+        compress(state, words, &STANDARD, trace);
+    }
+}
+
+pub type Sha1 = MerkleDamgard<Sha1Compression>;
+pub type State = merkle_damgard::State<Sha1Compression>;
+
+pub fn core<T>(message: T, total_length: usize, iv: (u32, u32, u32, u32, u32)) -> [u8; 20]
+where
+    T: AsBytes,
+{
+    let bytes = message.as_bytes();
+    let iv = [iv.0, iv.1, iv.2, iv.3, iv.4];
+    let state = State::for_suffix(iv, total_length, bytes.len());
+    let mut hasher = Sha1::from_state(state);
+    hasher.update(bytes);
+    hasher.finalize()
+}
+
+pub fn default<T>(message: T) -> [u8; 20]
+where
+    T: AsBytes,
+{
+    let mut hasher = Sha1::new();
+    hasher.update(message);
+    hasher.finalize()
+}
+
+// `default`, reporting every block's schedule and registers to `trace`.
+pub fn traced<T>(message: T, trace: &mut dyn Trace<u32>) -> [u8; 20]
+where
+    T: AsBytes,
+{
+    merkle_damgard::hash_traced::<Sha1Compression>(State::new(IV, 0), message.as_bytes(), trace)
+}
+
+// `core`, reporting every block's schedule and registers to `trace`.
+pub fn core_traced<T>(
+    message: T,
+    total_length: usize,
+    iv: (u32, u32, u32, u32, u32),
+    trace: &mut dyn Trace<u32>,
+) -> [u8; 20]
+where
+    T: AsBytes,
+{
+    let bytes = message.as_bytes();
+    let iv = [iv.0, iv.1, iv.2, iv.3, iv.4];
+    let state = State::for_suffix(iv, total_length, bytes.len());
+    merkle_damgard::hash_traced::<Sha1Compression>(state, bytes, trace)
+}
+
+impl<S> Params<S> {
+    // Compresses one block of 16 words into `state`.
+    pub fn compress(&self, state: &mut [u32; 5], words: &[u32]) {
+        compress(state, words, self, &mut NoTrace);
+    }
+
+    // SHA-1 with this compression function, padding and IV unchanged.
+    pub fn hash<T>(&self, message: T) -> [u8; 20]
+    where
+        T: AsBytes,
+    {
+        self.traced(message, &mut NoTrace)
+    }
+
+    pub fn traced<T>(&self, message: T, trace: &mut dyn Trace<u32>) -> [u8; 20]
+    where
+        T: AsBytes,
+    {
+        merkle_damgard::hash_with::<Sha1Compression, _>(
+            State::new(IV, 0),
+            message.as_bytes(),
+            trace,
+            |state, words, trace| compress(state, words, self, trace),
+        )
+    }
+}
+
+// Collision detection (counter-cryptanalysis) after Stevens and Shumow.
+// Practical SHA-1 collisions are pairs of near-collision blocks built on one
+// of a few dozen disturbance vectors. The two compressions of such a pair
+// share their whole state at some step, so given one block the other can be
+// recomputed from there: backwards to the chaining value it starts from and
+// forwards to the one it ends in. When the latter is ours, the block is the
+// second half of a collision. The reference implementation first filters
+// vectors on unavoidable message bit conditions; here every block is
+// recompressed for every vector, about 30 times the work of plain SHA-1.
+// https://marc-stevens.nl/research/papers/C13-S.pdf
+// https://github.com/cr-marcstevens/sha1collisiondetection
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DisturbanceKind {
+    I,
+    II,
+}
+
+// Disturbance vector I(K, b) or II(K, b), an expanded message whose words K
+// to K + 15 are zero except for word K + 15, 2^b, and for type II words
+// K + 1 and K + 3, 2^(b + 31).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DisturbanceVector {
+    pub kind: DisturbanceKind,
+    pub k: usize,
+    pub b: u32,
+}
+
+impl DisturbanceVector {
+    const fn new(kind: DisturbanceKind, k: usize, b: u32) -> Self {
+        DisturbanceVector { kind, k, b }
+    }
+
+    // Step from which blocks are recompressed, the one sha1collisiondetection
+    // picked for the vector.
+    pub const fn test_step(&self) -> usize {
+        if self.k < 50 {
+            58
+        } else {
+            65
+        }
+    }
+
+    // XOR difference between the expanded words of the two blocks. Each
+    // disturbance at step t is a local collision, corrected at steps t + 1
+    // to t + 5.
+    pub const fn message_difference(&self) -> [u32; 80] {
+        // Vector words -5 to 79, offset by 5.
+        let mut vector = [0u32; 85];
+        let window = self.k + 5;
+        vector[window + 15] = 1u32.rotate_left(self.b);
+        if matches!(self.kind, DisturbanceKind::II) {
+            vector[window + 1] = 1u32.rotate_left(self.b + 31);
+            vector[window + 3] = 1u32.rotate_left(self.b + 31);
+        }
+
+        // The message expansion runs backwards just as well.
+        let mut t = window + 16;
+        while t < 85 {
+            vector[t] =
+                (vector[t - 3] ^ vector[t - 8] ^ vector[t - 14] ^ vector[t - 16]).rotate_left(1);
+            t += 1;
+        }
+        let mut t = window + 15;
+        while t >= 16 {
+            vector[t - 16] =
+                vector[t].rotate_right(1) ^ vector[t - 3] ^ vector[t - 8] ^ vector[t - 14];
+            t -= 1;
+        }
+
+        let mut difference = [0u32; 80];
+        let mut t = 0;
+        while t < 80 {
+            let i = t + 5;
+            difference[t] = vector[i]
+                ^ vector[i - 1].rotate_left(5)
+                ^ vector[i - 2]
+                ^ vector[i - 3].rotate_left(30)
+                ^ vector[i - 4].rotate_left(30)
+                ^ vector[i - 5].rotate_left(30);
+            t += 1;
+        }
+        difference
+    }
+}
+
+// The vectors checked by sha1collisiondetection. SHAttered used II(52, 0).
+pub const DISTURBANCE_VECTORS: [DisturbanceVector; 32] = {
+    use DisturbanceKind::{I, II};
+    [
+        DisturbanceVector::new(I, 43, 0),
+        DisturbanceVector::new(I, 44, 0),
+        DisturbanceVector::new(I, 45, 0),
+        DisturbanceVector::new(I, 46, 0),
+        DisturbanceVector::new(I, 46, 2),
+        DisturbanceVector::new(I, 47, 0),
+        DisturbanceVector::new(I, 47, 2),
+        DisturbanceVector::new(I, 48, 0),
+        DisturbanceVector::new(I, 48, 2),
+        DisturbanceVector::new(I, 49, 0),
+        DisturbanceVector::new(I, 49, 2),
+        DisturbanceVector::new(I, 50, 0),
+        DisturbanceVector::new(I, 50, 2),
+        DisturbanceVector::new(I, 51, 0),
+        DisturbanceVector::new(I, 51, 2),
+        DisturbanceVector::new(I, 52, 0),
+        DisturbanceVector::new(II, 45, 0),
+        DisturbanceVector::new(II, 46, 0),
+        DisturbanceVector::new(II, 46, 2),
+        DisturbanceVector::new(II, 47, 0),
+        DisturbanceVector::new(II, 48, 0),
+        DisturbanceVector::new(II, 49, 0),
+        DisturbanceVector::new(II, 49, 2),
+        DisturbanceVector::new(II, 50, 0),
+        DisturbanceVector::new(II, 50, 2),
+        DisturbanceVector::new(II, 51, 0),
+        DisturbanceVector::new(II, 51, 2),
+        DisturbanceVector::new(II, 52, 0),
+        DisturbanceVector::new(II, 53, 0),
+        DisturbanceVector::new(II, 54, 0),
+        DisturbanceVector::new(II, 55, 0),
+        DisturbanceVector::new(II, 56, 0),
+    ]
+};
+
+const MESSAGE_DIFFERENCES: [[u32; 80]; 32] = {
+    let mut differences = [[0; 80]; 32];
+    let mut i = 0;
+    while i < 32 {
+        differences[i] = DISTURBANCE_VECTORS[i].message_difference();
+        i += 1;
+    }
+    differences
+};
+
+// Every value of `a`, A_-4 to A_80 offset by 4. At step t the registers are
+// a = A_t, b = A_t-1, and c, d, e are A_t-2, A_t-3, A_t-4 rotated by 30.
+type States = [u32; 85];
+
+fn chaining_value_to_states(chaining_value: &[u32; 5]) -> States {
+    let [a, b, c, d, e] = *chaining_value;
+    let mut states = [0; 85];
+    states[..5].copy_from_slice(&[
+        e.rotate_right(30),
+        d.rotate_right(30),
+        c.rotate_right(30),
+        b,
+        a,
+    ]);
+    states
+}
+
+// The registers before step `step`.
+fn registers(states: &States, step: usize) -> [u32; 5] {
+    [
+        states[step + 4],
+        states[step + 3],
+        states[step + 2].rotate_left(30),
+        states[step + 1].rotate_left(30),
+        states[step].rotate_left(30),
+    ]
+}
+
+fn step_forward(states: &mut States, extended_words: &[u32; 80], step: usize) {
+    let [a, b, c, d, e] = registers(states, step);
+    let (f, k) = STANDARD.round_function(step, b, c, d);
+    states[step + 5] = a
+        .rotate_left(5)
+        .wrapping_add(f)
+        .wrapping_add(e)
+        .wrapping_add(k)
+        .wrapping_add(extended_words[step]);
+}
+
+// Recovers A_step-4 from the registers after the step.
+fn step_backward(states: &mut States, extended_words: &[u32; 80], step: usize) {
+    let [a, b, c, d, _] = registers(states, step);
+    let (f, k) = STANDARD.round_function(step, b, c, d);
+    states[step] = states[step + 5]
+        .wrapping_sub(a.rotate_left(5))
+        .wrapping_sub(f)
+        .wrapping_sub(k)
+        .wrapping_sub(extended_words[step])
+        .rotate_right(30);
+}
+
+// Compresses a block, recording every state, and checks it against each
+// disturbance vector. Returns the first vector it matches.
+fn compress_checked(state: &mut [u32; 5], words: &[u32]) -> Option<DisturbanceVector> {
+    let extended_words = expand(words);
+    let mut states = chaining_value_to_states(state);
+    for step in 0..80 {
+        step_forward(&mut states, &extended_words, step);
+    }
+
+    let output = registers(&states, 80);
+    for (word, input) in state.iter_mut().zip(output) {
+        *word = word.wrapping_add(input);
+    }
+
+    for (vector, difference) in DISTURBANCE_VECTORS.iter().zip(MESSAGE_DIFFERENCES.iter()) {
+        let mut other_words = extended_words;
+        for (word, difference) in other_words.iter_mut().zip(difference) {
+            *word ^= difference;
+        }
+
+        // Both compressions agree on A_test-4 to A_test.
+        let test_step = vector.test_step();
+        let mut other = states;
+        for step in (0..test_step).rev() {
+            step_backward(&mut other, &other_words, step);
+        }
+        for step in test_step..80 {
+            step_forward(&mut other, &other_words, step);
+        }
+
+        let input = registers(&other, 0);
+        let output = registers(&other, 80);
+        if input
+            .iter()
+            .zip(output)
+            .map(|(input, output)| input.wrapping_add(output))
+            .eq(state.iter().copied())
+        {
+            return Some(*vector);
+        }
+    }
+    None
+}
+
+// SHA-1 that checks every block it compresses for traces of a collision
+// attack. Digests are those of `Sha1` unless a block is flagged.
+#[derive(Clone, Debug)]
+pub struct CheckedSha1 {
+    chaining_value: [u32; 5],
+    buffer: Vec<u8>,
+    length: usize,
+    detected: Option<DisturbanceVector>,
+}
+
+impl CheckedSha1 {
+    pub fn new() -> Self {
+        CheckedSha1 {
+            chaining_value: IV,
+            buffer: Vec::with_capacity(64),
+            length: 0,
+            detected: None,
+        }
+    }
+
+    pub fn update<T>(&mut self, message: T)
+    where
+        T: AsBytes,
+    {
+        let bytes = message.as_bytes();
+        self.length += bytes.len();
+        self.absorb(bytes);
+    }
+
+    fn absorb(&mut self, bytes: &[u8]) {
+        self.buffer.extend(bytes);
+        let blocks = self.buffer.len() / 64;
+        for block in 0..blocks {
+            let mut words = [0u32; 16];
+            for (word, chunk) in words.iter_mut().zip(self.buffer[64 * block..].chunks(4)) {
+                *word = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+            }
+            let detected = compress_checked(&mut self.chaining_value, &words);
+            self.detected = self.detected.or(detected);
+        }
+        self.buffer.drain(..64 * blocks);
+    }
+
+    // The disturbance vector of the first flagged block so far.
+    pub fn detected(&self) -> Option<DisturbanceVector> {
+        self.detected
+    }
+
+    // The digest, or None if any block looked like half of a collision.
+    pub fn finalize(mut self) -> Option<[u8; 20]> {
+        let padding = md_padding_64(self.length, Endian::Big);
+        self.absorb(&padding);
+        match self.detected {
+            Some(_) => None,
+            None => Some(merkle_damgard::chaining_value_to_digest::<Sha1Compression>(
+                &self.chaining_value,
+            )),
+        }
+    }
+}
+
+impl Default for CheckedSha1 {
+    fn default() -> Self {
+        CheckedSha1::new()
+    }
+}
+
+pub fn checked<T>(message: T) -> Option<[u8; 20]>
+where
+    T: AsBytes,
+{
+    let mut hasher = CheckedSha1::new();
+    hasher.update(message);
+    hasher.finalize()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sha1_streaming() {
+        let message = "abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmn".repeat(5);
+
+        // Feed the message in uneven pieces that straddle block boundaries.
+        for piece_size in [1, 7, 63, 64, 65, 200] {
+            let mut hasher = Sha1::new();
+            for piece in message.as_bytes().chunks(piece_size) {
+                hasher.update(piece);
+            }
+            assert_eq!(hasher.finalize(), default(message.as_str()));
+        }
+    }
+
+    #[test]
+    fn test_sha1_reset() {
+        let mut hasher = Sha1::new();
+        hasher.update("garbage");
+        hasher.reset();
+        hasher.update("The quick brown fox jumps over the lazy dog");
+        assert_eq!(
+            hasher.finalize(),
+            default("The quick brown fox jumps over the lazy dog")
+        );
+    }
+
+    #[test]
+    fn test_params() {
+        let message = "abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmn".repeat(3);
+        assert_eq!(
+            Params::new().hash(message.as_str()),
+            default(message.as_str())
+        );
+        // Back to 80 steps, same output but no longer standard in its type.
+        let params: Params<NonStandard> = Params::new()
+            .with_steps(20)
+            .unwrap()
+            .with_steps(80)
+            .unwrap();
+        assert_eq!(params.hash(message.as_str()), default(message.as_str()));
+        assert!(Params::new().with_steps(81).is_none());
+
+        let mut steps = 0;
+        let reduced = Params::new().with_steps(20).unwrap();
+        let digest = reduced.traced(message.as_str(), &mut |event: Event<'_, u32>| {
+            if let Event::Registers { .. } = event {
+                steps += 1;
+            }
+        });
+        assert_ne!(digest, default(message.as_str()));
+        assert_eq!(steps, 3 * 20);
+
+        // With no boolean functions or constants, a single step from the zero
+        // state just copies the first word into a.
+        let zero: BooleanFunction = |_, _, _| 0;
+        let linear = Params::new()
+            .with_steps(1)
+            .unwrap()
+            .with_constants([0; 4])
+            .with_functions([zero; 4]);
+        let mut words = [0u32; 16];
+        words[0] = 0x80000000;
+        let mut state = [0; 5];
+        linear.compress(&mut state, &words);
+        assert_eq!(state, [0x80000000, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_states() {
+        let words: Vec<u32> = (0..16).map(|i| 0x01234567u32.wrapping_mul(i + 1)).collect();
+        let extended_words = expand(&words);
+
+        let mut states = chaining_value_to_states(&IV);
+        assert_eq!(registers(&states, 0), IV);
+        for step in 0..80 {
+            step_forward(&mut states, &extended_words, step);
+        }
+
+        let mut expected = IV;
+        compress(&mut expected, &words, &STANDARD, &mut NoTrace);
+        let output = registers(&states, 80);
+        for i in 0..5 {
+            assert_eq!(IV[i].wrapping_add(output[i]), expected[i]);
+        }
+
+        // Stepping back from the end recovers the chaining value.
+        let mut backwards = [0; 85];
+        backwards[80..].copy_from_slice(&states[80..]);
+        for step in (0..80).rev() {
+            step_backward(&mut backwards, &extended_words, step);
+        }
+        assert_eq!(backwards, states);
+    }
+
+    #[test]
+    fn test_message_difference() {
+        // Every difference is itself an expanded message.
+        for vector in DISTURBANCE_VECTORS {
+            let difference = vector.message_difference();
+            assert_eq!(expand(&difference[..16]), difference);
+        }
+
+        // Spot check against the sha1collisiondetection tables.
+        let difference = DISTURBANCE_VECTORS[0].message_difference();
+        assert_eq!(
+            difference[..4],
+            [0x08000000, 0x9800000C, 0xD8000010, 0x08000010]
+        );
+    }
+
+    #[test]
+    fn test_checked() {
+        let message = "abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmn".repeat(5);
+        assert_eq!(checked(message.as_str()), Some(default(message.as_str())));
+        assert_eq!(checked(""), Some(default("")));
+
+        let mut hasher = CheckedSha1::new();
+        for piece in message.as_bytes().chunks(7) {
+            hasher.update(piece);
+        }
+        assert!(hasher.detected().is_none());
+        assert_eq!(hasher.finalize(), Some(default(message.as_str())));
+    }
+
+    #[test]
+    fn test_sha1_from_state() {
+        let message = [0x61; 200];
+
+        // Compress the first two blocks, then resume from the state.
+        let mut hasher = Sha1::new();
+        hasher.update(&message[..128]);
+
+        let mut hasher = Sha1::from_state(hasher.state());
+        hasher.update(&message[128..]);
+        assert_eq!(hasher.finalize(), default(message.as_slice()));
+    }
+
+    #[test]
+    fn test_sha1_state_from_digest() {
+        let digest = default("abc");
+
+        // "abc" plus its padding fills exactly one block.
+        let state = State::from_digest(&digest, 64).unwrap();
+        assert_eq!(state.to_digest(), digest);
+
+        let mut forged = Sha1::from_state(state);
+        forged.update("def");
+
+        let mut glued = b"abc".to_vec();
+        glued.extend(md_padding_64(3, Endian::Big));
+        glued.extend(b"def");
+        assert_eq!(forged.finalize(), default(glued.as_slice()));
+    }
+}
@@ -0,0 +1,263 @@
+use super::*;
+
+// IV from the spec. First 32 bits of the fractional parts of the square
+// roots of the first 8 primes.
+// https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.180-4.pdf#section.5.3.3
+pub const IV: [u32; 8] = [
+    0x6A09E667, 0xBB67AE85, 0x3C6EF372, 0xA54FF53A, 0x510E527F, 0x9B05688C, 0x1F83D9AB, 0x5BE0CD19,
+];
+
+// SHA-224 only differs by its IV and by truncating the output.
+// https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.180-4.pdf#section.5.3.2
+pub const IV_224: [u32; 8] = [
+    0xC1059ED8, 0x367CD507, 0x3070DD17, 0xF70E5939, 0xFFC00B31, 0x68581511, 0x64F98FA7, 0xBEFA4FA4,
+];
+
+// First 32 bits of the fractional parts of the cube roots of the first 64
+// primes.
+const K: [u32; 64] = [
+    0x428A2F98, 0x71374491, 0xB5C0FBCF, 0xE9B5DBA5, 0x3956C25B, 0x59F111F1, 0x923F82A4, 0xAB1C5ED5,
+    0xD807AA98, 0x12835B01, 0x243185BE, 0x550C7DC3, 0x72BE5D74, 0x80DEB1FE, 0x9BDC06A7, 0xC19BF174,
+    0xE49B69C1, 0xEFBE4786, 0x0FC19DC6, 0x240CA1CC, 0x2DE92C6F, 0x4A7484AA, 0x5CB0A9DC, 0x76F988DA,
+    0x983E5152, 0xA831C66D, 0xB00327C8, 0xBF597FC7, 0xC6E00BF3, 0xD5A79147, 0x06CA6351, 0x14292967,
+    0x27B70A85, 0x2E1B2138, 0x4D2C6DFC, 0x53380D13, 0x650A7354, 0x766A0ABB, 0x81C2C92E, 0x92722C85,
+    0xA2BFE8A1, 0xA81A664B, 0xC24B8B70, 0xC76C51A3, 0xD192E819, 0xD6990624, 0xF40E3585, 0x106AA070,
+    0x19A4C116, 0x1E376C08, 0x2748774C, 0x34B0BCB5, 0x391C0CB3, 0x4ED8AA4A, 0x5B9CCA4F, 0x682E6FF3,
+    0x748F82EE, 0x78A5636F, 0x84C87814, 0x8CC70208, 0x90BEFFFA, 0xA4506CEB, 0xBEF9A3F7, 0xC67178F2,
+];
+
+const REGISTER_NAMES: [&str; 8] = ["a", "b", "c", "d", "e", "f", "g", "h"];
+
+fn compress<T>(state: &mut [u32; 8], words: &[u32], trace: &mut T)
+where
+    T: Trace<u32> + ?Sized,
+{
+    let mut extended_words: [u32; 64] = [0; 64];
+    extended_words[..16].copy_from_slice(words);
+
+    for i in 16..64 {
+        let w15 = extended_words[i - 15];
+        let w2 = extended_words[i - 2];
+        let s0 = w15.rotate_right(7) ^ w15.rotate_right(18) ^ (w15 >> 3);
+        let s1 = w2.rotate_right(17) ^ w2.rotate_right(19) ^ (w2 >> 10);
+        extended_words[i] = extended_words[i - 16]
+            .wrapping_add(s0)
+            .wrapping_add(extended_words[i - 7])
+            .wrapping_add(s1);
+    }
+
+    trace.record(Event::ExtendedWords(&extended_words));
+
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *state;
+
+    for (i, (word, k)) in extended_words.iter().zip(K.iter()).enumerate() {
+        let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+        let ch = (e & f) ^ ((!e) & g);
+        let temp1 = h
+            .wrapping_add(s1)
+            .wrapping_add(ch)
+            .wrapping_add(*k)
+            .wrapping_add(*word);
+        let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+        let maj = (a & b) ^ (a & c) ^ (b & c);
+        let temp2 = s0.wrapping_add(maj);
+
+        h = g;
+        g = f;
+        f = e;
+        e = d.wrapping_add(temp1);
+        d = c;
+        c = b;
+        b = a;
+        a = temp1.wrapping_add(temp2);
+        trace.record(Event::Registers {
+            step: i,
+            names: &REGISTER_NAMES,
+            values: &[a, b, c, d, e, f, g, h],
+        });
+    }
+
+    for (word, value) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+        *word = word.wrapping_add(value);
+    }
+}
+
+pub struct Sha256Compression;
+
+impl CompressionFunction for Sha256Compression {
+    type Word = u32;
+    type ChainingValue = [u32; 8];
+    type Digest = [u8; 32];
+
+    const BLOCK_SIZE: usize = 64;
+    const LENGTH_SIZE: usize = 8;
+    const ENDIAN: Endian = Endian::Big;
+    const IV: Self::ChainingValue = IV;
+
+    fn compress(state: &mut Self::ChainingValue, words: &[u32]) {
+        compress(state, words, &mut NoTrace);
+    }
+
+    fn compress_traced(state: &mut Self::ChainingValue, words: &[u32], trace: &mut dyn Trace<u32>) {
+        compress(state, words, trace);
+    }
+}
+
+// Same compression function, different IV and a truncated digest.
+pub struct Sha224Compression;
+
+impl CompressionFunction for Sha224Compression {
+    type Word = u32;
+    type ChainingValue = [u32; 8];
+    type Digest = [u8; 28];
+
+    const BLOCK_SIZE: usize = 64;
+    const LENGTH_SIZE: usize = 8;
+    const ENDIAN: Endian = Endian::Big;
+    const IV: Self::ChainingValue = IV_224;
+
+    fn compress(state: &mut Self::ChainingValue, words: &[u32]) {
+        compress(state, words, &mut NoTrace);
+    }
+
+    fn compress_traced(state: &mut Self::ChainingValue, words: &[u32], trace: &mut dyn Trace<u32>) {
+        compress(state, words, trace);
+    }
+}
+
+pub type Sha256 = MerkleDamgard<Sha256Compression>;
+pub type Sha224 = MerkleDamgard<Sha224Compression>;
+pub type State = merkle_damgard::State<Sha256Compression>;
+pub type State224 = merkle_damgard::State<Sha224Compression>;
+
+pub fn core<T>(message: T, total_length: usize, iv: [u32; 8]) -> [u8; 32]
+where
+    T: AsBytes,
+{
+    let bytes = message.as_bytes();
+    let state = State::for_suffix(iv, total_length, bytes.len());
+    let mut hasher = Sha256::from_state(state);
+    hasher.update(bytes);
+    hasher.finalize()
+}
+
+pub fn default<T>(message: T) -> [u8; 32]
+where
+    T: AsBytes,
+{
+    let mut hasher = Sha256::new();
+    hasher.update(message);
+    hasher.finalize()
+}
+
+// `default`, reporting every block's schedule and registers to `trace`.
+pub fn traced<T>(message: T, trace: &mut dyn Trace<u32>) -> [u8; 32]
+where
+    T: AsBytes,
+{
+    merkle_damgard::hash_traced::<Sha256Compression>(State::new(IV, 0), message.as_bytes(), trace)
+}
+
+// `core`, reporting every block's schedule and registers to `trace`.
+pub fn core_traced<T>(
+    message: T,
+    total_length: usize,
+    iv: [u32; 8],
+    trace: &mut dyn Trace<u32>,
+) -> [u8; 32]
+where
+    T: AsBytes,
+{
+    let bytes = message.as_bytes();
+    let state = State::for_suffix(iv, total_length, bytes.len());
+    merkle_damgard::hash_traced::<Sha256Compression>(state, bytes, trace)
+}
+
+pub fn default_224<T>(message: T) -> [u8; 28]
+where
+    T: AsBytes,
+{
+    let mut hasher = Sha224::new();
+    hasher.update(message);
+    hasher.finalize()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Example vectors from
+    // https://csrc.nist.gov/projects/cryptographic-standards-and-guidelines/example-values
+    #[test]
+    fn test_sha256() {
+        let empty_output = default("");
+        let abc_output = default("abc");
+        let two_block_output = default("abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq");
+
+        let expected_empty_output: [u8; 32] = [
+            0xE3, 0xB0, 0xC4, 0x42, 0x98, 0xFC, 0x1C, 0x14, 0x9A, 0xFB, 0xF4, 0xC8, 0x99, 0x6F,
+            0xB9, 0x24, 0x27, 0xAE, 0x41, 0xE4, 0x64, 0x9B, 0x93, 0x4C, 0xA4, 0x95, 0x99, 0x1B,
+            0x78, 0x52, 0xB8, 0x55,
+        ];
+        let expected_abc_output: [u8; 32] = [
+            0xBA, 0x78, 0x16, 0xBF, 0x8F, 0x01, 0xCF, 0xEA, 0x41, 0x41, 0x40, 0xDE, 0x5D, 0xAE,
+            0x22, 0x23, 0xB0, 0x03, 0x61, 0xA3, 0x96, 0x17, 0x7A, 0x9C, 0xB4, 0x10, 0xFF, 0x61,
+            0xF2, 0x00, 0x15, 0xAD,
+        ];
+        let expected_two_block_output: [u8; 32] = [
+            0x24, 0x8D, 0x6A, 0x61, 0xD2, 0x06, 0x38, 0xB8, 0xE5, 0xC0, 0x26, 0x93, 0x0C, 0x3E,
+            0x60, 0x39, 0xA3, 0x3C, 0xE4, 0x59, 0x64, 0xFF, 0x21, 0x67, 0xF6, 0xEC, 0xED, 0xD4,
+            0x19, 0xDB, 0x06, 0xC1,
+        ];
+
+        assert_eq!(empty_output, expected_empty_output);
+        assert_eq!(abc_output, expected_abc_output);
+        assert_eq!(two_block_output, expected_two_block_output);
+    }
+
+    #[test]
+    fn test_sha224() {
+        let empty_output = default_224("");
+        let abc_output = default_224("abc");
+        let two_block_output =
+            default_224("abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq");
+
+        let expected_empty_output: [u8; 28] = [
+            0xD1, 0x4A, 0x02, 0x8C, 0x2A, 0x3A, 0x2B, 0xC9, 0x47, 0x61, 0x02, 0xBB, 0x28, 0x82,
+            0x34, 0xC4, 0x15, 0xA2, 0xB0, 0x1F, 0x82, 0x8E, 0xA6, 0x2A, 0xC5, 0xB3, 0xE4, 0x2F,
+        ];
+        let expected_abc_output: [u8; 28] = [
+            0x23, 0x09, 0x7D, 0x22, 0x34, 0x05, 0xD8, 0x22, 0x86, 0x42, 0xA4, 0x77, 0xBD, 0xA2,
+            0x55, 0xB3, 0x2A, 0xAD, 0xBC, 0xE4, 0xBD, 0xA0, 0xB3, 0xF7, 0xE3, 0x6C, 0x9D, 0xA7,
+        ];
+        let expected_two_block_output: [u8; 28] = [
+            0x75, 0x38, 0x8B, 0x16, 0x51, 0x27, 0x76, 0xCC, 0x5D, 0xBA, 0x5D, 0xA1, 0xFD, 0x89,
+            0x01, 0x50, 0xB0, 0xC6, 0x45, 0x5C, 0xB4, 0xF5, 0x8B, 0x19, 0x52, 0x52, 0x25, 0x25,
+        ];
+
+        assert_eq!(empty_output, expected_empty_output);
+        assert_eq!(abc_output, expected_abc_output);
+        assert_eq!(two_block_output, expected_two_block_output);
+    }
+
+    #[test]
+    fn test_sha256_streaming() {
+        // One million repetitions of "a", fed in uneven pieces.
+        let message = vec![b'a'; 1_000_000];
+        let mut hasher = Sha256::new();
+        for piece in message.chunks(999) {
+            hasher.update(piece);
+        }
+
+        let expected_output: [u8; 32] = [
+            0xCD, 0xC7, 0x6E, 0x5C, 0x99, 0x14, 0xFB, 0x92, 0x81, 0xA1, 0xC7, 0xE2, 0x84, 0xD7,
+            0x3E, 0x67, 0xF1, 0x80, 0x9A, 0x48, 0xA4, 0x97, 0x20, 0x0E, 0x04, 0x6D, 0x39, 0xCC,
+            0xC7, 0x11, 0x2C, 0xD0,
+        ];
+        assert_eq!(hasher.finalize(), expected_output);
+
+        let mut hasher = Sha224::new();
+        hasher.update("abc");
+        assert_eq!(hasher.finalize(), default_224("abc"));
+    }
+}
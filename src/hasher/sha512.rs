@@ -0,0 +1,497 @@
+use super::*;
+
+// IV from the spec. First 64 bits of the fractional parts of the square
+// roots of the first 8 primes.
+// https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.180-4.pdf#section.5.3.5
+pub const IV: [u64; 8] = [
+    0x6A09E667F3BCC908,
+    0xBB67AE8584CAA73B,
+    0x3C6EF372FE94F82B,
+    0xA54FF53A5F1D36F1,
+    0x510E527FADE682D1,
+    0x9B05688C2B3E6C1F,
+    0x1F83D9ABFB41BD6B,
+    0x5BE0CD19137E2179,
+];
+
+// SHA-384 uses the 9th through 16th primes instead.
+// https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.180-4.pdf#section.5.3.4
+pub const IV_384: [u64; 8] = [
+    0xCBBB9D5DC1059ED8,
+    0x629A292A367CD507,
+    0x9159015A3070DD17,
+    0x152FECD8F70E5939,
+    0x67332667FFC00B31,
+    0x8EB44A8768581511,
+    0xDB0C2E0D64F98FA7,
+    0x47B5481DBEFA4FA4,
+];
+
+// Precomputed outputs of `iv_512_t(224)` and `iv_512_t(256)`.
+// https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.180-4.pdf#subsection.5.3.6
+pub const IV_512_224: [u64; 8] = [
+    0x8C3D37C819544DA2,
+    0x73E1996689DCD4D6,
+    0x1DFAB7AE32FF9C82,
+    0x679DD514582F9FCF,
+    0x0F6D2B697BD44DA8,
+    0x77E36F7304C48942,
+    0x3F9D85A86A1D36C8,
+    0x1112E6AD91D692A1,
+];
+
+pub const IV_512_256: [u64; 8] = [
+    0x22312194FC2BF72C,
+    0x9F555FA3C84C64C2,
+    0x2393B86B6F53B151,
+    0x963877195940EABD,
+    0x96283EE2A88EFFE3,
+    0xBE5E1E2553863992,
+    0x2B0199FC2C85B8AA,
+    0x0EB72DDC81C52CA2,
+];
+
+// First 64 bits of the fractional parts of the cube roots of the first 80
+// primes.
+const K: [u64; 80] = [
+    0x428A2F98D728AE22,
+    0x7137449123EF65CD,
+    0xB5C0FBCFEC4D3B2F,
+    0xE9B5DBA58189DBBC,
+    0x3956C25BF348B538,
+    0x59F111F1B605D019,
+    0x923F82A4AF194F9B,
+    0xAB1C5ED5DA6D8118,
+    0xD807AA98A3030242,
+    0x12835B0145706FBE,
+    0x243185BE4EE4B28C,
+    0x550C7DC3D5FFB4E2,
+    0x72BE5D74F27B896F,
+    0x80DEB1FE3B1696B1,
+    0x9BDC06A725C71235,
+    0xC19BF174CF692694,
+    0xE49B69C19EF14AD2,
+    0xEFBE4786384F25E3,
+    0x0FC19DC68B8CD5B5,
+    0x240CA1CC77AC9C65,
+    0x2DE92C6F592B0275,
+    0x4A7484AA6EA6E483,
+    0x5CB0A9DCBD41FBD4,
+    0x76F988DA831153B5,
+    0x983E5152EE66DFAB,
+    0xA831C66D2DB43210,
+    0xB00327C898FB213F,
+    0xBF597FC7BEEF0EE4,
+    0xC6E00BF33DA88FC2,
+    0xD5A79147930AA725,
+    0x06CA6351E003826F,
+    0x142929670A0E6E70,
+    0x27B70A8546D22FFC,
+    0x2E1B21385C26C926,
+    0x4D2C6DFC5AC42AED,
+    0x53380D139D95B3DF,
+    0x650A73548BAF63DE,
+    0x766A0ABB3C77B2A8,
+    0x81C2C92E47EDAEE6,
+    0x92722C851482353B,
+    0xA2BFE8A14CF10364,
+    0xA81A664BBC423001,
+    0xC24B8B70D0F89791,
+    0xC76C51A30654BE30,
+    0xD192E819D6EF5218,
+    0xD69906245565A910,
+    0xF40E35855771202A,
+    0x106AA07032BBD1B8,
+    0x19A4C116B8D2D0C8,
+    0x1E376C085141AB53,
+    0x2748774CDF8EEB99,
+    0x34B0BCB5E19B48A8,
+    0x391C0CB3C5C95A63,
+    0x4ED8AA4AE3418ACB,
+    0x5B9CCA4F7763E373,
+    0x682E6FF3D6B2B8A3,
+    0x748F82EE5DEFB2FC,
+    0x78A5636F43172F60,
+    0x84C87814A1F0AB72,
+    0x8CC702081A6439EC,
+    0x90BEFFFA23631E28,
+    0xA4506CEBDE82BDE9,
+    0xBEF9A3F7B2C67915,
+    0xC67178F2E372532B,
+    0xCA273ECEEA26619C,
+    0xD186B8C721C0C207,
+    0xEADA7DD6CDE0EB1E,
+    0xF57D4F7FEE6ED178,
+    0x06F067AA72176FBA,
+    0x0A637DC5A2C898A6,
+    0x113F9804BEF90DAE,
+    0x1B710B35131C471B,
+    0x28DB77F523047D84,
+    0x32CAAB7B40C72493,
+    0x3C9EBE0A15C9BEBC,
+    0x431D67C49C100D4C,
+    0x4CC5D4BECB3E42B6,
+    0x597F299CFC657E2A,
+    0x5FCB6FAB3AD6FAEC,
+    0x6C44198C4A475817,
+];
+
+const REGISTER_NAMES: [&str; 8] = ["a", "b", "c", "d", "e", "f", "g", "h"];
+
+fn compress<T>(state: &mut [u64; 8], words: &[u64], trace: &mut T)
+where
+    T: Trace<u64> + ?Sized,
+{
+    let mut extended_words: [u64; 80] = [0; 80];
+    extended_words[..16].copy_from_slice(words);
+
+    for i in 16..80 {
+        let w15 = extended_words[i - 15];
+        let w2 = extended_words[i - 2];
+        let s0 = w15.rotate_right(1) ^ w15.rotate_right(8) ^ (w15 >> 7);
+        let s1 = w2.rotate_right(19) ^ w2.rotate_right(61) ^ (w2 >> 6);
+        extended_words[i] = extended_words[i - 16]
+            .wrapping_add(s0)
+            .wrapping_add(extended_words[i - 7])
+            .wrapping_add(s1);
+    }
+
+    trace.record(Event::ExtendedWords(&extended_words));
+
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *state;
+
+    for (i, (word, k)) in extended_words.iter().zip(K.iter()).enumerate() {
+        let s1 = e.rotate_right(14) ^ e.rotate_right(18) ^ e.rotate_right(41);
+        let ch = (e & f) ^ ((!e) & g);
+        let temp1 = h
+            .wrapping_add(s1)
+            .wrapping_add(ch)
+            .wrapping_add(*k)
+            .wrapping_add(*word);
+        let s0 = a.rotate_right(28) ^ a.rotate_right(34) ^ a.rotate_right(39);
+        let maj = (a & b) ^ (a & c) ^ (b & c);
+        let temp2 = s0.wrapping_add(maj);
+
+        h = g;
+        g = f;
+        f = e;
+        e = d.wrapping_add(temp1);
+        d = c;
+        c = b;
+        b = a;
+        a = temp1.wrapping_add(temp2);
+        trace.record(Event::Registers {
+            step: i,
+            names: &REGISTER_NAMES,
+            values: &[a, b, c, d, e, f, g, h],
+        });
+    }
+
+    for (word, value) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+        *word = word.wrapping_add(value);
+    }
+}
+
+// Generates the IV for SHA-512/t by hashing the string "SHA-512/t" with a
+// modified SHA-512 IV. None unless 0 < t < 512 and t is not 384, which is
+// SHA-384 with its own IV.
+pub fn iv_512_t(t: usize) -> Option<[u64; 8]> {
+    if t == 0 || t >= 512 || t == 384 {
+        return None;
+    }
+
+    let mut iv = IV;
+    for word in iv.iter_mut() {
+        *word ^= 0xA5A5A5A5A5A5A5A5;
+    }
+
+    let name = format!("SHA-512/{}", t);
+    let digest = core(name.as_str(), name.len(), iv);
+
+    let mut output: [u64; 8] = [0; 8];
+    for (i, ele) in digest.chunks(8).enumerate() {
+        output[i] = u64::from_be_bytes(ele.try_into().unwrap());
+    }
+    Some(output)
+}
+
+pub struct Sha512Compression;
+
+impl CompressionFunction for Sha512Compression {
+    type Word = u64;
+    type ChainingValue = [u64; 8];
+    type Digest = [u8; 64];
+
+    const BLOCK_SIZE: usize = 128;
+    const LENGTH_SIZE: usize = 16;
+    const ENDIAN: Endian = Endian::Big;
+    const IV: Self::ChainingValue = IV;
+
+    fn compress(state: &mut Self::ChainingValue, words: &[u64]) {
+        compress(state, words, &mut NoTrace);
+    }
+
+    fn compress_traced(state: &mut Self::ChainingValue, words: &[u64], trace: &mut dyn Trace<u64>) {
+        compress(state, words, trace);
+    }
+}
+
+// Same compression function, different IV and a truncated digest.
+pub struct Sha384Compression;
+
+impl CompressionFunction for Sha384Compression {
+    type Word = u64;
+    type ChainingValue = [u64; 8];
+    type Digest = [u8; 48];
+
+    const BLOCK_SIZE: usize = 128;
+    const LENGTH_SIZE: usize = 16;
+    const ENDIAN: Endian = Endian::Big;
+    const IV: Self::ChainingValue = IV_384;
+
+    fn compress(state: &mut Self::ChainingValue, words: &[u64]) {
+        compress(state, words, &mut NoTrace);
+    }
+
+    fn compress_traced(state: &mut Self::ChainingValue, words: &[u64], trace: &mut dyn Trace<u64>) {
+        compress(state, words, trace);
+    }
+}
+
+pub type Sha512 = MerkleDamgard<Sha512Compression>;
+pub type Sha384 = MerkleDamgard<Sha384Compression>;
+pub type State = merkle_damgard::State<Sha512Compression>;
+pub type State384 = merkle_damgard::State<Sha384Compression>;
+
+// SHA-512/t for a `t` of `8 * N` bits. Its IV is computed at runtime, so it
+// wraps `Sha512` rather than supplying a constant IV of its own.
+#[derive(Clone)]
+pub struct Sha512T<const N: usize> {
+    inner: Sha512,
+}
+
+impl<const N: usize> Sha512T<N> {
+    // Rejects the widths `iv_512_t` does, when the type is instantiated.
+    const VALID_WIDTH: () = assert!(
+        N > 0 && N < 64 && N != 48,
+        "SHA-512/t needs 0 < t < 512 bits, and t = 384 is SHA-384"
+    );
+}
+
+pub type Sha512_224 = Sha512T<28>;
+pub type Sha512_256 = Sha512T<32>;
+
+impl<const N: usize> Hasher for Sha512T<N> {
+    const BLOCK_SIZE: usize = 128;
+
+    type State = State;
+    type Digest = [u8; N];
+
+    fn new() -> Self {
+        // Avoid hashing the IV string for the two standardized sizes.
+        let iv = match N {
+            28 => IV_512_224,
+            32 => IV_512_256,
+            _ => match iv_512_t(N * 8) {
+                Some(iv) => iv,
+                None => unreachable!("width checked at compile time"),
+            },
+        };
+        Sha512T::from_state(State::new(iv, 0))
+    }
+
+    fn from_state(state: Self::State) -> Self {
+        let () = Self::VALID_WIDTH;
+        Sha512T {
+            inner: Sha512::from_state(state),
+        }
+    }
+
+    fn update<T>(&mut self, message: T)
+    where
+        T: AsBytes,
+    {
+        self.inner.update(message);
+    }
+
+    fn finalize(self) -> Self::Digest {
+        let mut output: [u8; N] = [0; N];
+        output.copy_from_slice(&self.inner.finalize()[..N]);
+        output
+    }
+
+    fn reset(&mut self) {
+        *self = Sha512T::new();
+    }
+}
+
+pub fn core<T>(message: T, total_length: usize, iv: [u64; 8]) -> [u8; 64]
+where
+    T: AsBytes,
+{
+    let bytes = message.as_bytes();
+    let state = State::for_suffix(iv, total_length, bytes.len());
+    let mut hasher = Sha512::from_state(state);
+    hasher.update(bytes);
+    hasher.finalize()
+}
+
+pub fn default<T>(message: T) -> [u8; 64]
+where
+    T: AsBytes,
+{
+    let mut hasher = Sha512::new();
+    hasher.update(message);
+    hasher.finalize()
+}
+
+// `default`, reporting every block's schedule and registers to `trace`.
+pub fn traced<T>(message: T, trace: &mut dyn Trace<u64>) -> [u8; 64]
+where
+    T: AsBytes,
+{
+    merkle_damgard::hash_traced::<Sha512Compression>(State::new(IV, 0), message.as_bytes(), trace)
+}
+
+// `core`, reporting every block's schedule and registers to `trace`.
+pub fn core_traced<T>(
+    message: T,
+    total_length: usize,
+    iv: [u64; 8],
+    trace: &mut dyn Trace<u64>,
+) -> [u8; 64]
+where
+    T: AsBytes,
+{
+    let bytes = message.as_bytes();
+    let state = State::for_suffix(iv, total_length, bytes.len());
+    merkle_damgard::hash_traced::<Sha512Compression>(state, bytes, trace)
+}
+
+pub fn default_384<T>(message: T) -> [u8; 48]
+where
+    T: AsBytes,
+{
+    let mut hasher = Sha384::new();
+    hasher.update(message);
+    hasher.finalize()
+}
+
+pub fn default_512_224<T>(message: T) -> [u8; 28]
+where
+    T: AsBytes,
+{
+    let mut hasher = Sha512_224::new();
+    hasher.update(message);
+    hasher.finalize()
+}
+
+pub fn default_512_256<T>(message: T) -> [u8; 32]
+where
+    T: AsBytes,
+{
+    let mut hasher = Sha512_256::new();
+    hasher.update(message);
+    hasher.finalize()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TWO_BLOCK_MESSAGE: &str = "abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmn\
+        hijklmnoijklmnopjklmnopqklmnopqrlmnopqrsmnopqrstnopqrstu";
+
+    // Example vectors from
+    // https://csrc.nist.gov/projects/cryptographic-standards-and-guidelines/example-values
+    #[test]
+    fn test_sha512() {
+        let empty_output = default("");
+        let abc_output = default("abc");
+        let two_block_output = default(TWO_BLOCK_MESSAGE);
+
+        let expected_empty_output: [u8; 64] = [
+            0xCF, 0x83, 0xE1, 0x35, 0x7E, 0xEF, 0xB8, 0xBD, 0xF1, 0x54, 0x28, 0x50, 0xD6, 0x6D,
+            0x80, 0x07, 0xD6, 0x20, 0xE4, 0x05, 0x0B, 0x57, 0x15, 0xDC, 0x83, 0xF4, 0xA9, 0x21,
+            0xD3, 0x6C, 0xE9, 0xCE, 0x47, 0xD0, 0xD1, 0x3C, 0x5D, 0x85, 0xF2, 0xB0, 0xFF, 0x83,
+            0x18, 0xD2, 0x87, 0x7E, 0xEC, 0x2F, 0x63, 0xB9, 0x31, 0xBD, 0x47, 0x41, 0x7A, 0x81,
+            0xA5, 0x38, 0x32, 0x7A, 0xF9, 0x27, 0xDA, 0x3E,
+        ];
+        let expected_abc_output: [u8; 64] = [
+            0xDD, 0xAF, 0x35, 0xA1, 0x93, 0x61, 0x7A, 0xBA, 0xCC, 0x41, 0x73, 0x49, 0xAE, 0x20,
+            0x41, 0x31, 0x12, 0xE6, 0xFA, 0x4E, 0x89, 0xA9, 0x7E, 0xA2, 0x0A, 0x9E, 0xEE, 0xE6,
+            0x4B, 0x55, 0xD3, 0x9A, 0x21, 0x92, 0x99, 0x2A, 0x27, 0x4F, 0xC1, 0xA8, 0x36, 0xBA,
+            0x3C, 0x23, 0xA3, 0xFE, 0xEB, 0xBD, 0x45, 0x4D, 0x44, 0x23, 0x64, 0x3C, 0xE8, 0x0E,
+            0x2A, 0x9A, 0xC9, 0x4F, 0xA5, 0x4C, 0xA4, 0x9F,
+        ];
+        let expected_two_block_output: [u8; 64] = [
+            0x8E, 0x95, 0x9B, 0x75, 0xDA, 0xE3, 0x13, 0xDA, 0x8C, 0xF4, 0xF7, 0x28, 0x14, 0xFC,
+            0x14, 0x3F, 0x8F, 0x77, 0x79, 0xC6, 0xEB, 0x9F, 0x7F, 0xA1, 0x72, 0x99, 0xAE, 0xAD,
+            0xB6, 0x88, 0x90, 0x18, 0x50, 0x1D, 0x28, 0x9E, 0x49, 0x00, 0xF7, 0xE4, 0x33, 0x1B,
+            0x99, 0xDE, 0xC4, 0xB5, 0x43, 0x3A, 0xC7, 0xD3, 0x29, 0xEE, 0xB6, 0xDD, 0x26, 0x54,
+            0x5E, 0x96, 0xE5, 0x5B, 0x87, 0x4B, 0xE9, 0x09,
+        ];
+
+        assert_eq!(empty_output, expected_empty_output);
+        assert_eq!(abc_output, expected_abc_output);
+        assert_eq!(two_block_output, expected_two_block_output);
+    }
+
+    #[test]
+    fn test_sha384() {
+        let abc_output = default_384("abc");
+        let two_block_output = default_384(TWO_BLOCK_MESSAGE);
+
+        let expected_abc_output: [u8; 48] = [
+            0xCB, 0x00, 0x75, 0x3F, 0x45, 0xA3, 0x5E, 0x8B, 0xB5, 0xA0, 0x3D, 0x69, 0x9A, 0xC6,
+            0x50, 0x07, 0x27, 0x2C, 0x32, 0xAB, 0x0E, 0xDE, 0xD1, 0x63, 0x1A, 0x8B, 0x60, 0x5A,
+            0x43, 0xFF, 0x5B, 0xED, 0x80, 0x86, 0x07, 0x2B, 0xA1, 0xE7, 0xCC, 0x23, 0x58, 0xBA,
+            0xEC, 0xA1, 0x34, 0xC8, 0x25, 0xA7,
+        ];
+        let expected_two_block_output: [u8; 48] = [
+            0x09, 0x33, 0x0C, 0x33, 0xF7, 0x11, 0x47, 0xE8, 0x3D, 0x19, 0x2F, 0xC7, 0x82, 0xCD,
+            0x1B, 0x47, 0x53, 0x11, 0x1B, 0x17, 0x3B, 0x3B, 0x05, 0xD2, 0x2F, 0xA0, 0x80, 0x86,
+            0xE3, 0xB0, 0xF7, 0x12, 0xFC, 0xC7, 0xC7, 0x1A, 0x55, 0x7E, 0x2D, 0xB9, 0x66, 0xC3,
+            0xE9, 0xFA, 0x91, 0x74, 0x60, 0x39,
+        ];
+
+        assert_eq!(abc_output, expected_abc_output);
+        assert_eq!(two_block_output, expected_two_block_output);
+    }
+
+    #[test]
+    fn test_sha512_t() {
+        let abc_224_output = default_512_224("abc");
+        let abc_256_output = default_512_256("abc");
+
+        let expected_abc_224_output: [u8; 28] = [
+            0x46, 0x34, 0x27, 0x0F, 0x70, 0x7B, 0x6A, 0x54, 0xDA, 0xAE, 0x75, 0x30, 0x46, 0x08,
+            0x42, 0xE2, 0x0E, 0x37, 0xED, 0x26, 0x5C, 0xEE, 0xE9, 0xA4, 0x3E, 0x89, 0x24, 0xAA,
+        ];
+        let expected_abc_256_output: [u8; 32] = [
+            0x53, 0x04, 0x8E, 0x26, 0x81, 0x94, 0x1E, 0xF9, 0x9B, 0x2E, 0x29, 0xB7, 0x6B, 0x4C,
+            0x7D, 0xAB, 0xE4, 0xC2, 0xD0, 0xC6, 0x34, 0xFC, 0x6D, 0x46, 0xE0, 0xE2, 0xF1, 0x31,
+            0x07, 0xE7, 0xAF, 0x23,
+        ];
+
+        assert_eq!(abc_224_output, expected_abc_224_output);
+        assert_eq!(abc_256_output, expected_abc_256_output);
+
+        // The precomputed IVs match the generation procedure.
+        assert_eq!(iv_512_t(224), Some(IV_512_224));
+        assert_eq!(iv_512_t(256), Some(IV_512_256));
+        assert_eq!(iv_512_t(384), None);
+        assert_eq!(iv_512_t(512), None);
+    }
+
+    #[test]
+    fn test_md_padding_128() {
+        // 111 bytes fill a block with the 1 bit and the 128-bit length.
+        assert_eq!(md_padding_128(111, Endian::Big).len(), 17);
+        assert_eq!(md_padding_128(112, Endian::Big).len(), 144);
+
+        let padding = md_padding_128(3, Endian::Big);
+        assert_eq!(padding.len(), 125);
+        assert_eq!(padding[padding.len() - 16..], 24u128.to_be_bytes());
+    }
+}
@@ -0,0 +1,349 @@
+use super::keccak::{Sponge, DOMAIN_CSHAKE, DOMAIN_SHAKE, STATE_SIZE};
+use super::*;
+use std::io;
+
+// Extendable-output functions from FIPS 202 and SP 800-185. `RATE` is the
+// sponge rate in bytes: 168 for the 128-bit security level and 136 for the
+// 256-bit one.
+// https://nvlpubs.nist.gov/nistpubs/SpecialPublications/NIST.SP.800-185.pdf
+
+// Output half of an XOF. `squeeze` can be called as many times as needed and
+// each call continues where the previous one stopped.
+#[derive(Clone)]
+pub struct XofReader {
+    sponge: Sponge,
+}
+
+impl XofReader {
+    pub fn squeeze(&mut self, output: &mut [u8]) {
+        self.sponge.squeeze(output);
+    }
+}
+
+impl io::Read for XofReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.squeeze(buf);
+        Ok(buf.len())
+    }
+}
+
+// Encodes `x` with its length in bytes prepended.
+pub fn left_encode(x: u64) -> Vec<u8> {
+    let bytes = x.to_be_bytes();
+    let skip = bytes.iter().take_while(|byte| **byte == 0).count().min(7);
+    let mut encoded = vec![(8 - skip) as u8];
+    encoded.extend(&bytes[skip..]);
+    encoded
+}
+
+// Encodes `x` with its length in bytes appended.
+pub fn right_encode(x: u64) -> Vec<u8> {
+    let bytes = x.to_be_bytes();
+    let skip = bytes.iter().take_while(|byte| **byte == 0).count().min(7);
+    let mut encoded = bytes[skip..].to_vec();
+    encoded.push((8 - skip) as u8);
+    encoded
+}
+
+// Prefixes a string with its length in bits.
+pub fn encode_string(bytes: &[u8]) -> Vec<u8> {
+    let mut encoded = left_encode(bytes.len() as u64 * 8);
+    encoded.extend(bytes);
+    encoded
+}
+
+// Prefixes `bytes` with the encoded width `w` and pads with zeros to a
+// multiple of `w`.
+pub fn bytepad(bytes: &[u8], w: usize) -> Vec<u8> {
+    let mut padded = left_encode(w as u64);
+    padded.extend(bytes);
+    let remainder = padded.len() % w;
+    if remainder > 0 {
+        padded.extend(vec![0; w - remainder]);
+    }
+    padded
+}
+
+#[derive(Clone)]
+pub struct Shake<const RATE: usize> {
+    sponge: Sponge,
+}
+
+pub type Shake128 = Shake<168>;
+pub type Shake256 = Shake<136>;
+
+impl<const RATE: usize> Shake<RATE> {
+    pub fn new() -> Self {
+        Shake {
+            sponge: Sponge::new(RATE, STATE_SIZE - RATE, DOMAIN_SHAKE),
+        }
+    }
+
+    pub fn update<T>(&mut self, message: T)
+    where
+        T: AsBytes,
+    {
+        self.sponge.absorb(message.as_bytes());
+    }
+
+    pub fn finalize_xof(self) -> XofReader {
+        XofReader {
+            sponge: self.sponge,
+        }
+    }
+}
+
+impl<const RATE: usize> Default for Shake<RATE> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// cSHAKE adds a function name, reserved for NIST defined functions such as
+// KMAC, and a user chosen customization string. With both empty it is
+// plain SHAKE.
+#[derive(Clone)]
+pub struct CShake<const RATE: usize> {
+    sponge: Sponge,
+}
+
+pub type CShake128 = CShake<168>;
+pub type CShake256 = CShake<136>;
+
+impl<const RATE: usize> CShake<RATE> {
+    pub fn new(function_name: &[u8], customization: &[u8]) -> Self {
+        if function_name.is_empty() && customization.is_empty() {
+            return CShake {
+                sponge: Shake::<RATE>::new().sponge,
+            };
+        }
+
+        let mut sponge = Sponge::new(RATE, STATE_SIZE - RATE, DOMAIN_CSHAKE);
+        let prefix = [encode_string(function_name), encode_string(customization)].concat();
+        sponge.absorb(&bytepad(&prefix, RATE));
+        CShake { sponge }
+    }
+
+    pub fn update<T>(&mut self, message: T)
+    where
+        T: AsBytes,
+    {
+        self.sponge.absorb(message.as_bytes());
+    }
+
+    pub fn finalize_xof(self) -> XofReader {
+        XofReader {
+            sponge: self.sponge,
+        }
+    }
+}
+
+// KMAC is cSHAKE keyed by absorbing a padded block holding the key before
+// the message. The requested output length is bound into the MAC, so a
+// shorter tag is not a prefix of a longer one.
+#[derive(Clone)]
+pub struct Kmac<const RATE: usize> {
+    inner: CShake<RATE>,
+}
+
+pub type Kmac128 = Kmac<168>;
+pub type Kmac256 = Kmac<136>;
+
+impl<const RATE: usize> Kmac<RATE> {
+    pub fn new(key: &[u8], customization: &[u8]) -> Self {
+        let mut inner = CShake::new(b"KMAC", customization);
+        inner.update(bytepad(&encode_string(key), RATE).as_slice());
+        Kmac { inner }
+    }
+
+    pub fn update<T>(&mut self, message: T)
+    where
+        T: AsBytes,
+    {
+        self.inner.update(message);
+    }
+
+    pub fn finalize(mut self, output_length: usize) -> Vec<u8> {
+        self.inner
+            .update(right_encode(output_length as u64 * 8).as_slice());
+
+        let mut output = vec![0; output_length];
+        self.inner.finalize_xof().squeeze(&mut output);
+        output
+    }
+
+    // KMACXOF, for when the output length is not known up front.
+    pub fn finalize_xof(mut self) -> XofReader {
+        self.inner.update(right_encode(0).as_slice());
+        self.inner.finalize_xof()
+    }
+}
+
+pub fn shake128<T>(message: T, output_length: usize) -> Vec<u8>
+where
+    T: AsBytes,
+{
+    let mut hasher = Shake128::new();
+    hasher.update(message);
+
+    let mut output = vec![0; output_length];
+    hasher.finalize_xof().squeeze(&mut output);
+    output
+}
+
+pub fn shake256<T>(message: T, output_length: usize) -> Vec<u8>
+where
+    T: AsBytes,
+{
+    let mut hasher = Shake256::new();
+    hasher.update(message);
+
+    let mut output = vec![0; output_length];
+    hasher.finalize_xof().squeeze(&mut output);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn test_shake() {
+        let expected_empty_128: [u8; 32] = [
+            0x7F, 0x9C, 0x2B, 0xA4, 0xE8, 0x8F, 0x82, 0x7D, 0x61, 0x60, 0x45, 0x50, 0x76, 0x05,
+            0x85, 0x3E, 0xD7, 0x3B, 0x80, 0x93, 0xF6, 0xEF, 0xBC, 0x88, 0xEB, 0x1A, 0x6E, 0xAC,
+            0xFA, 0x66, 0xEF, 0x26,
+        ];
+        let expected_empty_256: [u8; 64] = [
+            0x46, 0xB9, 0xDD, 0x2B, 0x0B, 0xA8, 0x8D, 0x13, 0x23, 0x3B, 0x3F, 0xEB, 0x74, 0x3E,
+            0xEB, 0x24, 0x3F, 0xCD, 0x52, 0xEA, 0x62, 0xB8, 0x1B, 0x82, 0xB5, 0x0C, 0x27, 0x64,
+            0x6E, 0xD5, 0x76, 0x2F, 0xD7, 0x5D, 0xC4, 0xDD, 0xD8, 0xC0, 0xF2, 0x00, 0xCB, 0x05,
+            0x01, 0x9D, 0x67, 0xB5, 0x92, 0xF6, 0xFC, 0x82, 0x1C, 0x49, 0x47, 0x9A, 0xB4, 0x86,
+            0x40, 0x29, 0x2E, 0xAC, 0xB3, 0xB7, 0xC4, 0xBE,
+        ];
+
+        assert_eq!(shake128("", 32), expected_empty_128);
+        assert_eq!(shake256("", 64), expected_empty_256);
+    }
+
+    #[test]
+    fn test_shake_repeated_squeeze() {
+        let message = [0xA3; 200];
+        let one_shot = shake128(message.as_slice(), 512);
+
+        // Squeeze in uneven pieces that cross the rate boundary.
+        let mut hasher = Shake128::new();
+        hasher.update(message.as_slice());
+        let mut reader = hasher.finalize_xof();
+        let mut output = vec![0; 512];
+        for piece in output.chunks_mut(100) {
+            reader.squeeze(piece);
+        }
+        assert_eq!(output, one_shot);
+
+        // Last 32 bytes of the 4096-bit output.
+        let expected_tail: [u8; 32] = [
+            0x44, 0xC9, 0xFB, 0x35, 0x9F, 0xD5, 0x6A, 0xC0, 0xA9, 0xA7, 0x5A, 0x74, 0x3C, 0xFF,
+            0x68, 0x62, 0xF1, 0x7D, 0x72, 0x59, 0xAB, 0x07, 0x52, 0x16, 0xC0, 0x69, 0x95, 0x11,
+            0x64, 0x3B, 0x64, 0x39,
+        ];
+        assert_eq!(output[480..], expected_tail);
+
+        // The io::Read implementation reads the same stream.
+        let mut hasher = Shake128::new();
+        hasher.update(message.as_slice());
+        let mut read_output = vec![0; 512];
+        hasher.finalize_xof().read_exact(&mut read_output).unwrap();
+        assert_eq!(read_output, one_shot);
+    }
+
+    #[test]
+    fn test_encodings() {
+        assert_eq!(left_encode(0), [1, 0]);
+        assert_eq!(left_encode(168), [1, 168]);
+        assert_eq!(left_encode(256), [2, 1, 0]);
+        assert_eq!(right_encode(0), [0, 1]);
+        assert_eq!(right_encode(256), [1, 0, 2]);
+        assert_eq!(encode_string(b""), [1, 0]);
+        assert_eq!(bytepad(b"", 8), [1, 8, 0, 0, 0, 0, 0, 0]);
+    }
+
+    // Sample vectors from
+    // https://csrc.nist.gov/projects/cryptographic-standards-and-guidelines/example-values
+    #[test]
+    fn test_cshake() {
+        let short_data: Vec<u8> = (0x00..=0x03).collect();
+        let long_data: Vec<u8> = (0x00..=0xC7).collect();
+
+        let mut output = [0; 32];
+        let mut hasher = CShake128::new(b"", b"Email Signature");
+        hasher.update(short_data.as_slice());
+        hasher.finalize_xof().squeeze(&mut output);
+        let expected_sample_1: [u8; 32] = [
+            0xC1, 0xC3, 0x69, 0x25, 0xB6, 0x40, 0x9A, 0x04, 0xF1, 0xB5, 0x04, 0xFC, 0xBC, 0xA9,
+            0xD8, 0x2B, 0x40, 0x17, 0x27, 0x7C, 0xB5, 0xED, 0x2B, 0x20, 0x65, 0xFC, 0x1D, 0x38,
+            0x14, 0xD5, 0xAA, 0xF5,
+        ];
+        assert_eq!(output, expected_sample_1);
+
+        let mut hasher = CShake128::new(b"", b"Email Signature");
+        hasher.update(long_data.as_slice());
+        hasher.finalize_xof().squeeze(&mut output);
+        let expected_sample_2: [u8; 32] = [
+            0xC5, 0x22, 0x1D, 0x50, 0xE4, 0xF8, 0x22, 0xD9, 0x6A, 0x2E, 0x88, 0x81, 0xA9, 0x61,
+            0x42, 0x0F, 0x29, 0x4B, 0x7B, 0x24, 0xFE, 0x3D, 0x20, 0x94, 0xBA, 0xED, 0x2C, 0x65,
+            0x24, 0xCC, 0x16, 0x6B,
+        ];
+        assert_eq!(output, expected_sample_2);
+
+        // Empty strings fall back to SHAKE.
+        let mut hasher = CShake256::new(b"", b"");
+        hasher.update("abc");
+        let mut output = [0; 64];
+        hasher.finalize_xof().squeeze(&mut output);
+        assert_eq!(output.to_vec(), shake256("abc", 64));
+    }
+
+    #[test]
+    fn test_kmac() {
+        let key: Vec<u8> = (0x40..=0x5F).collect();
+        let data: Vec<u8> = (0x00..=0x03).collect();
+
+        let mut mac = Kmac128::new(&key, b"");
+        mac.update(data.as_slice());
+        let expected_sample_1: [u8; 32] = [
+            0xE5, 0x78, 0x0B, 0x0D, 0x3E, 0xA6, 0xF7, 0xD3, 0xA4, 0x29, 0xC5, 0x70, 0x6A, 0xA4,
+            0x3A, 0x00, 0xFA, 0xDB, 0xD7, 0xD4, 0x96, 0x28, 0x83, 0x9E, 0x31, 0x87, 0x24, 0x3F,
+            0x45, 0x6E, 0xE1, 0x4E,
+        ];
+        assert_eq!(mac.finalize(32), expected_sample_1);
+
+        let mut mac = Kmac128::new(&key, b"My Tagged Application");
+        mac.update(data.as_slice());
+        let expected_sample_2: [u8; 32] = [
+            0x3B, 0x1F, 0xBA, 0x96, 0x3C, 0xD8, 0xB0, 0xB5, 0x9E, 0x8C, 0x1A, 0x6D, 0x71, 0x88,
+            0x8B, 0x71, 0x43, 0x65, 0x1A, 0xF8, 0xBA, 0x0A, 0x70, 0x70, 0xC0, 0x97, 0x9E, 0x28,
+            0x11, 0x32, 0x4A, 0xA5,
+        ];
+        assert_eq!(mac.finalize(32), expected_sample_2);
+
+        let mut mac = Kmac256::new(&key, b"My Tagged Application");
+        mac.update(data.as_slice());
+        let expected_sample_4: [u8; 64] = [
+            0x20, 0xC5, 0x70, 0xC3, 0x13, 0x46, 0xF7, 0x03, 0xC9, 0xAC, 0x36, 0xC6, 0x1C, 0x03,
+            0xCB, 0x64, 0xC3, 0x97, 0x0D, 0x0C, 0xFC, 0x78, 0x7E, 0x9B, 0x79, 0x59, 0x9D, 0x27,
+            0x3A, 0x68, 0xD2, 0xF7, 0xF6, 0x9D, 0x4C, 0xC3, 0xDE, 0x9D, 0x10, 0x4A, 0x35, 0x16,
+            0x89, 0xF2, 0x7C, 0xF6, 0xF5, 0x95, 0x1F, 0x01, 0x03, 0xF3, 0x3F, 0x4F, 0x24, 0x87,
+            0x10, 0x24, 0xD9, 0xC2, 0x77, 0x73, 0xA8, 0xDD,
+        ];
+        assert_eq!(mac.finalize(64), expected_sample_4);
+
+        // Binding the length means a short tag is not a prefix of a long one.
+        let mut short = Kmac128::new(&key, b"");
+        short.update(data.as_slice());
+        let mut long = Kmac128::new(&key, b"");
+        long.update(data.as_slice());
+        assert_ne!(short.finalize(16), long.finalize(32)[..16]);
+    }
+}
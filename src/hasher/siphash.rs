@@ -0,0 +1,493 @@
+use super::trace::{Event, NoTrace, Trace};
+use std::hash::{BuildHasher, Hasher as StdHasher};
+use std::marker::PhantomData;
+
+// SipHash, a keyed PRF over short inputs meant for hash tables: with a secret
+// key per table an attacker can't precompute colliding keys. SipHash-c-d runs
+// c rounds per 8 byte word and d rounds at the end.
+// https://www.aumasson.jp/siphash/siphash.pdf
+
+// Round counts distinguishing SipHash-2-4 from SipHash-1-3.
+pub trait Variant {
+    const COMPRESSION_ROUNDS: usize;
+    const FINALIZATION_ROUNDS: usize;
+}
+
+// The variant from the paper.
+#[derive(Clone, Copy, Debug)]
+pub struct Sip24;
+
+impl Variant for Sip24 {
+    const COMPRESSION_ROUNDS: usize = 2;
+    const FINALIZATION_ROUNDS: usize = 4;
+}
+
+// Faster variant used by Rust's and Python's hash tables.
+#[derive(Clone, Copy, Debug)]
+pub struct Sip13;
+
+impl Variant for Sip13 {
+    const COMPRESSION_ROUNDS: usize = 1;
+    const FINALIZATION_ROUNDS: usize = 3;
+}
+
+// "somepseudorandomlygeneratedbytes"
+const INIT: [u64; 4] = [
+    0x736F6D6570736575,
+    0x646F72616E646F6D,
+    0x6C7967656E657261,
+    0x7465646279746573,
+];
+
+const REGISTER_NAMES: [&str; 4] = ["v0", "v1", "v2", "v3"];
+
+// State shared by the 64 and 128-bit outputs. Input is consumed 8 bytes at a
+// time, partial words wait in `tail`.
+struct Core<V> {
+    v: [u64; 4],
+    tail: u64,
+    tail_length: usize,
+    length: usize,
+    variant: PhantomData<V>,
+}
+
+impl<V> Clone for Core<V> {
+    fn clone(&self) -> Self {
+        Core {
+            v: self.v,
+            tail: self.tail,
+            tail_length: self.tail_length,
+            length: self.length,
+            variant: PhantomData,
+        }
+    }
+}
+
+impl<V> Core<V>
+where
+    V: Variant,
+{
+    fn new(key: &[u8; 16], wide: bool) -> Self {
+        let k0 = u64::from_le_bytes(key[..8].try_into().unwrap());
+        let k1 = u64::from_le_bytes(key[8..].try_into().unwrap());
+        let mut v = [INIT[0] ^ k0, INIT[1] ^ k1, INIT[2] ^ k0, INIT[3] ^ k1];
+        if wide {
+            v[1] ^= 0xEE;
+        }
+        Core {
+            v,
+            tail: 0,
+            tail_length: 0,
+            length: 0,
+            variant: PhantomData,
+        }
+    }
+
+    fn sip_round<T>(v: &mut [u64; 4], step: usize, trace: &mut T)
+    where
+        T: Trace<u64> + ?Sized,
+    {
+        v[0] = v[0].wrapping_add(v[1]);
+        v[1] = v[1].rotate_left(13) ^ v[0];
+        v[0] = v[0].rotate_left(32);
+        v[2] = v[2].wrapping_add(v[3]);
+        v[3] = v[3].rotate_left(16) ^ v[2];
+        v[0] = v[0].wrapping_add(v[3]);
+        v[3] = v[3].rotate_left(21) ^ v[0];
+        v[2] = v[2].wrapping_add(v[1]);
+        v[1] = v[1].rotate_left(17) ^ v[2];
+        v[2] = v[2].rotate_left(32);
+        trace.record(Event::Registers {
+            step,
+            names: &REGISTER_NAMES,
+            values: v,
+        });
+    }
+
+    // Absorbs message word number `block`.
+    fn compress<T>(v: &mut [u64; 4], word: u64, block: usize, trace: &mut T)
+    where
+        T: Trace<u64> + ?Sized,
+    {
+        trace.record(Event::ChainingValue { block, value: v });
+        trace.record(Event::ExtendedWords(&[word]));
+        v[3] ^= word;
+        for round in 0..V::COMPRESSION_ROUNDS {
+            Self::sip_round(v, round, trace);
+        }
+        v[0] ^= word;
+    }
+
+    fn update(&mut self, bytes: &[u8]) {
+        self.update_with(bytes, &mut NoTrace);
+    }
+
+    fn update_with<T>(&mut self, bytes: &[u8], trace: &mut T)
+    where
+        T: Trace<u64> + ?Sized,
+    {
+        for byte in bytes {
+            self.tail |= (*byte as u64) << (8 * self.tail_length);
+            self.tail_length += 1;
+            self.length += 1;
+            if self.tail_length == 8 {
+                Self::compress(&mut self.v, self.tail, self.length / 8 - 1, trace);
+                self.tail = 0;
+                self.tail_length = 0;
+            }
+        }
+    }
+
+    fn finalize(&self, words: usize) -> [u64; 2] {
+        self.finalize_with(words, &mut NoTrace)
+    }
+
+    // Output words, one or two depending on the mode the state was keyed for.
+    // Traces report the finalization rounds as one more block after the
+    // last word, and the output words as the final chaining value.
+    fn finalize_with<T>(&self, words: usize, trace: &mut T) -> [u64; 2]
+    where
+        T: Trace<u64> + ?Sized,
+    {
+        let mut v = self.v;
+        // The last word carries the length mod 256 in its top byte.
+        let block = self.length / 8;
+        Self::compress(
+            &mut v,
+            self.tail | ((self.length as u64) << 56),
+            block,
+            trace,
+        );
+
+        let mut output = [0u64; 2];
+        v[2] ^= if words == 2 { 0xEE } else { 0xFF };
+        trace.record(Event::ChainingValue {
+            block: block + 1,
+            value: &v,
+        });
+        for (i, word) in output.iter_mut().take(words).enumerate() {
+            if i == 1 {
+                v[1] ^= 0xDD;
+            }
+            for round in 0..V::FINALIZATION_ROUNDS {
+                Self::sip_round(&mut v, i * V::FINALIZATION_ROUNDS + round, trace);
+            }
+            *word = v[0] ^ v[1] ^ v[2] ^ v[3];
+        }
+        trace.record(Event::ChainingValue {
+            block: block + 2,
+            value: &output[..words],
+        });
+        output
+    }
+}
+
+// SipHash with 64-bit output. Implements std::hash::Hasher so it can back a
+// HashMap through SipBuildHasher.
+pub struct SipHasher<V> {
+    core: Core<V>,
+}
+
+pub type SipHasher24 = SipHasher<Sip24>;
+pub type SipHasher13 = SipHasher<Sip13>;
+
+impl<V> Clone for SipHasher<V> {
+    fn clone(&self) -> Self {
+        SipHasher {
+            core: self.core.clone(),
+        }
+    }
+}
+
+impl<V> SipHasher<V>
+where
+    V: Variant,
+{
+    pub fn new(key: &[u8; 16]) -> Self {
+        SipHasher {
+            core: Core::new(key, false),
+        }
+    }
+
+    pub fn update(&mut self, bytes: &[u8]) {
+        self.core.update(bytes);
+    }
+
+    pub fn finalize(&self) -> u64 {
+        self.core.finalize(1)[0]
+    }
+
+    // Hashes `message` and finalizes, reporting every word's SipRounds and
+    // the finalization rounds to `trace`.
+    pub fn traced(mut self, message: &[u8], trace: &mut dyn Trace<u64>) -> u64 {
+        self.core.update_with(message, trace);
+        self.core.finalize_with(1, trace)[0]
+    }
+}
+
+impl<V> StdHasher for SipHasher<V>
+where
+    V: Variant,
+{
+    fn write(&mut self, bytes: &[u8]) {
+        self.update(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        self.finalize()
+    }
+}
+
+// SipHash with 128-bit output, as bytes in the reference order.
+pub struct SipHasher128<V> {
+    core: Core<V>,
+}
+
+impl<V> Clone for SipHasher128<V> {
+    fn clone(&self) -> Self {
+        SipHasher128 {
+            core: self.core.clone(),
+        }
+    }
+}
+
+impl<V> SipHasher128<V>
+where
+    V: Variant,
+{
+    pub fn new(key: &[u8; 16]) -> Self {
+        SipHasher128 {
+            core: Core::new(key, true),
+        }
+    }
+
+    pub fn update(&mut self, bytes: &[u8]) {
+        self.core.update(bytes);
+    }
+
+    pub fn finalize(&self) -> [u8; 16] {
+        Self::output_bytes(self.core.finalize(2))
+    }
+
+    // Hashes `message` and finalizes, reporting like `SipHasher::traced`.
+    pub fn traced(mut self, message: &[u8], trace: &mut dyn Trace<u64>) -> [u8; 16] {
+        self.core.update_with(message, trace);
+        Self::output_bytes(self.core.finalize_with(2, trace))
+    }
+
+    fn output_bytes([low, high]: [u64; 2]) -> [u8; 16] {
+        let mut output = [0u8; 16];
+        output[..8].copy_from_slice(&low.to_le_bytes());
+        output[8..].copy_from_slice(&high.to_le_bytes());
+        output
+    }
+}
+
+impl<V> StdHasher for SipHasher128<V>
+where
+    V: Variant,
+{
+    fn write(&mut self, bytes: &[u8]) {
+        self.update(bytes);
+    }
+
+    // The first half of the 128-bit output.
+    fn finish(&self) -> u64 {
+        self.core.finalize(2)[0]
+    }
+}
+
+// Hands out hashers sharing one key, e.g.
+// HashMap::with_hasher(SipBuildHasher::<Sip13>::new(key)). The key should be
+// random and secret, a fixed key is as floodable as an unkeyed hash.
+pub struct SipBuildHasher<V> {
+    key: [u8; 16],
+    variant: PhantomData<V>,
+}
+
+impl<V> Clone for SipBuildHasher<V> {
+    fn clone(&self) -> Self {
+        SipBuildHasher {
+            key: self.key,
+            variant: PhantomData,
+        }
+    }
+}
+
+impl<V> SipBuildHasher<V>
+where
+    V: Variant,
+{
+    pub fn new(key: [u8; 16]) -> Self {
+        SipBuildHasher {
+            key,
+            variant: PhantomData,
+        }
+    }
+}
+
+impl<V> BuildHasher for SipBuildHasher<V>
+where
+    V: Variant,
+{
+    type Hasher = SipHasher<V>;
+
+    fn build_hasher(&self) -> SipHasher<V> {
+        SipHasher::new(&self.key)
+    }
+}
+
+pub fn siphash24(key: &[u8; 16], message: &[u8]) -> u64 {
+    let mut hasher = SipHasher24::new(key);
+    hasher.update(message);
+    hasher.finalize()
+}
+
+pub fn siphash13(key: &[u8; 16], message: &[u8]) -> u64 {
+    let mut hasher = SipHasher13::new(key);
+    hasher.update(message);
+    hasher.finalize()
+}
+
+pub fn siphash24_128(key: &[u8; 16], message: &[u8]) -> [u8; 16] {
+    let mut hasher = SipHasher128::<Sip24>::new(key);
+    hasher.update(message);
+    hasher.finalize()
+}
+
+pub fn siphash13_128(key: &[u8; 16], message: &[u8]) -> [u8; 16] {
+    let mut hasher = SipHasher128::<Sip13>::new(key);
+    hasher.update(message);
+    hasher.finalize()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    // Reference setup: key 00 01 .. 0F, message 00 01 .. of each length.
+    fn key() -> [u8; 16] {
+        core::array::from_fn(|i| i as u8)
+    }
+
+    fn message(length: usize) -> Vec<u8> {
+        (0..length as u8).collect()
+    }
+
+    // Vectors from the reference implementation's vectors.h.
+    // https://github.com/veorq/SipHash/blob/master/vectors.h
+    #[test]
+    fn test_siphash24() {
+        let lengths = [0, 1, 7, 8, 15, 63];
+        let expected_outputs: [u64; 6] = [
+            0x726FDB47DD0E0E31,
+            0x74F839C593DC67FD,
+            0xAB0200F58B01D137,
+            0x93F5F5799A932462,
+            0xA129CA6149BE45E5,
+            0x958A324CEB064572,
+        ];
+        for (length, expected_output) in lengths.iter().zip(expected_outputs) {
+            assert_eq!(siphash24(&key(), &message(*length)), expected_output);
+        }
+
+        let expected_empty_output: [u8; 16] = [
+            0xA3, 0x81, 0x7F, 0x04, 0xBA, 0x25, 0xA8, 0xE6, 0x6D, 0xF6, 0x72, 0x14, 0xC7, 0x55,
+            0x02, 0x93,
+        ];
+        let expected_15_output: [u8; 16] = [
+            0x54, 0x93, 0xE9, 0x99, 0x33, 0xB0, 0xA8, 0x11, 0x7E, 0x08, 0xEC, 0x0F, 0x97, 0xCF,
+            0xC3, 0xD9,
+        ];
+        assert_eq!(siphash24_128(&key(), &message(0)), expected_empty_output);
+        assert_eq!(siphash24_128(&key(), &message(15)), expected_15_output);
+    }
+
+    #[test]
+    fn test_siphash13() {
+        let lengths = [0, 1, 7, 8, 15, 63];
+        let expected_outputs: [u64; 6] = [
+            0xABAC0158050FC4DC,
+            0xC9F49BF37D57CA93,
+            0xD3927D989BB11140,
+            0x369095118D299A8E,
+            0xD320D86D2A519956,
+            0x9D199062B7BBB3A8,
+        ];
+        for (length, expected_output) in lengths.iter().zip(expected_outputs) {
+            assert_eq!(siphash13(&key(), &message(*length)), expected_output);
+        }
+
+        let expected_empty_output: [u8; 16] = [
+            0xE7, 0x7E, 0xBC, 0xB2, 0x27, 0x88, 0xA5, 0xBE, 0xFD, 0x62, 0xDB, 0x6A, 0xDD, 0x30,
+            0x30, 0x01,
+        ];
+        let expected_15_output: [u8; 16] = [
+            0xC1, 0x7E, 0x55, 0x05, 0xB2, 0xBD, 0x52, 0x6C, 0x29, 0x21, 0xCD, 0xEC, 0x1E, 0x7E,
+            0x01, 0x09,
+        ];
+        assert_eq!(siphash13_128(&key(), &message(0)), expected_empty_output);
+        assert_eq!(siphash13_128(&key(), &message(15)), expected_15_output);
+    }
+
+    #[test]
+    fn test_siphash_streaming() {
+        let message = message(100);
+        for piece_size in [1, 3, 8, 13] {
+            let mut hasher = SipHasher24::new(&key());
+            let mut wide_hasher = SipHasher128::<Sip24>::new(&key());
+            for piece in message.chunks(piece_size) {
+                hasher.write(piece);
+                wide_hasher.write(piece);
+            }
+            assert_eq!(hasher.finish(), siphash24(&key(), &message));
+            assert_eq!(wide_hasher.finalize(), siphash24_128(&key(), &message));
+        }
+    }
+
+    #[test]
+    fn test_siphash_hash_map() {
+        let mut map = HashMap::with_hasher(SipBuildHasher::<Sip13>::new(key()));
+        for i in 0..100 {
+            map.insert(format!("key {}", i), i);
+        }
+        assert_eq!(map.len(), 100);
+        assert_eq!(map.get("key 42"), Some(&42));
+
+        // Different keys, different hashes for the same input.
+        let other = SipBuildHasher::<Sip13>::new([0xAB; 16]);
+        assert_ne!(map.hasher().hash_one("key 42"), other.hash_one("key 42"));
+    }
+
+    #[test]
+    fn test_siphash_traced() {
+        // Two full words and the word holding the last 7 bytes and the length,
+        // then the finalization.
+        let message = message(23);
+        let mut rounds = 0;
+        let mut chaining_values = vec![];
+        let output =
+            SipHasher24::new(&key()).traced(&message, &mut |event: Event<'_, u64>| match event {
+                Event::ChainingValue { block, value } => {
+                    chaining_values.push((block, value.to_vec()))
+                }
+                Event::ExtendedWords(words) => assert_eq!(words.len(), 1),
+                Event::Registers { .. } => rounds += 1,
+            });
+        assert_eq!(output, siphash24(&key(), &message));
+        assert_eq!(rounds, 3 * 2 + 4);
+        assert_eq!(chaining_values.len(), 5);
+        assert_eq!(chaining_values[4], (4, vec![output]));
+
+        let mut rounds = 0;
+        let output =
+            SipHasher128::<Sip13>::new(&key()).traced(&[], &mut |event: Event<'_, u64>| {
+                if let Event::Registers { .. } = event {
+                    rounds += 1;
+                }
+            });
+        assert_eq!(output, siphash13_128(&key(), &[]));
+        assert_eq!(rounds, 1 + 2 * 3);
+    }
+}
@@ -0,0 +1,306 @@
+use std::fmt;
+use std::ops::BitXor;
+
+// Observers for what happens inside a compression function. Instrumented
+// hashes report the message schedule and their registers after every step,
+// and `merkle_damgard::hash_traced` adds the chaining values between blocks.
+// Any `FnMut(Event<W>)` closure is a trace; `Table` records everything and
+// prints it round by round.
+//
+// The permutation based primitives fit the same events: Keccak reports the
+// state going into every permutation and its lanes after every round, BLAKE2
+// and BLAKE3 their working vector after every G, SipHash its four words after
+// every SipRound, with the finalization as one more block, and Poseidon its
+// state after every round. Pedersen and FNV-1a have no rounds to report.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event<'a, W> {
+    // Chaining value going into block `block`. The last one reported is the
+    // output of the final block.
+    ChainingValue {
+        block: usize,
+        value: &'a [W],
+    },
+    // Words of the current block as the steps consume them, after expansion
+    // for the hashes that expand their message.
+    ExtendedWords(&'a [W]),
+    // Working registers right after step `step`.
+    Registers {
+        step: usize,
+        names: &'static [&'static str],
+        values: &'a [W],
+    },
+}
+
+pub trait Trace<W> {
+    fn record(&mut self, event: Event<'_, W>);
+}
+
+impl<W, F> Trace<W> for F
+where
+    F: FnMut(Event<'_, W>),
+{
+    fn record(&mut self, event: Event<'_, W>) {
+        self(event)
+    }
+}
+
+// Ignores every event. Uninstrumented hashing goes through it, and the calls
+// compile away.
+pub struct NoTrace;
+
+impl<W> Trace<W> for NoTrace {
+    #[inline(always)]
+    fn record(&mut self, _event: Event<'_, W>) {}
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Row<W> {
+    ChainingValue {
+        block: usize,
+        value: Vec<W>,
+    },
+    ExtendedWords(Vec<W>),
+    Registers {
+        step: usize,
+        names: &'static [&'static str],
+        values: Vec<W>,
+    },
+}
+
+// Every event of a computation, printed as one line per step.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Table<W> {
+    pub rows: Vec<Row<W>>,
+    // Set on the XOR of two tables, which prints zero words as dots so the
+    // differences stand out.
+    pub difference: bool,
+}
+
+impl<W> Table<W>
+where
+    W: Copy + PartialEq + BitXor<Output = W>,
+{
+    pub fn new() -> Self {
+        Table {
+            rows: vec![],
+            difference: false,
+        }
+    }
+
+    // Word by word XOR with another trace of the same hash. None if the two
+    // don't line up, e.g. messages of different lengths.
+    pub fn diff(&self, other: &Table<W>) -> Option<Table<W>> {
+        fn xor<W: Copy + BitXor<Output = W>>(x: &[W], y: &[W]) -> Option<Vec<W>> {
+            if x.len() != y.len() {
+                return None;
+            }
+            Some(x.iter().zip(y).map(|(x, y)| *x ^ *y).collect())
+        }
+
+        if self.rows.len() != other.rows.len() {
+            return None;
+        }
+        let mut rows = vec![];
+        for pair in self.rows.iter().zip(&other.rows) {
+            let row = match pair {
+                (
+                    Row::ChainingValue { block, value },
+                    Row::ChainingValue {
+                        block: other_block,
+                        value: other_value,
+                    },
+                ) if block == other_block => Row::ChainingValue {
+                    block: *block,
+                    value: xor(value, other_value)?,
+                },
+                (Row::ExtendedWords(words), Row::ExtendedWords(other_words)) => {
+                    Row::ExtendedWords(xor(words, other_words)?)
+                }
+                (
+                    Row::Registers {
+                        step,
+                        names,
+                        values,
+                    },
+                    Row::Registers {
+                        step: other_step,
+                        values: other_values,
+                        ..
+                    },
+                ) if step == other_step => Row::Registers {
+                    step: *step,
+                    names,
+                    values: xor(values, other_values)?,
+                },
+                _ => return None,
+            };
+            rows.push(row);
+        }
+        Some(Table {
+            rows,
+            difference: true,
+        })
+    }
+}
+
+impl<W> Default for Table<W>
+where
+    W: Copy + PartialEq + BitXor<Output = W>,
+{
+    fn default() -> Self {
+        Table::new()
+    }
+}
+
+impl<W> Trace<W> for Table<W>
+where
+    W: Copy,
+{
+    fn record(&mut self, event: Event<'_, W>) {
+        let row = match event {
+            Event::ChainingValue { block, value } => Row::ChainingValue {
+                block,
+                value: value.to_vec(),
+            },
+            Event::ExtendedWords(words) => Row::ExtendedWords(words.to_vec()),
+            Event::Registers {
+                step,
+                names,
+                values,
+            } => Row::Registers {
+                step,
+                names,
+                values: values.to_vec(),
+            },
+        };
+        self.rows.push(row);
+    }
+}
+
+// Extended words per line.
+const WORDS_PER_LINE: usize = 8;
+
+impl<W> Table<W>
+where
+    W: Copy + fmt::UpperHex + Default + PartialEq,
+{
+    fn write_words(&self, f: &mut fmt::Formatter, words: &[W]) -> fmt::Result {
+        let width = 2 * std::mem::size_of::<W>();
+        for word in words {
+            if self.difference && *word == W::default() {
+                write!(f, " {:>width$}", ".")?;
+            } else {
+                write!(f, " {:0width$X}", word)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<W> fmt::Display for Table<W>
+where
+    W: Copy + fmt::UpperHex + Default + PartialEq,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let width = 2 * std::mem::size_of::<W>();
+        // Register names are printed again after anything else.
+        let mut header = true;
+        for row in &self.rows {
+            match row {
+                Row::ChainingValue { block, value } => {
+                    write!(f, "block {:>3} ", block)?;
+                    self.write_words(f, value)?;
+                    writeln!(f)?;
+                    header = true;
+                }
+                Row::ExtendedWords(words) => {
+                    for (line, chunk) in words.chunks(WORDS_PER_LINE).enumerate() {
+                        write!(f, "w[{:>2}..]  ", line * WORDS_PER_LINE)?;
+                        self.write_words(f, chunk)?;
+                        writeln!(f)?;
+                    }
+                    header = true;
+                }
+                Row::Registers {
+                    step,
+                    names,
+                    values,
+                } => {
+                    if header {
+                        write!(f, "step      ")?;
+                        for name in names.iter() {
+                            write!(f, " {:>width$}", name)?;
+                        }
+                        writeln!(f)?;
+                        header = false;
+                    }
+                    write!(f, "{:>4}      ", step)?;
+                    self.write_words(f, values)?;
+                    writeln!(f)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NAMES: &[&str] = &["a", "b"];
+
+    fn record(table: &mut Table<u32>, x: u32) {
+        table.record(Event::ChainingValue {
+            block: 0,
+            value: &[x, 2],
+        });
+        table.record(Event::Registers {
+            step: 0,
+            names: NAMES,
+            values: &[0xAB, x],
+        });
+    }
+
+    #[test]
+    fn test_table() {
+        let mut table = Table::new();
+        record(&mut table, 1);
+        assert_eq!(
+            table.to_string(),
+            "block   0  00000001 00000002\n\
+             step              a        b\n   \
+             0       000000AB 00000001\n"
+        );
+
+        let mut other = Table::new();
+        record(&mut other, 3);
+        let diff = table.diff(&other).unwrap();
+        assert_eq!(
+            diff.to_string(),
+            "block   0  00000002        .\n\
+             step              a        b\n   \
+             0              . 00000002\n"
+        );
+
+        other.rows.pop();
+        assert!(table.diff(&other).is_none());
+    }
+
+    #[test]
+    fn test_closure() {
+        let mut steps = vec![];
+        let mut trace = |event: Event<'_, u32>| {
+            if let Event::Registers { step, .. } = event {
+                steps.push(step);
+            }
+        };
+        trace.record(Event::Registers {
+            step: 7,
+            names: NAMES,
+            values: &[0, 0],
+        });
+        assert_eq!(steps, [7]);
+    }
+}
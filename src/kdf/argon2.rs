@@ -0,0 +1,541 @@
+use crate::hasher::blake2::Blake2b;
+use crate::hasher::Hasher;
+use crate::kdf::phc::PhcString;
+use crate::mac::constant_time_eq;
+
+// Memory-hard password hashing. Memory is a grid of 1 KiB blocks with one row
+// per lane; every block is the compression G of its predecessor and a
+// pseudorandomly chosen reference block. Argon2d picks references from the
+// data (fast to fill, leaks timing), Argon2i from a counter (side-channel
+// free, weaker against tradeoffs) and Argon2id does the latter for the first
+// half pass and the former afterwards.
+// https://www.rfc-editor.org/rfc/rfc9106
+
+const VERSION: u32 = 0x13;
+const BLOCK_WORDS: usize = 128;
+const SYNC_POINTS: usize = 4;
+
+type Block = [u64; BLOCK_WORDS];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Variant {
+    Argon2d,
+    Argon2i,
+    Argon2id,
+}
+
+impl Variant {
+    fn type_code(&self) -> u32 {
+        match self {
+            Variant::Argon2d => 0,
+            Variant::Argon2i => 1,
+            Variant::Argon2id => 2,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Variant::Argon2d => "argon2d",
+            Variant::Argon2i => "argon2i",
+            Variant::Argon2id => "argon2id",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "argon2d" => Some(Variant::Argon2d),
+            "argon2i" => Some(Variant::Argon2i),
+            "argon2id" => Some(Variant::Argon2id),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Params {
+    // Memory size in KiB blocks.
+    pub memory_cost: u32,
+    // Number of passes over memory.
+    pub time_cost: u32,
+    // Number of lanes.
+    pub parallelism: u32,
+    // Tag length in bytes, set through `with_output_length`.
+    output_length: usize,
+    // Optional key K, e.g. a pepper kept out of the database.
+    pub secret: Vec<u8>,
+    // Optional associated data X.
+    pub associated_data: Vec<u8>,
+}
+
+impl Params {
+    // Returns `None` for parameters the RFC rules out.
+    pub fn new(memory_cost: u32, time_cost: u32, parallelism: u32) -> Option<Self> {
+        if parallelism == 0 || parallelism >= 1 << 24 || time_cost == 0 {
+            return None;
+        }
+        if memory_cost < 8 * parallelism {
+            return None;
+        }
+        Some(Params {
+            memory_cost,
+            time_cost,
+            parallelism,
+            output_length: 32,
+            secret: vec![],
+            associated_data: vec![],
+        })
+    }
+
+    // Returns `None` for tags shorter than 4 bytes or longer than the 32-bit
+    // length field H0 and H' encode it in.
+    pub fn with_output_length(self, output_length: usize) -> Option<Self> {
+        if output_length < 4 || u32::try_from(output_length).is_err() {
+            return None;
+        }
+        Some(Params {
+            output_length,
+            ..self
+        })
+    }
+
+    pub fn output_length(&self) -> usize {
+        self.output_length
+    }
+}
+
+// BLAKE2b of the concatenated inputs with an output of up to 64 bytes.
+fn blake2b(output_length: usize, inputs: &[&[u8]]) -> Vec<u8> {
+    let mut hasher = Blake2b::with_output_length(output_length).unwrap();
+    for input in inputs {
+        hasher.update(*input);
+    }
+    hasher.finalize()
+}
+
+// Variable length hash H' built from BLAKE2b, used for the first blocks of
+// each lane and the tag.
+fn hash_long(output_length: usize, inputs: &[&[u8]]) -> Vec<u8> {
+    let length_prefix = (output_length as u32).to_le_bytes();
+    let prefixed: Vec<&[u8]> = [&length_prefix[..]]
+        .into_iter()
+        .chain(inputs.iter().copied())
+        .collect();
+
+    if output_length <= 64 {
+        return blake2b(output_length, &prefixed);
+    }
+
+    // Chain 64 byte hashes, keeping the first half of each, until the last
+    // hash can cover whatever is left.
+    let mut output = Vec::with_capacity(output_length);
+    let mut v = blake2b(64, &prefixed);
+    loop {
+        output.extend(&v[..32]);
+        let remaining = output_length - output.len();
+        if remaining <= 64 {
+            output.extend(blake2b(remaining, &[&v]));
+            return output;
+        }
+        v = blake2b(64, &[&v]);
+    }
+}
+
+// BLAKE2b's G with the additions strengthened by a 32x32 bit multiplication.
+fn blamka(v: &mut Block, a: usize, b: usize, c: usize, d: usize) {
+    fn mul_add(x: u64, y: u64) -> u64 {
+        let product = (x & 0xFFFFFFFF).wrapping_mul(y & 0xFFFFFFFF);
+        x.wrapping_add(y).wrapping_add(product.wrapping_mul(2))
+    }
+
+    v[a] = mul_add(v[a], v[b]);
+    v[d] = (v[d] ^ v[a]).rotate_right(32);
+    v[c] = mul_add(v[c], v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(24);
+    v[a] = mul_add(v[a], v[b]);
+    v[d] = (v[d] ^ v[a]).rotate_right(16);
+    v[c] = mul_add(v[c], v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(63);
+}
+
+// One BLAKE2b round over 16 words of the block picked out by `indices`.
+fn permute(v: &mut Block, indices: [usize; 16]) {
+    let [i0, i1, i2, i3, i4, i5, i6, i7, i8, i9, i10, i11, i12, i13, i14, i15] = indices;
+    blamka(v, i0, i4, i8, i12);
+    blamka(v, i1, i5, i9, i13);
+    blamka(v, i2, i6, i10, i14);
+    blamka(v, i3, i7, i11, i15);
+    blamka(v, i0, i5, i10, i15);
+    blamka(v, i1, i6, i11, i12);
+    blamka(v, i2, i7, i8, i13);
+    blamka(v, i3, i4, i9, i14);
+}
+
+// Compression function G(X, Y) = P(X ^ Y) ^ X ^ Y, where P views the block
+// as an 8x8 matrix of 16 byte registers and permutes its rows, then columns.
+fn compress(x: &Block, y: &Block) -> Block {
+    let mut r = [0u64; BLOCK_WORDS];
+    for i in 0..BLOCK_WORDS {
+        r[i] = x[i] ^ y[i];
+    }
+
+    let mut q = r;
+    for row in 0..8 {
+        let indices: [usize; 16] = std::array::from_fn(|i| 16 * row + i);
+        permute(&mut q, indices);
+    }
+    for column in 0..8 {
+        let indices: [usize; 16] = std::array::from_fn(|i| 2 * column + 16 * (i / 2) + i % 2);
+        permute(&mut q, indices);
+    }
+
+    for i in 0..BLOCK_WORDS {
+        q[i] ^= r[i];
+    }
+    q
+}
+
+fn block_from_bytes(bytes: &[u8]) -> Block {
+    let mut block = [0u64; BLOCK_WORDS];
+    for (word, chunk) in block.iter_mut().zip(bytes.chunks_exact(8)) {
+        *word = u64::from_le_bytes(chunk.try_into().unwrap());
+    }
+    block
+}
+
+// Generates pseudorandom reference indices independently of the password
+// for Argon2i and the first half pass of Argon2id.
+struct AddressGenerator {
+    input: Block,
+    addresses: Block,
+}
+
+impl AddressGenerator {
+    fn new(
+        pass: u32,
+        lane: u32,
+        slice: u32,
+        total_blocks: u32,
+        params: &Params,
+        variant: Variant,
+    ) -> Self {
+        let mut input = [0u64; BLOCK_WORDS];
+        input[0] = pass as u64;
+        input[1] = lane as u64;
+        input[2] = slice as u64;
+        input[3] = total_blocks as u64;
+        input[4] = params.time_cost as u64;
+        input[5] = variant.type_code() as u64;
+        AddressGenerator {
+            input,
+            addresses: [0; BLOCK_WORDS],
+        }
+    }
+
+    fn next_block(&mut self) {
+        let zero = [0u64; BLOCK_WORDS];
+        self.input[6] += 1;
+        self.addresses = compress(&zero, &compress(&zero, &self.input));
+    }
+}
+
+pub fn argon2(variant: Variant, password: &[u8], salt: &[u8], params: &Params) -> Vec<u8> {
+    let lanes = params.parallelism as usize;
+
+    // H0 binds every parameter and input.
+    let length_prefixed = |bytes: &[u8]| [&(bytes.len() as u32).to_le_bytes()[..], bytes].concat();
+    let h0 = blake2b(
+        64,
+        &[
+            &params.parallelism.to_le_bytes(),
+            &(params.output_length as u32).to_le_bytes(),
+            &params.memory_cost.to_le_bytes(),
+            &params.time_cost.to_le_bytes(),
+            &VERSION.to_le_bytes(),
+            &variant.type_code().to_le_bytes(),
+            &length_prefixed(password),
+            &length_prefixed(salt),
+            &length_prefixed(&params.secret),
+            &length_prefixed(&params.associated_data),
+        ],
+    );
+
+    // Round memory down to a multiple of 4 blocks per lane.
+    let segment_length = params.memory_cost as usize / (SYNC_POINTS * lanes);
+    let lane_length = segment_length * SYNC_POINTS;
+    let total_blocks = lane_length * lanes;
+    let mut memory: Vec<Block> = vec![[0; BLOCK_WORDS]; total_blocks];
+
+    for lane in 0..lanes {
+        for i in 0..2 {
+            let bytes = hash_long(
+                1024,
+                &[&h0, &(i as u32).to_le_bytes(), &(lane as u32).to_le_bytes()],
+            );
+            memory[lane * lane_length + i] = block_from_bytes(&bytes);
+        }
+    }
+
+    for pass in 0..params.time_cost as usize {
+        for slice in 0..SYNC_POINTS {
+            // Lanes only reference each other's finished segments, so this
+            // loop is where the lanes could run in parallel.
+            for lane in 0..lanes {
+                let data_independent = match variant {
+                    Variant::Argon2d => false,
+                    Variant::Argon2i => true,
+                    Variant::Argon2id => pass == 0 && slice < SYNC_POINTS / 2,
+                };
+                let mut addresses = AddressGenerator::new(
+                    pass as u32,
+                    lane as u32,
+                    slice as u32,
+                    total_blocks as u32,
+                    params,
+                    variant,
+                );
+
+                let start = if pass == 0 && slice == 0 { 2 } else { 0 };
+                if data_independent && start == 2 {
+                    addresses.next_block();
+                }
+
+                for index in start..segment_length {
+                    let column = slice * segment_length + index;
+                    let current = lane * lane_length + column;
+                    let previous = if column == 0 {
+                        current + lane_length - 1
+                    } else {
+                        current - 1
+                    };
+
+                    let pseudo_random = if data_independent {
+                        if index % BLOCK_WORDS == 0 {
+                            addresses.next_block();
+                        }
+                        addresses.addresses[index % BLOCK_WORDS]
+                    } else {
+                        memory[previous][0]
+                    };
+
+                    // The high half picks the lane, the low half a block
+                    // within the area already filled, skewed towards recent
+                    // blocks.
+                    let j1 = pseudo_random & 0xFFFFFFFF;
+                    let j2 = pseudo_random >> 32;
+                    let reference_lane = if pass == 0 && slice == 0 {
+                        lane
+                    } else {
+                        (j2 % lanes as u64) as usize
+                    };
+                    let same_lane = reference_lane == lane;
+
+                    let finished_segments = if pass == 0 { slice } else { SYNC_POINTS - 1 };
+                    let area_size = if same_lane {
+                        finished_segments * segment_length + index - 1
+                    } else if index == 0 {
+                        finished_segments * segment_length - 1
+                    } else {
+                        finished_segments * segment_length
+                    } as u64;
+
+                    let x = (j1 * j1) >> 32;
+                    let y = (area_size * x) >> 32;
+                    let relative_position = area_size - 1 - y;
+                    let area_start = if pass == 0 || slice == SYNC_POINTS - 1 {
+                        0
+                    } else {
+                        (slice + 1) * segment_length
+                    };
+                    let reference_column = (area_start + relative_position as usize) % lane_length;
+                    let reference = reference_lane * lane_length + reference_column;
+
+                    let mut block = compress(&memory[previous], &memory[reference]);
+                    // Later passes XOR into the old block instead of
+                    // overwriting it.
+                    if pass > 0 {
+                        for (word, old) in block.iter_mut().zip(memory[current]) {
+                            *word ^= old;
+                        }
+                    }
+                    memory[current] = block;
+                }
+            }
+        }
+    }
+
+    // XOR the last column and hash it down to the tag.
+    let mut last = [0u64; BLOCK_WORDS];
+    for lane in 0..lanes {
+        for (word, lane_word) in last
+            .iter_mut()
+            .zip(memory[lane * lane_length + lane_length - 1])
+        {
+            *word ^= lane_word;
+        }
+    }
+    let last_bytes: Vec<u8> = last.iter().flat_map(|word| word.to_le_bytes()).collect();
+    hash_long(params.output_length, &[&last_bytes])
+}
+
+// Hashes a password into a PHC string, e.g.
+// $argon2id$v=19$m=65536,t=3,p=4$<salt>$<hash>
+pub fn hash_encoded(variant: Variant, password: &[u8], salt: &[u8], params: &Params) -> String {
+    let hash = argon2(variant, password, salt, params);
+    PhcString {
+        algorithm: variant.name().to_string(),
+        version: Some(VERSION),
+        params: vec![
+            ("m".to_string(), params.memory_cost),
+            ("t".to_string(), params.time_cost),
+            ("p".to_string(), params.parallelism),
+        ],
+        salt: salt.to_vec(),
+        hash,
+    }
+    .to_string()
+}
+
+// Limits on encoded parameters, which may come from anywhere. They allow
+// the RFC 9106 recommendations of 2 GiB with one pass and 64 MiB with
+// three passes.
+const MAX_ENCODED_MEMORY_COST: u32 = 1 << 21;
+const MAX_ENCODED_TIME_COST: u32 = 16;
+
+fn params_from_phc(phc: &PhcString) -> Option<Params> {
+    let memory_cost = phc.param("m")?;
+    let time_cost = phc.param("t")?;
+    if memory_cost > MAX_ENCODED_MEMORY_COST || time_cost > MAX_ENCODED_TIME_COST {
+        return None;
+    }
+    Params::new(memory_cost, time_cost, phc.param("p")?)?.with_output_length(phc.hash.len())
+}
+
+pub fn verify_encoded(encoded: &str, password: &[u8]) -> bool {
+    let phc = match PhcString::parse(encoded) {
+        Some(phc) if phc.version.unwrap_or(VERSION) == VERSION && phc.hash.len() >= 4 => phc,
+        _ => return false,
+    };
+    let variant = match Variant::from_name(&phc.algorithm) {
+        Some(variant) => variant,
+        None => return false,
+    };
+
+    match params_from_phc(&phc) {
+        Some(params) => {
+            let hash = argon2(variant, password, &phc.salt, &params);
+            constant_time_eq(&hash, &phc.hash)
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Inputs shared by the RFC 9106 test vectors.
+    fn rfc_params() -> Params {
+        let mut params = Params::new(32, 3, 4).unwrap();
+        params.secret = vec![0x03; 8];
+        params.associated_data = vec![0x04; 12];
+        params
+    }
+
+    #[test]
+    fn test_argon2d() {
+        // Test vector from RFC 9106 section 5.1.
+        let expected: [u8; 32] = [
+            0x51, 0x2B, 0x39, 0x1B, 0x6F, 0x11, 0x62, 0x97, 0x53, 0x71, 0xD3, 0x09, 0x19, 0x73,
+            0x42, 0x94, 0xF8, 0x68, 0xE3, 0xBE, 0x39, 0x84, 0xF3, 0xC1, 0xA1, 0x3A, 0x4D, 0xB9,
+            0xFA, 0xBE, 0x4A, 0xCB,
+        ];
+        let tag = argon2(Variant::Argon2d, &[0x01; 32], &[0x02; 16], &rfc_params());
+        assert_eq!(tag, expected);
+    }
+
+    #[test]
+    fn test_argon2i() {
+        // Test vector from RFC 9106 section 5.2.
+        let expected: [u8; 32] = [
+            0xC8, 0x14, 0xD9, 0xD1, 0xDC, 0x7F, 0x37, 0xAA, 0x13, 0xF0, 0xD7, 0x7F, 0x24, 0x94,
+            0xBD, 0xA1, 0xC8, 0xDE, 0x6B, 0x01, 0x6D, 0xD3, 0x88, 0xD2, 0x99, 0x52, 0xA4, 0xC4,
+            0x67, 0x2B, 0x6C, 0xE8,
+        ];
+        let tag = argon2(Variant::Argon2i, &[0x01; 32], &[0x02; 16], &rfc_params());
+        assert_eq!(tag, expected);
+    }
+
+    #[test]
+    fn test_argon2id() {
+        // Test vector from RFC 9106 section 5.3.
+        let expected: [u8; 32] = [
+            0x0D, 0x64, 0x0D, 0xF5, 0x8D, 0x78, 0x76, 0x6C, 0x08, 0xC0, 0x37, 0xA3, 0x4A, 0x8B,
+            0x53, 0xC9, 0xD0, 0x1E, 0xF0, 0x45, 0x2D, 0x75, 0xB6, 0x5E, 0xB5, 0x25, 0x20, 0xE9,
+            0x6B, 0x01, 0xE6, 0x59,
+        ];
+        let tag = argon2(Variant::Argon2id, &[0x01; 32], &[0x02; 16], &rfc_params());
+        assert_eq!(tag, expected);
+    }
+
+    #[test]
+    fn test_hash_long() {
+        // Longer than one BLAKE2b output, with a partial final hash.
+        let output = hash_long(100, &[b"abc"]);
+        assert_eq!(output.len(), 100);
+        let v1 = blake2b(64, &[&100u32.to_le_bytes(), b"abc"]);
+        let v2 = blake2b(64, &[&v1]);
+        assert_eq!(&output[..32], &v1[..32]);
+        assert_eq!(&output[32..64], &v2[..32]);
+        assert_eq!(&output[64..], blake2b(36, &[&v2]));
+    }
+
+    #[test]
+    fn test_encoded() {
+        let params = Params::new(64, 2, 2).unwrap();
+        let encoded = hash_encoded(Variant::Argon2id, b"hunter2", b"somesaltsomesalt", &params);
+        assert!(encoded.starts_with("$argon2id$v=19$m=64,t=2,p=2$c29tZXNhbHRzb21lc2FsdA$"));
+
+        assert!(verify_encoded(&encoded, b"hunter2"));
+        assert!(!verify_encoded(&encoded, b"hunter3"));
+        assert!(!verify_encoded(
+            &encoded.replace("argon2id", "argon2d"),
+            b"hunter2"
+        ));
+        assert!(!verify_encoded(
+            &encoded.replace("v=19", "v=16"),
+            b"hunter2"
+        ));
+        // Costs no verifier should take on from an untrusted string.
+        assert!(!verify_encoded(
+            &encoded.replace("m=64", "m=4294967295"),
+            b"hunter2"
+        ));
+        assert!(!verify_encoded(
+            &encoded.replace("t=2", "t=4294967295"),
+            b"hunter2"
+        ));
+    }
+
+    #[test]
+    fn test_params() {
+        assert!(Params::new(8, 1, 1).is_some());
+        assert!(Params::new(7, 1, 1).is_none());
+        assert!(Params::new(32, 0, 4).is_none());
+        assert!(Params::new(32, 1, 0).is_none());
+        assert!(Params::new(31, 1, 4).is_none());
+
+        let params = Params::new(8, 1, 1).unwrap();
+        assert_eq!(params.output_length(), 32);
+        assert!(params.clone().with_output_length(0).is_none());
+        assert!(params.clone().with_output_length(3).is_none());
+        assert_eq!(
+            params
+                .clone()
+                .with_output_length(4)
+                .unwrap()
+                .output_length(),
+            4
+        );
+        assert!(params.with_output_length(1 << 32).is_none());
+    }
+}
@@ -0,0 +1,148 @@
+use crate::hasher::Hasher;
+use crate::mac::hmac::{hmac, Hmac};
+
+// HMAC-based extract-and-expand key derivation.
+// https://www.rfc-editor.org/rfc/rfc5869
+
+// Concentrates the entropy of `ikm` into a pseudorandom key. An empty salt
+// stands in for a block of zeros, which is what HMAC pads it to anyway.
+pub fn extract<H>(salt: &[u8], ikm: &[u8]) -> H::Digest
+where
+    H: Hasher,
+    H::Digest: AsRef<[u8]>,
+{
+    hmac::<H>(salt, ikm)
+}
+
+// Stretches a pseudorandom key into `length` bytes bound to `info`:
+// T(i) = HMAC(prk, T(i - 1) || info || i). Returns `None` when more than 255
+// blocks of output are requested.
+pub fn expand<H>(prk: &[u8], info: &[u8], length: usize) -> Option<Vec<u8>>
+where
+    H: Hasher,
+    H::Digest: AsRef<[u8]>,
+{
+    let mut okm: Vec<u8> = Vec::with_capacity(length);
+    let mut previous: Vec<u8> = vec![];
+    for counter in 1..=255u8 {
+        if okm.len() >= length {
+            break;
+        }
+
+        let mut mac = Hmac::<H>::new(prk);
+        mac.update(previous.as_slice());
+        mac.update(info);
+        mac.update([counter].as_slice());
+        previous = mac.finalize().as_ref().to_vec();
+        okm.extend(&previous);
+    }
+
+    if okm.len() < length {
+        return None;
+    }
+    okm.truncate(length);
+    Some(okm)
+}
+
+pub fn hkdf<H>(salt: &[u8], ikm: &[u8], info: &[u8], length: usize) -> Option<Vec<u8>>
+where
+    H: Hasher,
+    H::Digest: AsRef<[u8]>,
+{
+    let prk = extract::<H>(salt, ikm);
+    expand::<H>(prk.as_ref(), info, length)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hasher::sha1::Sha1;
+    use crate::hasher::sha256::Sha256;
+
+    #[test]
+    fn test_hkdf_sha256() {
+        // Test case 1 from RFC 5869.
+        let ikm = [0x0B; 22];
+        let salt: Vec<u8> = (0x00..=0x0C).collect();
+        let info: Vec<u8> = (0xF0..=0xF9).collect();
+
+        let expected_prk: [u8; 32] = [
+            0x07, 0x77, 0x09, 0x36, 0x2C, 0x2E, 0x32, 0xDF, 0x0D, 0xDC, 0x3F, 0x0D, 0xC4, 0x7B,
+            0xBA, 0x63, 0x90, 0xB6, 0xC7, 0x3B, 0xB5, 0x0F, 0x9C, 0x31, 0x22, 0xEC, 0x84, 0x4A,
+            0xD7, 0xC2, 0xB3, 0xE5,
+        ];
+        let expected_okm: [u8; 42] = [
+            0x3C, 0xB2, 0x5F, 0x25, 0xFA, 0xAC, 0xD5, 0x7A, 0x90, 0x43, 0x4F, 0x64, 0xD0, 0x36,
+            0x2F, 0x2A, 0x2D, 0x2D, 0x0A, 0x90, 0xCF, 0x1A, 0x5A, 0x4C, 0x5D, 0xB0, 0x2D, 0x56,
+            0xEC, 0xC4, 0xC5, 0xBF, 0x34, 0x00, 0x72, 0x08, 0xD5, 0xB8, 0x87, 0x18, 0x58, 0x65,
+        ];
+
+        let prk = extract::<Sha256>(&salt, &ikm);
+        assert_eq!(prk, expected_prk);
+        assert_eq!(expand::<Sha256>(&prk, &info, 42).unwrap(), expected_okm);
+    }
+
+    #[test]
+    fn test_hkdf_sha256_long_inputs() {
+        // Test case 2 from RFC 5869.
+        let ikm: Vec<u8> = (0x00..=0x4F).collect();
+        let salt: Vec<u8> = (0x60..=0xAF).collect();
+        let info: Vec<u8> = (0xB0..=0xFF).collect();
+
+        let expected_okm: [u8; 82] = [
+            0xB1, 0x1E, 0x39, 0x8D, 0xC8, 0x03, 0x27, 0xA1, 0xC8, 0xE7, 0xF7, 0x8C, 0x59, 0x6A,
+            0x49, 0x34, 0x4F, 0x01, 0x2E, 0xDA, 0x2D, 0x4E, 0xFA, 0xD8, 0xA0, 0x50, 0xCC, 0x4C,
+            0x19, 0xAF, 0xA9, 0x7C, 0x59, 0x04, 0x5A, 0x99, 0xCA, 0xC7, 0x82, 0x72, 0x71, 0xCB,
+            0x41, 0xC6, 0x5E, 0x59, 0x0E, 0x09, 0xDA, 0x32, 0x75, 0x60, 0x0C, 0x2F, 0x09, 0xB8,
+            0x36, 0x77, 0x93, 0xA9, 0xAC, 0xA3, 0xDB, 0x71, 0xCC, 0x30, 0xC5, 0x81, 0x79, 0xEC,
+            0x3E, 0x87, 0xC1, 0x4C, 0x01, 0xD5, 0xC1, 0xF3, 0x43, 0x4F, 0x1D, 0x87,
+        ];
+        assert_eq!(
+            hkdf::<Sha256>(&salt, &ikm, &info, 82).unwrap(),
+            expected_okm
+        );
+    }
+
+    #[test]
+    fn test_hkdf_sha256_empty_salt_and_info() {
+        // Test case 3 from RFC 5869.
+        let expected_okm: [u8; 42] = [
+            0x8D, 0xA4, 0xE7, 0x75, 0xA5, 0x63, 0xC1, 0x8F, 0x71, 0x5F, 0x80, 0x2A, 0x06, 0x3C,
+            0x5A, 0x31, 0xB8, 0xA1, 0x1F, 0x5C, 0x5E, 0xE1, 0x87, 0x9E, 0xC3, 0x45, 0x4E, 0x5F,
+            0x3C, 0x73, 0x8D, 0x2D, 0x9D, 0x20, 0x13, 0x95, 0xFA, 0xA4, 0xB6, 0x1A, 0x96, 0xC8,
+        ];
+        assert_eq!(
+            hkdf::<Sha256>(&[], &[0x0B; 22], &[], 42).unwrap(),
+            expected_okm
+        );
+    }
+
+    #[test]
+    fn test_hkdf_sha1() {
+        // Test case 4 from RFC 5869.
+        let ikm = [0x0B; 11];
+        let salt: Vec<u8> = (0x00..=0x0C).collect();
+        let info: Vec<u8> = (0xF0..=0xF9).collect();
+
+        let expected_prk: [u8; 20] = [
+            0x9B, 0x6C, 0x18, 0xC4, 0x32, 0xA7, 0xBF, 0x8F, 0x0E, 0x71, 0xC8, 0xEB, 0x88, 0xF4,
+            0xB3, 0x0B, 0xAA, 0x2B, 0xA2, 0x43,
+        ];
+        let expected_okm: [u8; 42] = [
+            0x08, 0x5A, 0x01, 0xEA, 0x1B, 0x10, 0xF3, 0x69, 0x33, 0x06, 0x8B, 0x56, 0xEF, 0xA5,
+            0xAD, 0x81, 0xA4, 0xF1, 0x4B, 0x82, 0x2F, 0x5B, 0x09, 0x15, 0x68, 0xA9, 0xCD, 0xD4,
+            0xF1, 0x55, 0xFD, 0xA2, 0xC2, 0x2E, 0x42, 0x24, 0x78, 0xD3, 0x05, 0xF3, 0xF8, 0x96,
+        ];
+
+        let prk = extract::<Sha1>(&salt, &ikm);
+        assert_eq!(prk, expected_prk);
+        assert_eq!(expand::<Sha1>(&prk, &info, 42).unwrap(), expected_okm);
+    }
+
+    #[test]
+    fn test_expand_length_limit() {
+        let prk = extract::<Sha1>(b"salt", b"ikm");
+        assert_eq!(expand::<Sha1>(&prk, b"", 255 * 20).unwrap().len(), 255 * 20);
+        assert_eq!(expand::<Sha1>(&prk, b"", 255 * 20 + 1), None);
+    }
+}
@@ -0,0 +1,5 @@
+pub mod argon2;
+pub mod hkdf;
+pub mod pbkdf2;
+pub mod phc;
+pub mod scrypt;
@@ -0,0 +1,137 @@
+use crate::hasher::Hasher;
+use crate::mac::hmac::Hmac;
+
+// Password-based key derivation with HMAC as the pseudorandom function. Each
+// output block is U_1 ^ U_2 ^ ... ^ U_c where U_1 = HMAC(P, S || i) and
+// U_j = HMAC(P, U_{j - 1}), so the cost grows linearly with `iterations`.
+// https://www.rfc-editor.org/rfc/rfc8018#section-5.2
+pub fn pbkdf2<H>(password: &[u8], salt: &[u8], iterations: u32, length: usize) -> Option<Vec<u8>>
+where
+    H: Hasher + Clone,
+    H::Digest: AsRef<[u8]>,
+{
+    if iterations == 0 {
+        return None;
+    }
+
+    // Key the HMAC once and clone it for every invocation.
+    let keyed = Hmac::<H>::new(password);
+
+    let block = |block_index: u32| -> Vec<u8> {
+        let mut mac = keyed.clone();
+        mac.update(salt);
+        mac.update(block_index.to_be_bytes().as_slice());
+        let mut u = mac.finalize();
+        let mut block = u.as_ref().to_vec();
+
+        for _ in 1..iterations {
+            let mut mac = keyed.clone();
+            mac.update(u.as_ref());
+            u = mac.finalize();
+            for (byte, u_byte) in block.iter_mut().zip(u.as_ref()) {
+                *byte ^= u_byte;
+            }
+        }
+        block
+    };
+
+    // The block index is 32 bits, so the derived key is at most 2^32 - 1
+    // blocks long.
+    let first = block(1);
+    let blocks = u32::try_from(length.div_ceil(first.len())).ok()?;
+
+    let mut derived_key: Vec<u8> = Vec::with_capacity(length);
+    derived_key.extend(first);
+    for block_index in 2..=blocks {
+        derived_key.extend(block(block_index));
+    }
+    derived_key.truncate(length);
+    Some(derived_key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hasher::sha1::Sha1;
+    use crate::hasher::sha256::Sha256;
+
+    #[test]
+    fn test_pbkdf2_sha1() {
+        // Test vectors from RFC 6070, minus the 16777216 iteration one.
+        let expected_1: [u8; 20] = [
+            0x0C, 0x60, 0xC8, 0x0F, 0x96, 0x1F, 0x0E, 0x71, 0xF3, 0xA9, 0xB5, 0x24, 0xAF, 0x60,
+            0x12, 0x06, 0x2F, 0xE0, 0x37, 0xA6,
+        ];
+        let expected_2: [u8; 20] = [
+            0xEA, 0x6C, 0x01, 0x4D, 0xC7, 0x2D, 0x6F, 0x8C, 0xCD, 0x1E, 0xD9, 0x2A, 0xCE, 0x1D,
+            0x41, 0xF0, 0xD8, 0xDE, 0x89, 0x57,
+        ];
+        let expected_4096: [u8; 20] = [
+            0x4B, 0x00, 0x79, 0x01, 0xB7, 0x65, 0x48, 0x9A, 0xBE, 0xAD, 0x49, 0xD9, 0x26, 0xF7,
+            0x21, 0xD0, 0x65, 0xA4, 0x29, 0xC1,
+        ];
+        let expected_long: [u8; 25] = [
+            0x3D, 0x2E, 0xEC, 0x4F, 0xE4, 0x1C, 0x84, 0x9B, 0x80, 0xC8, 0xD8, 0x36, 0x62, 0xC0,
+            0xE4, 0x4A, 0x8B, 0x29, 0x1A, 0x96, 0x4C, 0xF2, 0xF0, 0x70, 0x38,
+        ];
+        let expected_nul: [u8; 16] = [
+            0x56, 0xFA, 0x6A, 0xA7, 0x55, 0x48, 0x09, 0x9D, 0xCC, 0x37, 0xD7, 0xF0, 0x34, 0x25,
+            0xE0, 0xC3,
+        ];
+
+        assert_eq!(
+            pbkdf2::<Sha1>(b"password", b"salt", 1, 20).unwrap(),
+            expected_1
+        );
+        assert_eq!(
+            pbkdf2::<Sha1>(b"password", b"salt", 2, 20).unwrap(),
+            expected_2
+        );
+        assert_eq!(
+            pbkdf2::<Sha1>(b"password", b"salt", 4096, 20).unwrap(),
+            expected_4096
+        );
+        assert_eq!(
+            pbkdf2::<Sha1>(
+                b"passwordPASSWORDpassword",
+                b"saltSALTsaltSALTsaltSALTsaltSALTsalt",
+                4096,
+                25
+            )
+            .unwrap(),
+            expected_long
+        );
+        assert_eq!(
+            pbkdf2::<Sha1>(b"pass\0word", b"sa\0lt", 4096, 16).unwrap(),
+            expected_nul
+        );
+    }
+
+    #[test]
+    fn test_pbkdf2_sha256() {
+        // PBKDF2-HMAC-SHA256 vector from RFC 7914, spanning two output blocks.
+        let expected: [u8; 64] = [
+            0x55, 0xAC, 0x04, 0x6E, 0x56, 0xE3, 0x08, 0x9F, 0xEC, 0x16, 0x91, 0xC2, 0x25, 0x44,
+            0xB6, 0x05, 0xF9, 0x41, 0x85, 0x21, 0x6D, 0xDE, 0x04, 0x65, 0xE6, 0x8B, 0x9D, 0x57,
+            0xC2, 0x0D, 0xAC, 0xBC, 0x49, 0xCA, 0x9C, 0xCC, 0xF1, 0x79, 0xB6, 0x45, 0x99, 0x16,
+            0x64, 0xB3, 0x9D, 0x77, 0xEF, 0x31, 0x7C, 0x71, 0xB8, 0x45, 0xB1, 0xE3, 0x0B, 0xD5,
+            0x09, 0x11, 0x20, 0x41, 0xD3, 0xA1, 0x97, 0x83,
+        ];
+        assert_eq!(
+            pbkdf2::<Sha256>(b"passwd", b"salt", 1, 64).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_pbkdf2_invalid() {
+        assert_eq!(pbkdf2::<Sha1>(b"password", b"salt", 0, 20), None);
+        // Derived key too long for a 32-bit block index.
+        let max_length = u32::MAX as usize * 20;
+        assert_eq!(
+            pbkdf2::<Sha1>(b"password", b"salt", 1, max_length + 1),
+            None
+        );
+        assert_eq!(pbkdf2::<Sha1>(b"password", b"salt", 1, 0), Some(vec![]));
+    }
+}
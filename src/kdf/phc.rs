@@ -0,0 +1,176 @@
+use std::fmt;
+
+// PHC string format for storing password hashes alongside their parameters:
+// $<id>[$v=<version>][$<param>=<value>(,<param>=<value>)*][$<salt>[$<hash>]]
+// Salt and hash are base64 without padding.
+// https://github.com/P-H-C/phc-string-format/blob/master/phc-sf-spec.md
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PhcString {
+    pub algorithm: String,
+    pub version: Option<u32>,
+    pub params: Vec<(String, u32)>,
+    pub salt: Vec<u8>,
+    pub hash: Vec<u8>,
+}
+
+impl PhcString {
+    pub fn param(&self, name: &str) -> Option<u32> {
+        self.params
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| *value)
+    }
+
+    pub fn parse(encoded: &str) -> Option<Self> {
+        let mut fields = encoded.strip_prefix('$')?.split('$').peekable();
+
+        let algorithm = fields.next().filter(|id| !id.is_empty())?.to_string();
+
+        let mut version = None;
+        if let Some(field) = fields.peek() {
+            if let Some(value) = field.strip_prefix("v=") {
+                version = Some(value.parse().ok()?);
+                fields.next();
+            }
+        }
+
+        let mut params = vec![];
+        if let Some(field) = fields.peek() {
+            if field.contains('=') {
+                for pair in field.split(',') {
+                    let (key, value) = pair.split_once('=')?;
+                    params.push((key.to_string(), value.parse().ok()?));
+                }
+                fields.next();
+            }
+        }
+
+        let salt = match fields.next() {
+            Some(salt) => base64_decode(salt)?,
+            None => vec![],
+        };
+        let hash = match fields.next() {
+            Some(hash) => base64_decode(hash)?,
+            None => vec![],
+        };
+        if fields.next().is_some() {
+            return None;
+        }
+
+        Some(PhcString {
+            algorithm,
+            version,
+            params,
+            salt,
+            hash,
+        })
+    }
+}
+
+impl fmt::Display for PhcString {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "${}", self.algorithm)?;
+        if let Some(version) = self.version {
+            write!(f, "$v={}", version)?;
+        }
+        if !self.params.is_empty() {
+            let params: Vec<String> = self
+                .params
+                .iter()
+                .map(|(key, value)| format!("{}={}", key, value))
+                .collect();
+            write!(f, "${}", params.join(","))?;
+        }
+        if !self.salt.is_empty() {
+            write!(f, "${}", base64_encode(&self.salt))?;
+            if !self.hash.is_empty() {
+                write!(f, "${}", base64_encode(&self.hash))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64_encode(bytes: &[u8]) -> String {
+    let mut encoded = String::new();
+    for chunk in bytes.chunks(3) {
+        let mut group: u32 = 0;
+        for (i, byte) in chunk.iter().enumerate() {
+            group |= (*byte as u32) << (16 - 8 * i);
+        }
+        // 3 bytes make 4 characters, a trailing 1 or 2 bytes make 2 or 3.
+        for i in 0..=chunk.len() {
+            let index = (group >> (18 - 6 * i)) & 0x3F;
+            encoded.push(BASE64_ALPHABET[index as usize] as char);
+        }
+    }
+    encoded
+}
+
+fn base64_decode(encoded: &str) -> Option<Vec<u8>> {
+    let mut bytes = vec![];
+    for chunk in encoded.as_bytes().chunks(4) {
+        if chunk.len() == 1 {
+            return None;
+        }
+
+        let mut group: u32 = 0;
+        for (i, character) in chunk.iter().enumerate() {
+            let index = BASE64_ALPHABET.iter().position(|c| c == character)?;
+            group |= (index as u32) << (18 - 6 * i);
+        }
+        for i in 0..chunk.len() - 1 {
+            bytes.push((group >> (16 - 8 * i)) as u8);
+        }
+    }
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base64() {
+        assert_eq!(base64_encode(b""), "");
+        assert_eq!(base64_encode(b"f"), "Zg");
+        assert_eq!(base64_encode(b"fo"), "Zm8");
+        assert_eq!(base64_encode(b"foo"), "Zm9v");
+        assert_eq!(base64_encode(b"foobar"), "Zm9vYmFy");
+
+        for message in ["", "f", "fo", "foo", "foob", "fooba", "foobar"] {
+            let encoded = base64_encode(message.as_bytes());
+            assert_eq!(base64_decode(&encoded).unwrap(), message.as_bytes());
+        }
+        assert_eq!(base64_decode("Z"), None);
+        assert_eq!(base64_decode("Zm9="), None);
+    }
+
+    #[test]
+    fn test_phc_roundtrip() {
+        let encoded = "$argon2id$v=19$m=65536,t=2,p=1$gZiV/M1gPc22ElAH/Jh1Hw$CWOrkoo7oJBQ/iyh7uJ0LO2aLEfrHwTWllSAxT0zRno";
+        let phc = PhcString::parse(encoded).unwrap();
+
+        assert_eq!(phc.algorithm, "argon2id");
+        assert_eq!(phc.version, Some(19));
+        assert_eq!(phc.param("m"), Some(65536));
+        assert_eq!(phc.param("t"), Some(2));
+        assert_eq!(phc.param("p"), Some(1));
+        assert_eq!(phc.param("x"), None);
+        assert_eq!(phc.salt.len(), 16);
+        assert_eq!(phc.hash.len(), 32);
+        assert_eq!(phc.to_string(), encoded);
+    }
+
+    #[test]
+    fn test_phc_invalid() {
+        assert_eq!(PhcString::parse(""), None);
+        assert_eq!(PhcString::parse("argon2id$v=19"), None);
+        assert_eq!(PhcString::parse("$argon2id$v=x"), None);
+        assert_eq!(PhcString::parse("$scrypt$ln=15,r$c2FsdA"), None);
+        assert_eq!(PhcString::parse("$scrypt$ln=15$c2FsdA$aGFzaA$extra"), None);
+    }
+}
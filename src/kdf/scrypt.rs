@@ -0,0 +1,324 @@
+use crate::hasher::sha256::Sha256;
+use crate::kdf::pbkdf2::pbkdf2;
+use crate::kdf::phc::PhcString;
+use crate::mac::constant_time_eq;
+
+// Memory-hard password based key derivation. PBKDF2-HMAC-SHA256 spreads the
+// password over `p` blocks, each of which goes through ROMix: a walk over a
+// table of `n` BlockMix outputs in data-dependent order, so computing it
+// with less than `n * 128 * r` bytes of memory costs extra time.
+// https://www.rfc-editor.org/rfc/rfc7914
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Params {
+    // log2 of the CPU/memory cost `n`.
+    pub log_n: u8,
+    // Block size factor, a block is `128 * r` bytes.
+    pub r: u32,
+    // Parallelization, the number of independent ROMix calls.
+    pub p: u32,
+}
+
+impl Params {
+    // Returns `None` for parameters the RFC rules out.
+    pub fn new(log_n: u8, r: u32, p: u32) -> Option<Self> {
+        if log_n == 0 || log_n >= 64 || r == 0 || p == 0 {
+            return None;
+        }
+        // p <= (2^32 - 1) * 32 / (128 * r)
+        if (p as u64) * 128 * (r as u64) > (u32::MAX as u64) * 32 {
+            return None;
+        }
+        // n < 2^(128 * r / 8)
+        if r < 4 && log_n as u32 >= 16 * r {
+            return None;
+        }
+        // The table of `n` blocks has to be addressable.
+        1usize
+            .checked_shl(log_n as u32)?
+            .checked_mul(128)?
+            .checked_mul(r as usize)?;
+        Some(Params { log_n, r, p })
+    }
+
+    fn n(&self) -> usize {
+        1 << self.log_n
+    }
+
+    // Size of the ROMix table in bytes.
+    pub fn memory(&self) -> usize {
+        self.n() * 128 * self.r as usize
+    }
+}
+
+// Salsa20 with 8 rounds, used as a hash rather than a stream cipher.
+pub fn salsa20_8(block: &mut [u32; 16]) {
+    fn quarter_round(x: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize) {
+        x[b] ^= x[a].wrapping_add(x[d]).rotate_left(7);
+        x[c] ^= x[b].wrapping_add(x[a]).rotate_left(9);
+        x[d] ^= x[c].wrapping_add(x[b]).rotate_left(13);
+        x[a] ^= x[d].wrapping_add(x[c]).rotate_left(18);
+    }
+
+    let mut x = *block;
+    for _ in 0..4 {
+        // Columns.
+        quarter_round(&mut x, 0, 4, 8, 12);
+        quarter_round(&mut x, 5, 9, 13, 1);
+        quarter_round(&mut x, 10, 14, 2, 6);
+        quarter_round(&mut x, 15, 3, 7, 11);
+
+        // Rows.
+        quarter_round(&mut x, 0, 1, 2, 3);
+        quarter_round(&mut x, 5, 6, 7, 4);
+        quarter_round(&mut x, 10, 11, 8, 9);
+        quarter_round(&mut x, 15, 12, 13, 14);
+    }
+
+    for (word, mixed) in block.iter_mut().zip(x) {
+        *word = word.wrapping_add(mixed);
+    }
+}
+
+// Mixes `2 * r` 64 byte sub-blocks, writing the even outputs to the first
+// half of `output` and the odd ones to the second half.
+fn block_mix(input: &[u32], output: &mut [u32], r: usize) {
+    let mut x: [u32; 16] = input[(2 * r - 1) * 16..].try_into().unwrap();
+    for (i, chunk) in input.chunks_exact(16).enumerate() {
+        for (word, input_word) in x.iter_mut().zip(chunk) {
+            *word ^= input_word;
+        }
+        salsa20_8(&mut x);
+
+        let position = (i / 2 + (i % 2) * r) * 16;
+        output[position..position + 16].copy_from_slice(&x);
+    }
+}
+
+fn ro_mix(block: &mut [u32], n: usize, r: usize) {
+    let block_words = 32 * r;
+
+    // Fill the table with successive BlockMix outputs.
+    let mut table: Vec<u32> = Vec::with_capacity(n * block_words);
+    let mut x = block.to_vec();
+    let mut y = vec![0; block_words];
+    for _ in 0..n {
+        table.extend(&x);
+        block_mix(&x, &mut y, r);
+        std::mem::swap(&mut x, &mut y);
+    }
+
+    // Read it back in an order that depends on the running value.
+    for _ in 0..n {
+        // Integerify, only the low bits matter as `n` is a power of two.
+        let j = x[(2 * r - 1) * 16] as usize & (n - 1);
+        for (word, table_word) in x.iter_mut().zip(&table[j * block_words..]) {
+            *word ^= table_word;
+        }
+        block_mix(&x, &mut y, r);
+        std::mem::swap(&mut x, &mut y);
+    }
+
+    block.copy_from_slice(&x);
+}
+
+// Returns `None` for outputs longer than PBKDF2 can derive.
+pub fn scrypt(password: &[u8], salt: &[u8], params: &Params, length: usize) -> Option<Vec<u8>> {
+    let r = params.r as usize;
+    let block_size = 128 * r;
+
+    // `Params` keeps p * 128 * r within what PBKDF2 can derive.
+    let mut blocks = pbkdf2::<Sha256>(password, salt, 1, params.p as usize * block_size)?;
+    for chunk in blocks.chunks_exact_mut(block_size) {
+        let mut words: Vec<u32> = chunk
+            .chunks_exact(4)
+            .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+            .collect();
+        ro_mix(&mut words, params.n(), r);
+        for (bytes, word) in chunk.chunks_exact_mut(4).zip(words) {
+            bytes.copy_from_slice(&word.to_le_bytes());
+        }
+    }
+
+    pbkdf2::<Sha256>(password, &blocks, 1, length)
+}
+
+// Hashes a password into a PHC string, e.g.
+// $scrypt$ln=15,r=8,p=1$<salt>$<hash>
+pub fn hash_encoded(password: &[u8], salt: &[u8], params: &Params) -> String {
+    let hash = match scrypt(password, salt, params, 32) {
+        Some(hash) => hash,
+        None => unreachable!("32 bytes is one PBKDF2 block"),
+    };
+    PhcString {
+        algorithm: "scrypt".to_string(),
+        version: None,
+        params: vec![
+            ("ln".to_string(), params.log_n as u32),
+            ("r".to_string(), params.r),
+            ("p".to_string(), params.p),
+        ],
+        salt: salt.to_vec(),
+        hash,
+    }
+    .to_string()
+}
+
+// Limits on encoded parameters, which may come from anywhere. They allow
+// the usual interactive and sensitive settings, up to ln=20 with r=8 and up
+// to 16 ROMix calls.
+const MAX_ENCODED_LOG_N: u8 = 20;
+const MAX_ENCODED_MEMORY: usize = 1 << 30;
+const MAX_ENCODED_PARALLELISM: u32 = 16;
+
+fn params_from_phc(phc: &PhcString) -> Option<Params> {
+    let log_n = u8::try_from(phc.param("ln")?).ok()?;
+    if log_n > MAX_ENCODED_LOG_N {
+        return None;
+    }
+    let params = Params::new(log_n, phc.param("r")?, phc.param("p")?)?;
+    if params.memory() > MAX_ENCODED_MEMORY || params.p > MAX_ENCODED_PARALLELISM {
+        return None;
+    }
+    Some(params)
+}
+
+pub fn verify_encoded(encoded: &str, password: &[u8]) -> bool {
+    let phc = match PhcString::parse(encoded) {
+        Some(phc) if phc.algorithm == "scrypt" && !phc.hash.is_empty() => phc,
+        _ => return false,
+    };
+
+    match params_from_phc(&phc) {
+        Some(params) => match scrypt(password, &phc.salt, &params, phc.hash.len()) {
+            Some(hash) => constant_time_eq(&hash, &phc.hash),
+            None => false,
+        },
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_salsa20_8() {
+        // Test vector from RFC 7914 section 8.
+        let input: [u8; 64] = [
+            0x7E, 0x87, 0x9A, 0x21, 0x4F, 0x3E, 0xC9, 0x86, 0x7C, 0xA9, 0x40, 0xE6, 0x41, 0x71,
+            0x8F, 0x26, 0xBA, 0xEE, 0x55, 0x5B, 0x8C, 0x61, 0xC1, 0xB5, 0x0D, 0xF8, 0x46, 0x11,
+            0x6D, 0xCD, 0x3B, 0x1D, 0xEE, 0x24, 0xF3, 0x19, 0xDF, 0x9B, 0x3D, 0x85, 0x14, 0x12,
+            0x1E, 0x4B, 0x5A, 0xC5, 0xAA, 0x32, 0x76, 0x02, 0x1D, 0x29, 0x09, 0xC7, 0x48, 0x29,
+            0xED, 0xEB, 0xC6, 0x8D, 0xB8, 0xB8, 0xC2, 0x5E,
+        ];
+        let expected_output: [u8; 64] = [
+            0xA4, 0x1F, 0x85, 0x9C, 0x66, 0x08, 0xCC, 0x99, 0x3B, 0x81, 0xCA, 0xCB, 0x02, 0x0C,
+            0xEF, 0x05, 0x04, 0x4B, 0x21, 0x81, 0xA2, 0xFD, 0x33, 0x7D, 0xFD, 0x7B, 0x1C, 0x63,
+            0x96, 0x68, 0x2F, 0x29, 0xB4, 0x39, 0x31, 0x68, 0xE3, 0xC9, 0xE6, 0xBC, 0xFE, 0x6B,
+            0xC5, 0xB7, 0xA0, 0x6D, 0x96, 0xBA, 0xE4, 0x24, 0xCC, 0x10, 0x2C, 0x91, 0x74, 0x5C,
+            0x24, 0xAD, 0x67, 0x3D, 0xC7, 0x61, 0x8F, 0x81,
+        ];
+
+        let mut block = [0; 16];
+        for (word, bytes) in block.iter_mut().zip(input.chunks(4)) {
+            *word = u32::from_le_bytes(bytes.try_into().unwrap());
+        }
+        salsa20_8(&mut block);
+
+        let output: Vec<u8> = block.iter().flat_map(|word| word.to_le_bytes()).collect();
+        assert_eq!(output, expected_output);
+    }
+
+    #[test]
+    fn test_scrypt() {
+        // Test vectors from RFC 7914 section 12, minus the 1 GiB one.
+        let expected_empty: [u8; 64] = [
+            0x77, 0xD6, 0x57, 0x62, 0x38, 0x65, 0x7B, 0x20, 0x3B, 0x19, 0xCA, 0x42, 0xC1, 0x8A,
+            0x04, 0x97, 0xF1, 0x6B, 0x48, 0x44, 0xE3, 0x07, 0x4A, 0xE8, 0xDF, 0xDF, 0xFA, 0x3F,
+            0xED, 0xE2, 0x14, 0x42, 0xFC, 0xD0, 0x06, 0x9D, 0xED, 0x09, 0x48, 0xF8, 0x32, 0x6A,
+            0x75, 0x3A, 0x0F, 0xC8, 0x1F, 0x17, 0xE8, 0xD3, 0xE0, 0xFB, 0x2E, 0x0D, 0x36, 0x28,
+            0xCF, 0x35, 0xE2, 0x0C, 0x38, 0xD1, 0x89, 0x06,
+        ];
+        let expected_nacl: [u8; 64] = [
+            0xFD, 0xBA, 0xBE, 0x1C, 0x9D, 0x34, 0x72, 0x00, 0x78, 0x56, 0xE7, 0x19, 0x0D, 0x01,
+            0xE9, 0xFE, 0x7C, 0x6A, 0xD7, 0xCB, 0xC8, 0x23, 0x78, 0x30, 0xE7, 0x73, 0x76, 0x63,
+            0x4B, 0x37, 0x31, 0x62, 0x2E, 0xAF, 0x30, 0xD9, 0x2E, 0x22, 0xA3, 0x88, 0x6F, 0xF1,
+            0x09, 0x27, 0x9D, 0x98, 0x30, 0xDA, 0xC7, 0x27, 0xAF, 0xB9, 0x4A, 0x83, 0xEE, 0x6D,
+            0x83, 0x60, 0xCB, 0xDF, 0xA2, 0xCC, 0x06, 0x40,
+        ];
+        let expected_sodium_chloride: [u8; 64] = [
+            0x70, 0x23, 0xBD, 0xCB, 0x3A, 0xFD, 0x73, 0x48, 0x46, 0x1C, 0x06, 0xCD, 0x81, 0xFD,
+            0x38, 0xEB, 0xFD, 0xA8, 0xFB, 0xBA, 0x90, 0x4F, 0x8E, 0x3E, 0xA9, 0xB5, 0x43, 0xF6,
+            0x54, 0x5D, 0xA1, 0xF2, 0xD5, 0x43, 0x29, 0x55, 0x61, 0x3F, 0x0F, 0xCF, 0x62, 0xD4,
+            0x97, 0x05, 0x24, 0x2A, 0x9A, 0xF9, 0xE6, 0x1E, 0x85, 0xDC, 0x0D, 0x65, 0x1E, 0x40,
+            0xDF, 0xCF, 0x01, 0x7B, 0x45, 0x57, 0x58, 0x87,
+        ];
+
+        let params = Params::new(4, 1, 1).unwrap();
+        assert_eq!(scrypt(b"", b"", &params, 64).unwrap(), expected_empty);
+
+        let params = Params::new(10, 8, 16).unwrap();
+        assert_eq!(
+            scrypt(b"password", b"NaCl", &params, 64).unwrap(),
+            expected_nacl
+        );
+
+        let params = Params::new(14, 8, 1).unwrap();
+        assert_eq!(
+            scrypt(b"pleaseletmein", b"SodiumChloride", &params, 64).unwrap(),
+            expected_sodium_chloride
+        );
+    }
+
+    #[test]
+    fn test_params() {
+        assert!(Params::new(0, 8, 1).is_none());
+        assert!(Params::new(14, 0, 1).is_none());
+        assert!(Params::new(14, 8, 0).is_none());
+        assert!(Params::new(16, 1, 1).is_none());
+        assert!(Params::new(15, 1, 1).is_some());
+        // A table of 2^62 blocks can't be addressed.
+        assert!(Params::new(62, 8, 1).is_none());
+        assert_eq!(Params::new(14, 8, 1).unwrap().memory(), 16 << 20);
+    }
+
+    #[test]
+    fn test_encoded() {
+        let params = Params::new(4, 8, 2).unwrap();
+        let encoded = hash_encoded(b"hunter2", b"NaClNaClNaCl", &params);
+        assert!(encoded.starts_with("$scrypt$ln=4,r=8,p=2$TmFDbE5hQ2xOYUNs$"));
+
+        assert!(verify_encoded(&encoded, b"hunter2"));
+        assert!(!verify_encoded(&encoded, b"hunter3"));
+        assert!(!verify_encoded(
+            "$scrypt$ln=4,r=8$TmFDbE5hQ2xOYUNs$AAAA",
+            b"hunter2"
+        ));
+        assert!(!verify_encoded(
+            "$argon2id$ln=4,r=8,p=2$TmFDbE5hQ2xOYUNs$AAAA",
+            b"hunter2"
+        ));
+        // Costs no verifier should take on from an untrusted string.
+        assert!(!verify_encoded(
+            "$scrypt$ln=62,r=8,p=1$TmFDbA$AAAA",
+            b"hunter2"
+        ));
+        assert!(!verify_encoded(
+            "$scrypt$ln=21,r=8,p=1$TmFDbA$AAAA",
+            b"hunter2"
+        ));
+        assert!(!verify_encoded(
+            "$scrypt$ln=1,r=1,p=1000000000$TmFDbA$AAAA",
+            b"hunter2"
+        ));
+        assert!(!verify_encoded(
+            "$scrypt$ln=4,r=8,p=17$TmFDbA$AAAA",
+            b"hunter2"
+        ));
+        assert!(!verify_encoded(
+            "$scrypt$ln=20,r=1048576,p=1$TmFDbA$AAAA",
+            b"hunter2"
+        ));
+    }
+}
@@ -3,8 +3,11 @@
 #![allow(unused_variables)]
 #![allow(dead_code)]
 
+pub mod attacks;
 pub mod block;
 pub mod common;
 pub mod hasher;
-pub mod rand;
+pub mod kdf;
+pub mod mac;
 pub mod math;
+pub mod rand;
@@ -0,0 +1,368 @@
+use crate::common::AsBytes;
+use crate::hasher::Hasher;
+use crate::mac::constant_time_eq;
+
+// HMAC, H((K ^ opad) || H((K ^ ipad) || m)), for any of the hashers. Unlike
+// the secret-prefix MAC H(K || m) the tag is a digest of a digest, so the
+// chaining value after the message never leaks and length extension is off
+// the table.
+// https://www.rfc-editor.org/rfc/rfc2104
+
+const IPAD: u8 = 0x36;
+const OPAD: u8 = 0x5C;
+
+pub struct Hmac<H>
+where
+    H: Hasher,
+{
+    inner: H,
+    outer: H,
+}
+
+// Cloning a keyed HMAC skips re-absorbing the padded key, which is what
+// PBKDF2 relies on to stay at two compressions per iteration.
+impl<H> Clone for Hmac<H>
+where
+    H: Hasher + Clone,
+{
+    fn clone(&self) -> Self {
+        Hmac {
+            inner: self.inner.clone(),
+            outer: self.outer.clone(),
+        }
+    }
+}
+
+impl<H> Hmac<H>
+where
+    H: Hasher,
+    H::Digest: AsRef<[u8]>,
+{
+    pub fn new(key: &[u8]) -> Self {
+        // Keys longer than a block are hashed first, shorter ones are padded
+        // with zeros up to the block size.
+        let mut block_key = vec![0; H::BLOCK_SIZE];
+        if key.len() > H::BLOCK_SIZE {
+            let mut hasher = H::new();
+            hasher.update(key);
+            let digest = hasher.finalize();
+            block_key[..digest.as_ref().len()].copy_from_slice(digest.as_ref());
+        } else {
+            block_key[..key.len()].copy_from_slice(key);
+        }
+
+        let inner_key: Vec<u8> = block_key.iter().map(|byte| byte ^ IPAD).collect();
+        let outer_key: Vec<u8> = block_key.iter().map(|byte| byte ^ OPAD).collect();
+
+        let mut inner = H::new();
+        inner.update(inner_key.as_slice());
+        let mut outer = H::new();
+        outer.update(outer_key.as_slice());
+
+        Hmac { inner, outer }
+    }
+
+    pub fn update<T>(&mut self, message: T)
+    where
+        T: AsBytes,
+    {
+        self.inner.update(message);
+    }
+
+    pub fn finalize(self) -> H::Digest {
+        let mut outer = self.outer;
+        outer.update(self.inner.finalize().as_ref());
+        outer.finalize()
+    }
+
+    // Checks a received tag in constant time.
+    pub fn verify(self, mac: &[u8]) -> bool {
+        constant_time_eq(self.finalize().as_ref(), mac)
+    }
+}
+
+pub fn hmac<H>(key: &[u8], message: &[u8]) -> H::Digest
+where
+    H: Hasher,
+    H::Digest: AsRef<[u8]>,
+{
+    let mut mac = Hmac::<H>::new(key);
+    mac.update(message);
+    mac.finalize()
+}
+
+pub fn verify<H>(key: &[u8], message: &[u8], mac: &[u8]) -> bool
+where
+    H: Hasher,
+    H::Digest: AsRef<[u8]>,
+{
+    let mut expected = Hmac::<H>::new(key);
+    expected.update(message);
+    expected.verify(mac)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hasher::keccak::Sha3_256;
+    use crate::hasher::md5::Md5;
+    use crate::hasher::sha1::Sha1;
+    use crate::hasher::sha256::{Sha224, Sha256};
+    use crate::hasher::sha512::{Sha384, Sha512};
+
+    const LARGER_THAN_BLOCK_KEY: &[u8] = b"Test Using Larger Than Block-Size Key - Hash Key First";
+    const LARGER_THAN_BLOCK_KEY_AND_DATA: &[u8] = b"This is a test using a larger than \
+        block-size key and a larger than block-size data. The key needs to be hashed before \
+        being used by the HMAC algorithm.";
+
+    #[test]
+    fn test_hmac_md5() {
+        // Test cases 1, 2, 3 and 6 from RFC 2202.
+        let expected_1: [u8; 16] = [
+            0x92, 0x94, 0x72, 0x7A, 0x36, 0x38, 0xBB, 0x1C, 0x13, 0xF4, 0x8E, 0xF8, 0x15, 0x8B,
+            0xFC, 0x9D,
+        ];
+        let expected_2: [u8; 16] = [
+            0x75, 0x0C, 0x78, 0x3E, 0x6A, 0xB0, 0xB5, 0x03, 0xEA, 0xA8, 0x6E, 0x31, 0x0A, 0x5D,
+            0xB7, 0x38,
+        ];
+        let expected_3: [u8; 16] = [
+            0x56, 0xBE, 0x34, 0x52, 0x1D, 0x14, 0x4C, 0x88, 0xDB, 0xB8, 0xC7, 0x33, 0xF0, 0xE8,
+            0xB3, 0xF6,
+        ];
+        let expected_4: [u8; 16] = [
+            0x6B, 0x1A, 0xB7, 0xFE, 0x4B, 0xD7, 0xBF, 0x8F, 0x0B, 0x62, 0xE6, 0xCE, 0x61, 0xB9,
+            0xD0, 0xCD,
+        ];
+        assert_eq!(hmac::<Md5>(&[0x0B; 16], b"Hi There"), expected_1);
+        assert_eq!(
+            hmac::<Md5>(b"Jefe", b"what do ya want for nothing?"),
+            expected_2
+        );
+        assert_eq!(hmac::<Md5>(&[0xAA; 16], &[0xDD; 50]), expected_3);
+        assert_eq!(hmac::<Md5>(&[0xAA; 80], LARGER_THAN_BLOCK_KEY), expected_4);
+    }
+
+    #[test]
+    fn test_hmac_sha1() {
+        // Test cases 1, 2, 3 and 6 from RFC 2202.
+        let expected_1: [u8; 20] = [
+            0xB6, 0x17, 0x31, 0x86, 0x55, 0x05, 0x72, 0x64, 0xE2, 0x8B, 0xC0, 0xB6, 0xFB, 0x37,
+            0x8C, 0x8E, 0xF1, 0x46, 0xBE, 0x00,
+        ];
+        let expected_2: [u8; 20] = [
+            0xEF, 0xFC, 0xDF, 0x6A, 0xE5, 0xEB, 0x2F, 0xA2, 0xD2, 0x74, 0x16, 0xD5, 0xF1, 0x84,
+            0xDF, 0x9C, 0x25, 0x9A, 0x7C, 0x79,
+        ];
+        let expected_3: [u8; 20] = [
+            0x12, 0x5D, 0x73, 0x42, 0xB9, 0xAC, 0x11, 0xCD, 0x91, 0xA3, 0x9A, 0xF4, 0x8A, 0xA1,
+            0x7B, 0x4F, 0x63, 0xF1, 0x75, 0xD3,
+        ];
+        let expected_4: [u8; 20] = [
+            0xAA, 0x4A, 0xE5, 0xE1, 0x52, 0x72, 0xD0, 0x0E, 0x95, 0x70, 0x56, 0x37, 0xCE, 0x8A,
+            0x3B, 0x55, 0xED, 0x40, 0x21, 0x12,
+        ];
+        assert_eq!(hmac::<Sha1>(&[0x0B; 20], b"Hi There"), expected_1);
+        assert_eq!(
+            hmac::<Sha1>(b"Jefe", b"what do ya want for nothing?"),
+            expected_2
+        );
+        assert_eq!(hmac::<Sha1>(&[0xAA; 20], &[0xDD; 50]), expected_3);
+        assert_eq!(hmac::<Sha1>(&[0xAA; 80], LARGER_THAN_BLOCK_KEY), expected_4);
+    }
+
+    #[test]
+    fn test_hmac_sha224() {
+        // Test cases 1, 2, 6 and 7 from RFC 4231.
+        let expected_1: [u8; 28] = [
+            0x89, 0x6F, 0xB1, 0x12, 0x8A, 0xBB, 0xDF, 0x19, 0x68, 0x32, 0x10, 0x7C, 0xD4, 0x9D,
+            0xF3, 0x3F, 0x47, 0xB4, 0xB1, 0x16, 0x99, 0x12, 0xBA, 0x4F, 0x53, 0x68, 0x4B, 0x22,
+        ];
+        let expected_2: [u8; 28] = [
+            0xA3, 0x0E, 0x01, 0x09, 0x8B, 0xC6, 0xDB, 0xBF, 0x45, 0x69, 0x0F, 0x3A, 0x7E, 0x9E,
+            0x6D, 0x0F, 0x8B, 0xBE, 0xA2, 0xA3, 0x9E, 0x61, 0x48, 0x00, 0x8F, 0xD0, 0x5E, 0x44,
+        ];
+        let expected_3: [u8; 28] = [
+            0x95, 0xE9, 0xA0, 0xDB, 0x96, 0x20, 0x95, 0xAD, 0xAE, 0xBE, 0x9B, 0x2D, 0x6F, 0x0D,
+            0xBC, 0xE2, 0xD4, 0x99, 0xF1, 0x12, 0xF2, 0xD2, 0xB7, 0x27, 0x3F, 0xA6, 0x87, 0x0E,
+        ];
+        let expected_4: [u8; 28] = [
+            0x3A, 0x85, 0x41, 0x66, 0xAC, 0x5D, 0x9F, 0x02, 0x3F, 0x54, 0xD5, 0x17, 0xD0, 0xB3,
+            0x9D, 0xBD, 0x94, 0x67, 0x70, 0xDB, 0x9C, 0x2B, 0x95, 0xC9, 0xF6, 0xF5, 0x65, 0xD1,
+        ];
+        assert_eq!(hmac::<Sha224>(&[0x0B; 20], b"Hi There"), expected_1);
+        assert_eq!(
+            hmac::<Sha224>(b"Jefe", b"what do ya want for nothing?"),
+            expected_2
+        );
+        assert_eq!(
+            hmac::<Sha224>(&[0xAA; 131], LARGER_THAN_BLOCK_KEY),
+            expected_3
+        );
+        assert_eq!(
+            hmac::<Sha224>(&[0xAA; 131], LARGER_THAN_BLOCK_KEY_AND_DATA),
+            expected_4
+        );
+    }
+
+    #[test]
+    fn test_hmac_sha256() {
+        // Test cases 1, 2, 6 and 7 from RFC 4231.
+        let expected_1: [u8; 32] = [
+            0xB0, 0x34, 0x4C, 0x61, 0xD8, 0xDB, 0x38, 0x53, 0x5C, 0xA8, 0xAF, 0xCE, 0xAF, 0x0B,
+            0xF1, 0x2B, 0x88, 0x1D, 0xC2, 0x00, 0xC9, 0x83, 0x3D, 0xA7, 0x26, 0xE9, 0x37, 0x6C,
+            0x2E, 0x32, 0xCF, 0xF7,
+        ];
+        let expected_2: [u8; 32] = [
+            0x5B, 0xDC, 0xC1, 0x46, 0xBF, 0x60, 0x75, 0x4E, 0x6A, 0x04, 0x24, 0x26, 0x08, 0x95,
+            0x75, 0xC7, 0x5A, 0x00, 0x3F, 0x08, 0x9D, 0x27, 0x39, 0x83, 0x9D, 0xEC, 0x58, 0xB9,
+            0x64, 0xEC, 0x38, 0x43,
+        ];
+        let expected_3: [u8; 32] = [
+            0x60, 0xE4, 0x31, 0x59, 0x1E, 0xE0, 0xB6, 0x7F, 0x0D, 0x8A, 0x26, 0xAA, 0xCB, 0xF5,
+            0xB7, 0x7F, 0x8E, 0x0B, 0xC6, 0x21, 0x37, 0x28, 0xC5, 0x14, 0x05, 0x46, 0x04, 0x0F,
+            0x0E, 0xE3, 0x7F, 0x54,
+        ];
+        let expected_4: [u8; 32] = [
+            0x9B, 0x09, 0xFF, 0xA7, 0x1B, 0x94, 0x2F, 0xCB, 0x27, 0x63, 0x5F, 0xBC, 0xD5, 0xB0,
+            0xE9, 0x44, 0xBF, 0xDC, 0x63, 0x64, 0x4F, 0x07, 0x13, 0x93, 0x8A, 0x7F, 0x51, 0x53,
+            0x5C, 0x3A, 0x35, 0xE2,
+        ];
+        assert_eq!(hmac::<Sha256>(&[0x0B; 20], b"Hi There"), expected_1);
+        assert_eq!(
+            hmac::<Sha256>(b"Jefe", b"what do ya want for nothing?"),
+            expected_2
+        );
+        assert_eq!(
+            hmac::<Sha256>(&[0xAA; 131], LARGER_THAN_BLOCK_KEY),
+            expected_3
+        );
+        assert_eq!(
+            hmac::<Sha256>(&[0xAA; 131], LARGER_THAN_BLOCK_KEY_AND_DATA),
+            expected_4
+        );
+    }
+
+    #[test]
+    fn test_hmac_sha384() {
+        // Test cases 1, 2, 6 and 7 from RFC 4231.
+        let expected_1: [u8; 48] = [
+            0xAF, 0xD0, 0x39, 0x44, 0xD8, 0x48, 0x95, 0x62, 0x6B, 0x08, 0x25, 0xF4, 0xAB, 0x46,
+            0x90, 0x7F, 0x15, 0xF9, 0xDA, 0xDB, 0xE4, 0x10, 0x1E, 0xC6, 0x82, 0xAA, 0x03, 0x4C,
+            0x7C, 0xEB, 0xC5, 0x9C, 0xFA, 0xEA, 0x9E, 0xA9, 0x07, 0x6E, 0xDE, 0x7F, 0x4A, 0xF1,
+            0x52, 0xE8, 0xB2, 0xFA, 0x9C, 0xB6,
+        ];
+        let expected_2: [u8; 48] = [
+            0xAF, 0x45, 0xD2, 0xE3, 0x76, 0x48, 0x40, 0x31, 0x61, 0x7F, 0x78, 0xD2, 0xB5, 0x8A,
+            0x6B, 0x1B, 0x9C, 0x7E, 0xF4, 0x64, 0xF5, 0xA0, 0x1B, 0x47, 0xE4, 0x2E, 0xC3, 0x73,
+            0x63, 0x22, 0x44, 0x5E, 0x8E, 0x22, 0x40, 0xCA, 0x5E, 0x69, 0xE2, 0xC7, 0x8B, 0x32,
+            0x39, 0xEC, 0xFA, 0xB2, 0x16, 0x49,
+        ];
+        let expected_3: [u8; 48] = [
+            0x4E, 0xCE, 0x08, 0x44, 0x85, 0x81, 0x3E, 0x90, 0x88, 0xD2, 0xC6, 0x3A, 0x04, 0x1B,
+            0xC5, 0xB4, 0x4F, 0x9E, 0xF1, 0x01, 0x2A, 0x2B, 0x58, 0x8F, 0x3C, 0xD1, 0x1F, 0x05,
+            0x03, 0x3A, 0xC4, 0xC6, 0x0C, 0x2E, 0xF6, 0xAB, 0x40, 0x30, 0xFE, 0x82, 0x96, 0x24,
+            0x8D, 0xF1, 0x63, 0xF4, 0x49, 0x52,
+        ];
+        let expected_4: [u8; 48] = [
+            0x66, 0x17, 0x17, 0x8E, 0x94, 0x1F, 0x02, 0x0D, 0x35, 0x1E, 0x2F, 0x25, 0x4E, 0x8F,
+            0xD3, 0x2C, 0x60, 0x24, 0x20, 0xFE, 0xB0, 0xB8, 0xFB, 0x9A, 0xDC, 0xCE, 0xBB, 0x82,
+            0x46, 0x1E, 0x99, 0xC5, 0xA6, 0x78, 0xCC, 0x31, 0xE7, 0x99, 0x17, 0x6D, 0x38, 0x60,
+            0xE6, 0x11, 0x0C, 0x46, 0x52, 0x3E,
+        ];
+        assert_eq!(hmac::<Sha384>(&[0x0B; 20], b"Hi There"), expected_1);
+        assert_eq!(
+            hmac::<Sha384>(b"Jefe", b"what do ya want for nothing?"),
+            expected_2
+        );
+        assert_eq!(
+            hmac::<Sha384>(&[0xAA; 131], LARGER_THAN_BLOCK_KEY),
+            expected_3
+        );
+        assert_eq!(
+            hmac::<Sha384>(&[0xAA; 131], LARGER_THAN_BLOCK_KEY_AND_DATA),
+            expected_4
+        );
+    }
+
+    #[test]
+    fn test_hmac_sha512() {
+        // Test cases 1, 2, 6 and 7 from RFC 4231.
+        let expected_1: [u8; 64] = [
+            0x87, 0xAA, 0x7C, 0xDE, 0xA5, 0xEF, 0x61, 0x9D, 0x4F, 0xF0, 0xB4, 0x24, 0x1A, 0x1D,
+            0x6C, 0xB0, 0x23, 0x79, 0xF4, 0xE2, 0xCE, 0x4E, 0xC2, 0x78, 0x7A, 0xD0, 0xB3, 0x05,
+            0x45, 0xE1, 0x7C, 0xDE, 0xDA, 0xA8, 0x33, 0xB7, 0xD6, 0xB8, 0xA7, 0x02, 0x03, 0x8B,
+            0x27, 0x4E, 0xAE, 0xA3, 0xF4, 0xE4, 0xBE, 0x9D, 0x91, 0x4E, 0xEB, 0x61, 0xF1, 0x70,
+            0x2E, 0x69, 0x6C, 0x20, 0x3A, 0x12, 0x68, 0x54,
+        ];
+        let expected_2: [u8; 64] = [
+            0x16, 0x4B, 0x7A, 0x7B, 0xFC, 0xF8, 0x19, 0xE2, 0xE3, 0x95, 0xFB, 0xE7, 0x3B, 0x56,
+            0xE0, 0xA3, 0x87, 0xBD, 0x64, 0x22, 0x2E, 0x83, 0x1F, 0xD6, 0x10, 0x27, 0x0C, 0xD7,
+            0xEA, 0x25, 0x05, 0x54, 0x97, 0x58, 0xBF, 0x75, 0xC0, 0x5A, 0x99, 0x4A, 0x6D, 0x03,
+            0x4F, 0x65, 0xF8, 0xF0, 0xE6, 0xFD, 0xCA, 0xEA, 0xB1, 0xA3, 0x4D, 0x4A, 0x6B, 0x4B,
+            0x63, 0x6E, 0x07, 0x0A, 0x38, 0xBC, 0xE7, 0x37,
+        ];
+        let expected_3: [u8; 64] = [
+            0x80, 0xB2, 0x42, 0x63, 0xC7, 0xC1, 0xA3, 0xEB, 0xB7, 0x14, 0x93, 0xC1, 0xDD, 0x7B,
+            0xE8, 0xB4, 0x9B, 0x46, 0xD1, 0xF4, 0x1B, 0x4A, 0xEE, 0xC1, 0x12, 0x1B, 0x01, 0x37,
+            0x83, 0xF8, 0xF3, 0x52, 0x6B, 0x56, 0xD0, 0x37, 0xE0, 0x5F, 0x25, 0x98, 0xBD, 0x0F,
+            0xD2, 0x21, 0x5D, 0x6A, 0x1E, 0x52, 0x95, 0xE6, 0x4F, 0x73, 0xF6, 0x3F, 0x0A, 0xEC,
+            0x8B, 0x91, 0x5A, 0x98, 0x5D, 0x78, 0x65, 0x98,
+        ];
+        let expected_4: [u8; 64] = [
+            0xE3, 0x7B, 0x6A, 0x77, 0x5D, 0xC8, 0x7D, 0xBA, 0xA4, 0xDF, 0xA9, 0xF9, 0x6E, 0x5E,
+            0x3F, 0xFD, 0xDE, 0xBD, 0x71, 0xF8, 0x86, 0x72, 0x89, 0x86, 0x5D, 0xF5, 0xA3, 0x2D,
+            0x20, 0xCD, 0xC9, 0x44, 0xB6, 0x02, 0x2C, 0xAC, 0x3C, 0x49, 0x82, 0xB1, 0x0D, 0x5E,
+            0xEB, 0x55, 0xC3, 0xE4, 0xDE, 0x15, 0x13, 0x46, 0x76, 0xFB, 0x6D, 0xE0, 0x44, 0x60,
+            0x65, 0xC9, 0x74, 0x40, 0xFA, 0x8C, 0x6A, 0x58,
+        ];
+        assert_eq!(hmac::<Sha512>(&[0x0B; 20], b"Hi There"), expected_1);
+        assert_eq!(
+            hmac::<Sha512>(b"Jefe", b"what do ya want for nothing?"),
+            expected_2
+        );
+        assert_eq!(
+            hmac::<Sha512>(&[0xAA; 131], LARGER_THAN_BLOCK_KEY),
+            expected_3
+        );
+        assert_eq!(
+            hmac::<Sha512>(&[0xAA; 131], LARGER_THAN_BLOCK_KEY_AND_DATA),
+            expected_4
+        );
+    }
+
+    #[test]
+    fn test_hmac_sha3_256() {
+        // SHA3 uses its rate as the block size.
+        let expected: [u8; 32] = [
+            0xC7, 0xD4, 0x07, 0x2E, 0x78, 0x88, 0x77, 0xAE, 0x35, 0x96, 0xBB, 0xB0, 0xDA, 0x73,
+            0xB8, 0x87, 0xC9, 0x17, 0x1F, 0x93, 0x09, 0x5B, 0x29, 0x4A, 0xE8, 0x57, 0xFB, 0xE2,
+            0x64, 0x5E, 0x1B, 0xA5,
+        ];
+        assert_eq!(
+            hmac::<Sha3_256>(b"Jefe", b"what do ya want for nothing?"),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_hmac_streaming() {
+        let mut mac = Hmac::<Sha256>::new(b"key");
+        for piece in LARGER_THAN_BLOCK_KEY_AND_DATA.chunks(7) {
+            mac.update(piece);
+        }
+        assert_eq!(
+            mac.finalize(),
+            hmac::<Sha256>(b"key", LARGER_THAN_BLOCK_KEY_AND_DATA)
+        );
+    }
+
+    #[test]
+    fn test_verify() {
+        let tag = hmac::<Sha1>(b"key", b"message");
+        assert!(verify::<Sha1>(b"key", b"message", &tag));
+        assert!(!verify::<Sha1>(b"key", b"massage", &tag));
+        assert!(!verify::<Sha1>(b"yek", b"message", &tag));
+        assert!(!verify::<Sha1>(b"key", b"message", &tag[..19]));
+    }
+}
@@ -0,0 +1,283 @@
+use super::field::{Bn254FrParams, FieldParams, Fp};
+use super::uint::U256;
+use std::fmt;
+use std::ops::{Add, Neg, Sub};
+
+// Twisted Edwards curve a * x^2 + y^2 = 1 + d * x^2 * y^2 over the base
+// field, with a prime order subgroup of the given scalar field and cofactor.
+// With a square and d a non-square the addition law is complete: no special
+// cases for doubling or the identity.
+pub trait TwistedEdwards: 'static {
+    type Base: FieldParams;
+    type Scalar: FieldParams;
+
+    const A: U256;
+    const D: U256;
+    const COFACTOR: u64;
+    // Generator of the prime order subgroup, affine (x, y).
+    const BASE_POINT: (U256, U256);
+}
+
+// Point in extended coordinates (X : Y : Z : T) with x = X / Z, y = Y / Z
+// and x * y = T / Z, which keeps inversions out of addition.
+// https://eprint.iacr.org/2008/522
+pub struct Point<C>
+where
+    C: TwistedEdwards,
+{
+    x: Fp<C::Base>,
+    y: Fp<C::Base>,
+    z: Fp<C::Base>,
+    t: Fp<C::Base>,
+}
+
+impl<C> Point<C>
+where
+    C: TwistedEdwards,
+{
+    fn a() -> Fp<C::Base> {
+        Fp::reduce(C::A)
+    }
+
+    fn d() -> Fp<C::Base> {
+        Fp::reduce(C::D)
+    }
+
+    pub fn identity() -> Self {
+        Point {
+            x: Fp::zero(),
+            y: Fp::one(),
+            z: Fp::one(),
+            t: Fp::zero(),
+        }
+    }
+
+    pub fn base_point() -> Self {
+        let (x, y) = C::BASE_POINT;
+        Self::from_affine(Fp::reduce(x), Fp::reduce(y)).unwrap()
+    }
+
+    // None if (x, y) is not on the curve. The point may still lie outside
+    // the prime order subgroup, see is_in_prime_subgroup.
+    pub fn from_affine(x: Fp<C::Base>, y: Fp<C::Base>) -> Option<Self> {
+        let x2 = x.square();
+        let y2 = y.square();
+        if Self::a() * x2 + y2 != Fp::one() + Self::d() * x2 * y2 {
+            return None;
+        }
+        Some(Point {
+            x,
+            y,
+            z: Fp::one(),
+            t: x * y,
+        })
+    }
+
+    pub fn to_affine(&self) -> (Fp<C::Base>, Fp<C::Base>) {
+        // Z is never zero for points produced by the complete formulas.
+        let z_inverse = self.z.inverse().unwrap();
+        (self.x * z_inverse, self.y * z_inverse)
+    }
+
+    pub fn is_identity(&self) -> bool {
+        self.x.is_zero() && self.y == self.z
+    }
+
+    pub fn double(&self) -> Self {
+        *self + *self
+    }
+
+    // Double and add from the top bit down.
+    pub fn mul(&self, scalar: &U256) -> Self {
+        let mut result = Self::identity();
+        for i in (0..scalar.bits()).rev() {
+            result = result.double();
+            if scalar.bit(i) {
+                result = result + *self;
+            }
+        }
+        result
+    }
+
+    pub fn mul_scalar(&self, scalar: &Fp<C::Scalar>) -> Self {
+        self.mul(&scalar.to_uint())
+    }
+
+    // Maps any curve point into the prime order subgroup.
+    pub fn clear_cofactor(&self) -> Self {
+        self.mul(&U256::from_u64(C::COFACTOR))
+    }
+
+    pub fn is_in_prime_subgroup(&self) -> bool {
+        self.mul(&C::Scalar::MODULUS).is_identity()
+    }
+}
+
+impl<C> Clone for Point<C>
+where
+    C: TwistedEdwards,
+{
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<C> Copy for Point<C> where C: TwistedEdwards {}
+
+impl<C> PartialEq for Point<C>
+where
+    C: TwistedEdwards,
+{
+    // Projective equality: compare X1 / Z1 with X2 / Z2 without inverting.
+    fn eq(&self, other: &Self) -> bool {
+        self.x * other.z == other.x * self.z && self.y * other.z == other.y * self.z
+    }
+}
+
+impl<C> Eq for Point<C> where C: TwistedEdwards {}
+
+impl<C> fmt::Debug for Point<C>
+where
+    C: TwistedEdwards,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (x, y) = self.to_affine();
+        write!(f, "({:?}, {:?})", x, y)
+    }
+}
+
+// add-2008-hwcd, complete for any a and non-square d.
+impl<C> Add for Point<C>
+where
+    C: TwistedEdwards,
+{
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        let a = self.x * other.x;
+        let b = self.y * other.y;
+        let c = Self::d() * self.t * other.t;
+        let d = self.z * other.z;
+        let e = (self.x + self.y) * (other.x + other.y) - a - b;
+        let f = d - c;
+        let g = d + c;
+        let h = b - Self::a() * a;
+
+        Point {
+            x: e * f,
+            y: g * h,
+            z: f * g,
+            t: e * h,
+        }
+    }
+}
+
+impl<C> Neg for Point<C>
+where
+    C: TwistedEdwards,
+{
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Point {
+            x: -self.x,
+            y: self.y,
+            z: self.z,
+            t: -self.t,
+        }
+    }
+}
+
+impl<C> Sub for Point<C>
+where
+    C: TwistedEdwards,
+{
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        self + (-other)
+    }
+}
+
+// Baby Jubjub, the twisted Edwards curve defined over the BN254 scalar field
+// so it can be computed inside BN254 SNARK circuits.
+// https://eips.ethereum.org/EIPS/eip-2494
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BabyJubjub;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BabyJubjubScalarParams;
+
+impl FieldParams for BabyJubjubScalarParams {
+    const MODULUS: U256 =
+        U256::from_hex("060C89CE5C263405370A08B6D0302B0BAB3EEDB83920EE0A677297DC392126F1");
+}
+
+impl TwistedEdwards for BabyJubjub {
+    type Base = Bn254FrParams;
+    type Scalar = BabyJubjubScalarParams;
+
+    const A: U256 = U256::from_u64(168700);
+    const D: U256 = U256::from_u64(168696);
+    const COFACTOR: u64 = 8;
+    const BASE_POINT: (U256, U256) = (
+        U256::from_hex("0BB77A6AD63E739B4EACB2E09D6277C12AB8D8010534E0B62893F3F6BB957051"),
+        U256::from_hex("25797203F7A0B24925572E1CD16BF9EDFCE0051FB9E133774B3C257A872D7D8B"),
+    );
+}
+
+pub type BabyJubjubPoint = Point<BabyJubjub>;
+pub type BabyJubjubScalar = Fp<BabyJubjubScalarParams>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::field::Bn254Fr;
+
+    // Generator of the full group from EIP-2494, eight times it is the base point.
+    fn full_generator() -> BabyJubjubPoint {
+        BabyJubjubPoint::from_affine(
+            Bn254Fr::reduce(U256::from_hex(
+                "023343E3445B673D38BCBA38F25645ADB494B1255B1162BB40F41A59F4D4B45E",
+            )),
+            Bn254Fr::reduce(U256::from_hex(
+                "0C19139CB84C680A6E14116DA06056174A0CFA121E6E5C2450F87D64FC000001",
+            )),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_baby_jubjub_base_point() {
+        let base = BabyJubjubPoint::base_point();
+        let generator = full_generator();
+
+        assert_eq!(generator.clear_cofactor(), base);
+        assert!(base.is_in_prime_subgroup());
+        assert!(!generator.is_in_prime_subgroup());
+        assert!(!base.is_identity());
+    }
+
+    #[test]
+    fn test_baby_jubjub_group_law() {
+        let base = BabyJubjubPoint::base_point();
+        let identity = BabyJubjubPoint::identity();
+
+        assert_eq!(base + identity, base);
+        assert_eq!(base - base, identity);
+        assert_eq!(base.double(), base + base);
+        assert_eq!(base.mul(&U256::from_u64(5)), base.double().double() + base);
+        assert_eq!((base + base.double()) + base, base + (base.double() + base));
+
+        // Scalars wrap around the subgroup order.
+        let order_plus_one = BabyJubjubScalarParams::MODULUS
+            .overflowing_add(&U256::ONE)
+            .0;
+        assert_eq!(base.mul(&order_plus_one), base);
+        assert_eq!(base.mul_scalar(&-BabyJubjubScalar::one()), -base);
+
+        let (x, y) = base.double().to_affine();
+        assert_eq!(BabyJubjubPoint::from_affine(x, y), Some(base.double()));
+        assert_eq!(BabyJubjubPoint::from_affine(x, y + Bn254Fr::one()), None);
+    }
+}
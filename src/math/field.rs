@@ -0,0 +1,416 @@
+use super::uint::U256;
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::{Add, Mul, Neg, Sub};
+
+// A prime modulus. Everything else the field needs is derived from it at
+// compile time. The modulus must be an odd prime below 2^255.
+pub trait FieldParams: 'static {
+    const MODULUS: U256;
+}
+
+// Montgomery form constants: R = 2^256 mod p, R2 = 2^512 mod p and
+// INV = -p^-1 mod 2^64.
+const fn double_mod(value: U256, modulus: &U256) -> U256 {
+    let (doubled, carry) = value.overflowing_add(&value);
+    if carry || doubled.const_ge(modulus) {
+        doubled.overflowing_sub(modulus).0
+    } else {
+        doubled
+    }
+}
+
+const fn pow2_mod(exponent: usize, modulus: &U256) -> U256 {
+    let mut value = U256::ONE;
+    let mut i = 0;
+    while i < exponent {
+        value = double_mod(value, modulus);
+        i += 1;
+    }
+    value
+}
+
+const fn montgomery_inv(modulus: &U256) -> u64 {
+    // Newton iteration, each step doubles the number of correct low bits.
+    let mut inv = 1u64;
+    let mut i = 0;
+    while i < 6 {
+        inv = inv.wrapping_mul(2u64.wrapping_sub(modulus.0[0].wrapping_mul(inv)));
+        i += 1;
+    }
+    inv.wrapping_neg()
+}
+
+// a + b * c + carry, returned as (low, high).
+fn mac(a: u64, b: u64, c: u64, carry: u64) -> (u64, u64) {
+    let wide = a as u128 + (b as u128) * (c as u128) + carry as u128;
+    (wide as u64, (wide >> 64) as u64)
+}
+
+fn adc(a: u64, b: u64, carry: u64) -> (u64, u64) {
+    let wide = a as u128 + b as u128 + carry as u128;
+    (wide as u64, (wide >> 64) as u64)
+}
+
+// Element of the prime field given by P, kept in Montgomery form so
+// multiplication needs no division.
+pub struct Fp<P> {
+    montgomery: U256,
+    params: PhantomData<P>,
+}
+
+impl<P> Fp<P>
+where
+    P: FieldParams,
+{
+    const R: U256 = pow2_mod(256, &P::MODULUS);
+    const R2: U256 = pow2_mod(512, &P::MODULUS);
+    const INV: u64 = montgomery_inv(&P::MODULUS);
+
+    fn from_montgomery(montgomery: U256) -> Self {
+        Fp {
+            montgomery,
+            params: PhantomData,
+        }
+    }
+
+    // a * b / R mod p. Holds for any a < 2^256 as long as b < p.
+    fn montgomery_mul(a: &U256, b: &U256) -> U256 {
+        let modulus = P::MODULUS.0;
+        let mut t = [0u64; 6];
+
+        for i in 0..4 {
+            let mut carry = 0;
+            for (limb, a_limb) in t.iter_mut().zip(a.0) {
+                (*limb, carry) = mac(*limb, a_limb, b.0[i], carry);
+            }
+            (t[4], t[5]) = adc(t[4], carry, 0);
+
+            // Add a multiple of p that clears the lowest limb, then shift.
+            let m = t[0].wrapping_mul(Self::INV);
+            let (_, mut carry) = mac(t[0], m, modulus[0], 0);
+            for j in 1..4 {
+                (t[j - 1], carry) = mac(t[j], m, modulus[j], carry);
+            }
+            (t[3], carry) = adc(t[4], carry, 0);
+            t[4] = t[5] + carry;
+        }
+
+        let result = U256([t[0], t[1], t[2], t[3]]);
+        if t[4] != 0 || result >= P::MODULUS {
+            result.overflowing_sub(&P::MODULUS).0
+        } else {
+            result
+        }
+    }
+
+    pub fn modulus() -> U256 {
+        P::MODULUS
+    }
+
+    pub fn zero() -> Self {
+        Self::from_montgomery(U256::ZERO)
+    }
+
+    pub fn one() -> Self {
+        Self::from_montgomery(Self::R)
+    }
+
+    pub fn from_u64(value: u64) -> Self {
+        Self::reduce(U256::from_u64(value))
+    }
+
+    // Canonical values only, None for anything at or above the modulus.
+    pub fn new(value: U256) -> Option<Self> {
+        if value >= P::MODULUS {
+            return None;
+        }
+        Some(Self::reduce(value))
+    }
+
+    // Any 256-bit value, taken mod p.
+    pub fn reduce(value: U256) -> Self {
+        Self::from_montgomery(Self::montgomery_mul(&value, &Self::R2))
+    }
+
+    pub fn from_be_bytes_mod_order(bytes: [u8; 32]) -> Self {
+        Self::reduce(U256::from_be_bytes(bytes))
+    }
+
+    pub fn to_uint(&self) -> U256 {
+        Self::montgomery_mul(&self.montgomery, &U256::ONE)
+    }
+
+    pub fn to_be_bytes(&self) -> [u8; 32] {
+        self.to_uint().to_be_bytes()
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.montgomery.is_zero()
+    }
+
+    // Parity of the canonical representative, used to pick a square root.
+    pub fn is_odd(&self) -> bool {
+        self.to_uint().is_odd()
+    }
+
+    pub fn square(&self) -> Self {
+        *self * *self
+    }
+
+    pub fn double(&self) -> Self {
+        *self + *self
+    }
+
+    pub fn pow(&self, exponent: &U256) -> Self {
+        let mut result = Self::one();
+        for i in (0..exponent.bits()).rev() {
+            result = result.square();
+            if exponent.bit(i) {
+                result = result * *self;
+            }
+        }
+        result
+    }
+
+    // Fermat: a^(p - 2) = a^-1. None for zero.
+    pub fn inverse(&self) -> Option<Self> {
+        if self.is_zero() {
+            return None;
+        }
+        let exponent = P::MODULUS.overflowing_sub(&U256::from_u64(2)).0;
+        Some(self.pow(&exponent))
+    }
+
+    // Euler's criterion: a^((p - 1) / 2) is 1 for non-zero squares.
+    pub fn is_square(&self) -> bool {
+        let exponent = P::MODULUS.shr(1);
+        self.is_zero() || self.pow(&exponent) == Self::one()
+    }
+
+    // Tonelli-Shanks. Returns either root, None for non-squares.
+    pub fn sqrt(&self) -> Option<Self> {
+        if self.is_zero() {
+            return Some(*self);
+        }
+        if !self.is_square() {
+            return None;
+        }
+
+        // p - 1 = q * 2^s with q odd.
+        let p_minus_one = P::MODULUS.overflowing_sub(&U256::ONE).0;
+        let s = (0..256).find(|i| p_minus_one.bit(*i)).unwrap();
+        let q = p_minus_one.shr(s);
+
+        let mut non_residue = Self::from_u64(2);
+        while non_residue.is_square() {
+            non_residue = non_residue + Self::one();
+        }
+
+        let mut m = s;
+        let mut c = non_residue.pow(&q);
+        let mut t = self.pow(&q);
+        let mut root = self.pow(&q.overflowing_add(&U256::ONE).0.shr(1));
+
+        while t != Self::one() {
+            // Least i with t^(2^i) = 1.
+            let mut i = 0;
+            let mut t_power = t;
+            while t_power != Self::one() {
+                t_power = t_power.square();
+                i += 1;
+            }
+
+            let mut b = c;
+            for _ in 0..m - i - 1 {
+                b = b.square();
+            }
+            m = i;
+            c = b.square();
+            t = t * c;
+            root = root * b;
+        }
+        Some(root)
+    }
+}
+
+impl<P> Clone for Fp<P> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<P> Copy for Fp<P> {}
+
+impl<P> PartialEq for Fp<P> {
+    fn eq(&self, other: &Self) -> bool {
+        self.montgomery == other.montgomery
+    }
+}
+
+impl<P> Eq for Fp<P> {}
+
+impl<P> fmt::Debug for Fp<P>
+where
+    P: FieldParams,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self.to_uint())
+    }
+}
+
+impl<P> Add for Fp<P>
+where
+    P: FieldParams,
+{
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        let (sum, carry) = self.montgomery.overflowing_add(&other.montgomery);
+        if carry || sum >= P::MODULUS {
+            Self::from_montgomery(sum.overflowing_sub(&P::MODULUS).0)
+        } else {
+            Self::from_montgomery(sum)
+        }
+    }
+}
+
+impl<P> Sub for Fp<P>
+where
+    P: FieldParams,
+{
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        let (difference, borrow) = self.montgomery.overflowing_sub(&other.montgomery);
+        if borrow {
+            Self::from_montgomery(difference.overflowing_add(&P::MODULUS).0)
+        } else {
+            Self::from_montgomery(difference)
+        }
+    }
+}
+
+impl<P> Neg for Fp<P>
+where
+    P: FieldParams,
+{
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::zero() - self
+    }
+}
+
+impl<P> Mul for Fp<P>
+where
+    P: FieldParams,
+{
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        Self::from_montgomery(Self::montgomery_mul(&self.montgomery, &other.montgomery))
+    }
+}
+
+// Scalar field of the BN254 pairing curve. Also the base field of Baby
+// Jubjub, and the field most SNARK friendly hashes are defined over.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Bn254FrParams;
+
+impl FieldParams for Bn254FrParams {
+    const MODULUS: U256 =
+        U256::from_hex("30644E72E131A029B85045B68181585D2833E84879B9709143E1F593F0000001");
+}
+
+pub type Bn254Fr = Fp<Bn254FrParams>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    struct SmallParams;
+
+    impl FieldParams for SmallParams {
+        const MODULUS: U256 = U256::from_hex("65");
+    }
+
+    type Small = Fp<SmallParams>;
+
+    fn fr(hex: &str) -> Bn254Fr {
+        Bn254Fr::new(U256::from_hex(hex)).unwrap()
+    }
+
+    #[test]
+    fn test_small_field() {
+        // Exhaustive checks in F_101.
+        let mut squares = 0;
+        for value in 0..101 {
+            let a = Small::from_u64(value);
+            assert_eq!(a.to_uint(), U256::from_u64(value));
+            assert_eq!(a + (-a), Small::zero());
+
+            if value != 0 {
+                assert_eq!(a * a.inverse().unwrap(), Small::one());
+            }
+            if let Some(root) = a.sqrt() {
+                assert_eq!(root.square(), a);
+                squares += 1;
+            }
+        }
+        // Zero plus half the units.
+        assert_eq!(squares, 51);
+        assert_eq!(Small::zero().inverse(), None);
+        assert_eq!(Small::from_u64(150), Small::from_u64(49));
+        assert_eq!(Small::new(U256::from_u64(101)), None);
+    }
+
+    // Expected values from Python's built in integers.
+    #[test]
+    fn test_bn254_field() {
+        let a = fr("1234567890ABCDEF1234567890ABCDEF1234567890ABCDEF1234567890ABCDEF");
+        let b = fr("0FEDCBA9876543210FEDCBA9876543210FEDCBA9876543210FEDCBA987654321");
+
+        assert_eq!(
+            a * b,
+            fr("0DC5521A3D3804C5AFB37E5B1020FAFF6EA73A00C3DD1413401063EA0026434B")
+        );
+        assert_eq!(
+            a + b,
+            fr("2222222218111110222222221811111022222222181111102222222218111110")
+        );
+        assert_eq!(
+            b - a,
+            fr("2E1DC3A3D7EB155BB609BAE7783ACD8F25ED5D797072E5C3419B6AC4E6B97533")
+        );
+        assert_eq!(
+            a.inverse().unwrap(),
+            fr("2BB13C11312079EABCC30B313613A9E013DB48335CBDB86B9B83ED107FCAEA83")
+        );
+        assert_eq!(
+            Bn254Fr::reduce(U256::MAX),
+            fr("0E0A77C19A07DF2F666EA36F7879462E36FC76959F60CD29AC96341C4FFFFFFA")
+        );
+        assert_eq!(Bn254Fr::new(Bn254Fr::modulus()), None);
+        assert_eq!(-Bn254Fr::one() + Bn254Fr::one(), Bn254Fr::zero());
+    }
+
+    #[test]
+    fn test_bn254_sqrt() {
+        // p - 1 is divisible by 2^28, so this exercises the full Tonelli-Shanks loop.
+        for value in [4, 5, 7, 1 << 40] {
+            let a = Bn254Fr::from_u64(value);
+            match a.sqrt() {
+                Some(root) => assert_eq!(root.square(), a),
+                None => assert!(!a.is_square()),
+            }
+        }
+        assert_eq!(
+            Bn254Fr::from_u64(4).sqrt().unwrap().square(),
+            Bn254Fr::from_u64(4)
+        );
+        // 5 is a generator of the multiplicative group.
+        assert_eq!(Bn254Fr::from_u64(5).sqrt(), None);
+    }
+}
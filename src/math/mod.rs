@@ -1 +1,4 @@
+pub mod curve;
+pub mod field;
 pub mod number_theory;
+pub mod uint;
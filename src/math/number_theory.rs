@@ -59,14 +59,10 @@ pub fn extended_euclidean_algorithm(a: i32, b: i32) -> EuclideanOutput {
 // If a and n are not coprime, returns 0.
 pub fn mod_inverse(a: i32, n: i32) -> i32 {
     match extended_euclidean_algorithm(n, a) {
-        EuclideanOutput(1, u, _) => {
-            return u;
-        }
+        EuclideanOutput(1, u, _) => u,
 
         // Inverse doesn't exists with non-coprime numbers.
-        EuclideanOutput(_, _, _) => {
-            return 0;
-        }
+        EuclideanOutput(_, _, _) => 0,
     }
 }
 
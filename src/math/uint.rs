@@ -0,0 +1,239 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+// 256-bit unsigned integer as four 64-bit limbs, least significant first.
+// Only what modular arithmetic needs: carries, comparison and bit access.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct U256(pub [u64; 4]);
+
+impl U256 {
+    pub const ZERO: U256 = U256([0, 0, 0, 0]);
+    pub const ONE: U256 = U256([1, 0, 0, 0]);
+    pub const MAX: U256 = U256([u64::MAX; 4]);
+
+    pub const fn from_u64(value: u64) -> Self {
+        U256([value, 0, 0, 0])
+    }
+
+    // Parses big endian hex digits, without a 0x prefix. Usable in consts, so
+    // invalid input panics instead of returning None.
+    pub const fn from_hex(hex: &str) -> Self {
+        let digits = hex.as_bytes();
+        assert!(digits.len() <= 64, "hex literal longer than 256 bits");
+
+        let mut limbs = [0u64; 4];
+        let mut i = 0;
+        while i < digits.len() {
+            let nibble = match digits[digits.len() - 1 - i] {
+                c @ b'0'..=b'9' => c - b'0',
+                c @ b'a'..=b'f' => c - b'a' + 10,
+                c @ b'A'..=b'F' => c - b'A' + 10,
+                _ => panic!("invalid hex digit"),
+            };
+            limbs[i / 16] |= (nibble as u64) << (4 * (i % 16));
+            i += 1;
+        }
+        U256(limbs)
+    }
+
+    pub fn from_be_bytes(bytes: [u8; 32]) -> Self {
+        let mut limbs = [0u64; 4];
+        for (i, chunk) in bytes.chunks(8).rev().enumerate() {
+            limbs[i] = u64::from_be_bytes(chunk.try_into().unwrap());
+        }
+        U256(limbs)
+    }
+
+    pub fn to_be_bytes(&self) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        for (i, chunk) in bytes.chunks_mut(8).rev().enumerate() {
+            chunk.copy_from_slice(&self.0[i].to_be_bytes());
+        }
+        bytes
+    }
+
+    pub fn from_le_bytes(bytes: [u8; 32]) -> Self {
+        let mut limbs = [0u64; 4];
+        for (i, chunk) in bytes.chunks(8).enumerate() {
+            limbs[i] = u64::from_le_bytes(chunk.try_into().unwrap());
+        }
+        U256(limbs)
+    }
+
+    pub fn to_le_bytes(&self) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        for (i, chunk) in bytes.chunks_mut(8).enumerate() {
+            chunk.copy_from_slice(&self.0[i].to_le_bytes());
+        }
+        bytes
+    }
+
+    pub const fn overflowing_add(&self, other: &U256) -> (U256, bool) {
+        let mut limbs = [0u64; 4];
+        let mut carry = false;
+        let mut i = 0;
+        while i < 4 {
+            let (sum, carry_1) = self.0[i].overflowing_add(other.0[i]);
+            let (sum, carry_2) = sum.overflowing_add(carry as u64);
+            limbs[i] = sum;
+            carry = carry_1 | carry_2;
+            i += 1;
+        }
+        (U256(limbs), carry)
+    }
+
+    pub const fn overflowing_sub(&self, other: &U256) -> (U256, bool) {
+        let mut limbs = [0u64; 4];
+        let mut borrow = false;
+        let mut i = 0;
+        while i < 4 {
+            let (difference, borrow_1) = self.0[i].overflowing_sub(other.0[i]);
+            let (difference, borrow_2) = difference.overflowing_sub(borrow as u64);
+            limbs[i] = difference;
+            borrow = borrow_1 | borrow_2;
+            i += 1;
+        }
+        (U256(limbs), borrow)
+    }
+
+    // Const friendly `>=`, the Ord impl can't be called from const fns.
+    pub const fn const_ge(&self, other: &U256) -> bool {
+        let mut i = 4;
+        while i > 0 {
+            i -= 1;
+            if self.0[i] != other.0[i] {
+                return self.0[i] > other.0[i];
+            }
+        }
+        true
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0 == [0; 4]
+    }
+
+    pub fn is_odd(&self) -> bool {
+        self.0[0] & 1 == 1
+    }
+
+    pub fn bit(&self, index: usize) -> bool {
+        index < 256 && (self.0[index / 64] >> (index % 64)) & 1 == 1
+    }
+
+    // Position of the highest set bit plus one, 0 for zero.
+    pub fn bits(&self) -> usize {
+        for i in (0..4).rev() {
+            if self.0[i] != 0 {
+                return 64 * i + 64 - self.0[i].leading_zeros() as usize;
+            }
+        }
+        0
+    }
+
+    pub fn rem_u64(&self, modulus: u64) -> u64 {
+        self.0.iter().rev().fold(0u128, |remainder, limb| {
+            ((remainder << 64) | *limb as u128) % modulus as u128
+        }) as u64
+    }
+
+    pub fn shr(&self, shift: usize) -> U256 {
+        let mut limbs = [0u64; 4];
+        if shift >= 256 {
+            return U256(limbs);
+        }
+
+        let (words, bits) = (shift / 64, shift % 64);
+        for (i, limb) in limbs.iter_mut().enumerate().take(4 - words) {
+            *limb = self.0[i + words] >> bits;
+            if bits > 0 && i + words + 1 < 4 {
+                *limb |= self.0[i + words + 1] << (64 - bits);
+            }
+        }
+        U256(limbs)
+    }
+}
+
+impl Ord for U256 {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.iter().rev().cmp(other.0.iter().rev())
+    }
+}
+
+impl PartialOrd for U256 {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Debug for U256 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "0x")?;
+        for limb in self.0.iter().rev() {
+            write!(f, "{:016x}", limb)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for U256 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_u256_hex_and_bytes() {
+        let value =
+            U256::from_hex("30644E72E131A029B85045B68181585D2833E84879B9709143E1F593F0000001");
+        assert_eq!(
+            value.0,
+            [
+                0x43E1F593F0000001,
+                0x2833E84879B97091,
+                0xB85045B68181585D,
+                0x30644E72E131A029
+            ]
+        );
+        assert_eq!(value.bits(), 254);
+        assert_eq!(U256::from_be_bytes(value.to_be_bytes()), value);
+        assert_eq!(U256::from_le_bytes(value.to_le_bytes()), value);
+        assert_eq!(U256::from_hex("1F"), U256::from_u64(31));
+        assert_eq!(format!("{:?}", U256::from_u64(255))[62..], *"00ff");
+    }
+
+    #[test]
+    fn test_u256_arithmetic() {
+        let (sum, carry) = U256::MAX.overflowing_add(&U256::ONE);
+        assert_eq!((sum, carry), (U256::ZERO, true));
+
+        let (difference, borrow) = U256::ZERO.overflowing_sub(&U256::ONE);
+        assert_eq!((difference, borrow), (U256::MAX, true));
+
+        // Carries ripple across limbs.
+        let (sum, carry) = U256([u64::MAX, u64::MAX, 0, 0]).overflowing_add(&U256::ONE);
+        assert_eq!((sum, carry), (U256([0, 0, 1, 0]), false));
+
+        assert!(U256([0, 0, 0, 1]) > U256([u64::MAX, u64::MAX, u64::MAX, 0]));
+        assert!(U256([0, 0, 0, 1]).const_ge(&U256([0, 0, 0, 1])));
+        assert!(!U256::ONE.const_ge(&U256::from_u64(2)));
+
+        // (2^200 + 7) mod 1000003
+        assert_eq!(U256([7, 0, 0, 1 << 8]).rem_u64(1000003), 973699);
+    }
+
+    #[test]
+    fn test_u256_bits() {
+        let value = U256([0, 0b1011, 0, 0]);
+        assert_eq!(value.bits(), 68);
+        assert!(value.bit(64) && value.bit(65) && !value.bit(66) && value.bit(67));
+        assert!(!value.bit(300));
+        assert_eq!(value.shr(65), U256::from_u64(0b101));
+        assert_eq!(value.shr(1), U256([1 << 63, 0b101, 0, 0]));
+        assert_eq!(U256::MAX.shr(255), U256::ONE);
+        assert_eq!(U256::ZERO.bits(), 0);
+    }
+}
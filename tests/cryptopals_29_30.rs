@@ -1,5 +1,9 @@
+use brykto::attacks::length_extension::brute_force_key_length;
 use brykto::common::*;
+use brykto::hasher::md4::Md4Compression;
+use brykto::hasher::sha1::Sha1Compression;
 use brykto::hasher::*;
+use brykto::mac::hmac::{hmac, verify};
 
 #[test]
 fn challenge_29() {
@@ -9,22 +13,6 @@ fn challenge_29() {
     // MAC using a key prefix: H(K, m).
     let mac = sha1([secret_key, message].concat().as_str());
 
-    // Output of the MAC will be used as the IV to implement length
-    // extension.
-    let mut iv: (u32, u32, u32, u32, u32) = (0, 0, 0, 0, 0);
-    let to_32bit: Vec<_> = mac
-        .chunks(4)
-        .map(|chunk| {
-            let to_32_bit: [u8; 4] = [chunk[0], chunk[1], chunk[2], chunk[3]];
-            u32::from_be_bytes(to_32_bit)
-        })
-        .collect();
-    iv.0 = to_32bit[0];
-    iv.1 = to_32bit[1];
-    iv.2 = to_32bit[2];
-    iv.3 = to_32bit[3];
-    iv.4 = to_32bit[4];
-
     let attacker_message = ";admin=true";
     let mut attacker_mac: [u8; 20];
     let mut expected_keyed_mac: [u8; 20];
@@ -32,8 +20,14 @@ fn challenge_29() {
     for i in 1..40 {
         // I know the message but I don't know the secret key.
         let glue_padding = md_padding_64(i + message.len(), Endian::Big);
-        let total_length = i + message.len() + glue_padding.len() + attacker_message.len();
-        attacker_mac = sha1::core(attacker_message, total_length, iv);
+
+        // The MAC is the state after the glue padding, which lets the
+        // session resume from there.
+        let processed_length = i + message.len() + glue_padding.len();
+        let state = sha1::State::from_digest(&mac, processed_length).unwrap();
+        let mut hasher = sha1::Sha1::from_state(state);
+        hasher.update(attacker_message);
+        attacker_mac = hasher.finalize();
 
         expected_keyed_mac = sha1(
             [
@@ -66,21 +60,6 @@ fn challenge_30() {
     // MAC using a key prefix: H(K, m).
     let mac = md4([secret_key, message].concat().as_str());
 
-    // Output of the MAC will be used as the IV to implement length
-    // extension.
-    let mut iv: (u32, u32, u32, u32) = (0, 0, 0, 0);
-    let to_32bit: Vec<_> = mac
-        .chunks(4)
-        .map(|chunk| {
-            let to_32_bit: [u8; 4] = [chunk[0], chunk[1], chunk[2], chunk[3]];
-            u32::from_le_bytes(to_32_bit)
-        })
-        .collect();
-    iv.0 = to_32bit[0];
-    iv.1 = to_32bit[1];
-    iv.2 = to_32bit[2];
-    iv.3 = to_32bit[3];
-
     let attacker_message = ";admin=true";
     let mut attacker_mac: [u8; 16];
     let mut expected_keyed_mac: [u8; 16];
@@ -88,8 +67,14 @@ fn challenge_30() {
     for i in 1..40 {
         // I know the message but I don't know the secret key.
         let glue_padding = md_padding_64(i + message.len(), Endian::Little);
-        let total_length = i + message.len() + glue_padding.len() + attacker_message.len();
-        attacker_mac = md4::core(attacker_message, total_length, iv);
+
+        // The MAC is the state after the glue padding, which lets the
+        // session resume from there.
+        let processed_length = i + message.len() + glue_padding.len();
+        let state = md4::State::from_digest(&mac, processed_length).unwrap();
+        let mut hasher = md4::Md4::from_state(state);
+        hasher.update(attacker_message);
+        attacker_mac = hasher.finalize();
 
         expected_keyed_mac = md4([
             secret_key.as_bytes(),
@@ -111,3 +96,99 @@ fn challenge_30() {
     }
     assert!(found_match)
 }
+
+#[test]
+fn md5_length_extension() {
+    let secret_key = "spookiest";
+    let message = "comment1=cooking%20MCs;userdata=foo;comment2=%20like%20a%20pound%20of%20bacon";
+
+    // MAC using a key prefix: H(K, m).
+    let mac = md5([secret_key, message].concat().as_str());
+
+    let attacker_message = ";admin=true";
+    let mut found_match = false;
+    for i in 1..40 {
+        let glue_padding = md_padding_64(i + message.len(), Endian::Little);
+
+        // Seed the session with the leaked MAC.
+        let processed_length = i + message.len() + glue_padding.len();
+        let state = md5::State::from_digest(&mac, processed_length).unwrap();
+        let mut hasher = md5::Md5::from_state(state);
+        hasher.update(attacker_message);
+        let attacker_mac = hasher.finalize();
+
+        let expected_keyed_mac = md5([
+            secret_key.as_bytes(),
+            message.as_bytes(),
+            glue_padding.as_slice(),
+            attacker_message.as_bytes(),
+        ]
+        .concat()
+        .as_slice());
+
+        found_match = attacker_mac == expected_keyed_mac;
+        if found_match {
+            break;
+        }
+    }
+    assert!(found_match)
+}
+
+#[test]
+fn length_extension_library() {
+    let secret_key = "veryspooky";
+    let message = "comment1=cooking%20MCs;userdata=foo;comment2=%20like%20a%20pound%20of%20bacon";
+    let attacker_message = ";admin=true";
+
+    // The same attacks as above, with the glue handled by the library.
+    let mac = sha1([secret_key, message].concat().as_str());
+    let sha1_oracle = |forged_message: &[u8], forged_mac: &[u8; 20]| {
+        sha1([secret_key.as_bytes(), forged_message].concat().as_slice()) == *forged_mac
+    };
+    let (key_length, forgery) = brute_force_key_length::<Sha1Compression, _>(
+        &mac,
+        message.as_bytes(),
+        attacker_message.as_bytes(),
+        40,
+        sha1_oracle,
+    )
+    .unwrap();
+    assert_eq!(key_length, secret_key.len());
+    assert!(forgery.message.ends_with(attacker_message.as_bytes()));
+
+    let mac = md4([secret_key, message].concat().as_str());
+    let md4_oracle = |forged_message: &[u8], forged_mac: &[u8; 16]| {
+        md4([secret_key.as_bytes(), forged_message].concat().as_slice()) == *forged_mac
+    };
+    let (key_length, _) = brute_force_key_length::<Md4Compression, _>(
+        &mac,
+        message.as_bytes(),
+        attacker_message.as_bytes(),
+        40,
+        md4_oracle,
+    )
+    .unwrap();
+    assert_eq!(key_length, secret_key.len());
+}
+
+#[test]
+fn hmac_resists_length_extension() {
+    let secret_key = "veryspooky";
+    let message = "comment1=cooking%20MCs;userdata=foo;comment2=%20like%20a%20pound%20of%20bacon";
+    let attacker_message = ";admin=true";
+
+    // Same attack as `length_extension_library`, but the server checks HMAC
+    // tags instead of H(K || m). No key length yields a forgery.
+    let mac = hmac::<sha1::Sha1>(secret_key.as_bytes(), message.as_bytes());
+    let hmac_oracle = |forged_message: &[u8], forged_mac: &[u8; 20]| {
+        verify::<sha1::Sha1>(secret_key.as_bytes(), forged_message, forged_mac)
+    };
+    let forgery = brute_force_key_length::<Sha1Compression, _>(
+        &mac,
+        message.as_bytes(),
+        attacker_message.as_bytes(),
+        200,
+        hmac_oracle,
+    );
+    assert!(forgery.is_none());
+}
@@ -0,0 +1,39 @@
+use brykto::attacks::multicollision::{cascade_collision, multicollision, WeakHash};
+use brykto::hasher::md4::Md4Compression;
+use brykto::hasher::sha1::Sha1Compression;
+use std::collections::HashSet;
+
+#[test]
+fn challenge_52_multicollision() {
+    // A 16-bit MD4 falls in about 2^8 compressions, so 2^k collisions cost
+    // k * 2^8 rather than 2^(8k).
+    let f = WeakHash::<Md4Compression>::new(16).unwrap();
+    let multicollision = multicollision(&f, f.iv(), 8, 1 << 16).unwrap();
+    assert_eq!(multicollision.count(), Some(256));
+    assert!(multicollision.compressions() < 256 * 64);
+
+    let messages: HashSet<_> = (0..256)
+        .map(|index| multicollision.message(index).unwrap())
+        .collect();
+    assert_eq!(messages.len(), 256);
+
+    let digest = f.hash(&multicollision.message(0).unwrap());
+    assert!(messages.iter().all(|message| f.hash(message) == digest));
+}
+
+#[test]
+fn challenge_52_cascade() {
+    // h(x) = f(x) || g(x) with a cheap 16-bit f and a "stronger" 32-bit g,
+    // nominally 48 bits, collided with about 2^16 calls to g.
+    let f = WeakHash::<Md4Compression>::new(16).unwrap();
+    let g = WeakHash::<Sha1Compression>::new(32).unwrap();
+    let collision = cascade_collision(&f, &g, 24).unwrap();
+
+    println!(
+        "Cascade collision with {} calls to f and {} calls to g",
+        collision.cheap_compressions, collision.expensive_compressions
+    );
+    assert_ne!(collision.first, collision.second);
+    assert_eq!(f.hash(&collision.first), f.hash(&collision.second));
+    assert_eq!(g.hash(&collision.first), g.hash(&collision.second));
+}
@@ -0,0 +1,34 @@
+use brykto::attacks::expandable_message::second_preimage;
+use brykto::attacks::herding::{diamond, herd, predict};
+use brykto::attacks::multicollision::WeakHash;
+use brykto::hasher::md4::Md4Compression;
+use brykto::hasher::sha1::Sha1Compression;
+
+#[test]
+fn challenge_53() {
+    // A 2^10 block message under a 24-bit MD4: the bridge lands on one of
+    // about a thousand chaining values, 2^14 work instead of 2^24.
+    let hash = WeakHash::<Md4Compression>::new(24).unwrap();
+    let message: Vec<u8> = (0..64 * 1024).map(|i| (i * 7 % 256) as u8).collect();
+
+    let forgery = second_preimage(&hash, &message, 10, 1 << 24).unwrap();
+    println!("Second preimage in {} compressions", forgery.compressions);
+    assert_ne!(forgery.message, message);
+    assert_eq!(forgery.message.len(), message.len());
+    assert_eq!(hash.hash(&forgery.message), hash.hash(&message));
+}
+
+#[test]
+fn challenge_54() {
+    // Commit to the season's results before it starts.
+    let hash = WeakHash::<Sha1Compression>::new(20).unwrap();
+    let diamond = diamond(&hash, 6, 1 << 20).unwrap();
+    let prediction = predict(&hash, &diamond, 1);
+    println!("Prediction: {:05X}", prediction.digest);
+
+    for result in ["Reds 2, Blues 0", "Reds 1, Blues 1", "Reds 0, Blues 4"] {
+        let message = herd(&hash, &diamond, &prediction, result.as_bytes(), 1 << 24).unwrap();
+        assert!(message.starts_with(result.as_bytes()));
+        assert_eq!(hash.hash(&message), prediction.digest);
+    }
+}
@@ -0,0 +1,25 @@
+use brykto::attacks::md4_collision::{bytes_to_words, differential, find_collision};
+use brykto::hasher::md4;
+
+#[test]
+fn challenge_55() {
+    // Every first round condition is met by construction, so the trial count
+    // depends on the later rounds only: thousands to hundreds of thousands.
+    for seed in [0, 4] {
+        let collision = find_collision(seed, 1 << 24).unwrap();
+        println!(
+            "Collision after {} trials:\nM  = {:02X?}\nM' = {:02X?}",
+            collision.trials, collision.first, collision.second
+        );
+
+        assert_ne!(collision.first, collision.second);
+        assert_eq!(
+            differential(&bytes_to_words(&collision.first)),
+            bytes_to_words(&collision.second)
+        );
+        assert_eq!(
+            md4::default(collision.first.as_slice()),
+            md4::default(collision.second.as_slice())
+        );
+    }
+}
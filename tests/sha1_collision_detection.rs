@@ -0,0 +1,60 @@
+use brykto::hasher::sha1::{CheckedSha1, DisturbanceKind, DisturbanceVector};
+use brykto::hasher::*;
+
+// The first 320 bytes of shattered-1.pdf and shattered-2.pdf: a common PDF
+// header followed by the two near-collision blocks.
+// https://shattered.io
+const SHATTERED_1: &[u8] = include_bytes!("fixtures/shattered-1-prefix.bin");
+const SHATTERED_2: &[u8] = include_bytes!("fixtures/shattered-2-prefix.bin");
+
+#[test]
+fn shattered_prefixes_collide() {
+    assert_ne!(SHATTERED_1, SHATTERED_2);
+    assert_eq!(sha1(SHATTERED_1), sha1(SHATTERED_2));
+
+    // Any common suffix keeps the collision.
+    let suffix = b"%%EOF\n".as_slice();
+    assert_eq!(
+        sha1([SHATTERED_1, suffix].concat().as_slice()),
+        sha1([SHATTERED_2, suffix].concat().as_slice())
+    );
+}
+
+#[test]
+fn shattered_prefixes_detected() {
+    assert_eq!(sha1_checked(SHATTERED_1), None);
+    assert_eq!(sha1_checked(SHATTERED_2), None);
+
+    // The second near-collision block gives it away, with the vector the
+    // attack was built on.
+    for prefix in [SHATTERED_1, SHATTERED_2] {
+        let mut hasher = CheckedSha1::new();
+        hasher.update(&prefix[..256]);
+        assert!(hasher.detected().is_none());
+        hasher.update(&prefix[256..]);
+        assert_eq!(
+            hasher.detected(),
+            Some(DisturbanceVector {
+                kind: DisturbanceKind::II,
+                k: 52,
+                b: 0,
+            })
+        );
+    }
+}
+
+#[test]
+fn unrelated_inputs_accepted() {
+    // The header alone, and the prefix with its collision block altered.
+    assert_eq!(
+        sha1_checked(&SHATTERED_1[..192]),
+        Some(sha1(&SHATTERED_1[..192]))
+    );
+
+    let mut tampered = SHATTERED_1.to_vec();
+    tampered[300] ^= 1;
+    assert_eq!(
+        sha1_checked(tampered.as_slice()),
+        Some(sha1(tampered.as_slice()))
+    );
+}
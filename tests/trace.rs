@@ -0,0 +1,173 @@
+use brykto::attacks::md4_collision::{bytes_to_words, find_collision};
+use brykto::hasher::blake2::{blake2b, blake2b_traced};
+use brykto::hasher::blake3::{self, Blake3};
+use brykto::hasher::keccak::{sha3_256, Sha3_256};
+use brykto::hasher::siphash::{siphash24, SipHasher24};
+use brykto::hasher::trace::{Row, Table};
+use brykto::hasher::{md4, md5, ripemd160, sha1, sha256, sha512, Hasher};
+
+#[test]
+fn sha1_abc() {
+    // The intermediate values printed in the FIPS 180 example.
+    let mut table = Table::new();
+    let digest = sha1::traced("abc", &mut table);
+    println!("{}", table);
+    assert_eq!(digest, sha1::default("abc"));
+
+    let steps: Vec<&[u32]> = table
+        .rows
+        .iter()
+        .filter_map(|row| match row {
+            Row::Registers { values, .. } => Some(values.as_slice()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(steps.len(), 80);
+    assert_eq!(
+        steps[0],
+        [0x0116FC33, 0x67452301, 0x7BF36AE2, 0x98BADCFE, 0x10325476]
+    );
+    assert_eq!(
+        steps[79],
+        [0x42541B35, 0x5738D5E1, 0x21834873, 0x681E6DF6, 0xD8FDF6AD]
+    );
+    assert_eq!(
+        table.rows.last(),
+        Some(&Row::ChainingValue {
+            block: 1,
+            value: vec![0xA9993E36, 0x4706816A, 0xBA3E2571, 0x7850C26C, 0x9CD0D89D],
+        })
+    );
+}
+
+#[test]
+fn every_primitive() {
+    let message = "The quick brown fox jumps over the lazy dog".repeat(3);
+    let mut table = Table::new();
+    assert_eq!(
+        md4::traced(message.as_str(), &mut table),
+        md4::default(message.as_str())
+    );
+    let mut table = Table::new();
+    assert_eq!(
+        md5::traced(message.as_str(), &mut table),
+        md5::default(message.as_str())
+    );
+    let mut table = Table::new();
+    assert_eq!(
+        ripemd160::traced(message.as_str(), &mut table),
+        ripemd160::default(message.as_str())
+    );
+    let mut table = Table::new();
+    assert_eq!(
+        sha256::traced(message.as_str(), &mut table),
+        sha256::default(message.as_str())
+    );
+    let mut table = Table::new();
+    assert_eq!(
+        sha512::traced(message.as_str(), &mut table),
+        sha512::default(message.as_str())
+    );
+    assert!(!table.to_string().is_empty());
+
+    let mut table = Table::new();
+    assert_eq!(
+        Sha3_256::traced(message.as_str(), &mut table),
+        sha3_256(message.as_str())
+    );
+    assert!(!table.to_string().is_empty());
+    let mut table = Table::new();
+    assert_eq!(
+        blake2b_traced(message.as_str(), 64, &mut table),
+        blake2b(message.as_str(), 64)
+    );
+    assert!(!table.to_string().is_empty());
+    let mut table = Table::new();
+    assert_eq!(
+        Blake3::new().traced(message.as_str(), &mut table),
+        blake3::hash(message.as_str())
+    );
+    assert!(!table.to_string().is_empty());
+    let mut table = Table::new();
+    let key = [0x42; 16];
+    assert_eq!(
+        SipHasher24::new(&key).traced(message.as_bytes(), &mut table),
+        siphash24(&key, message.as_bytes())
+    );
+    assert!(!table.to_string().is_empty());
+}
+
+#[test]
+fn md4_differential() {
+    // The message differences of a Wang collision are all cancelled out by
+    // the last step, so the chaining values agree again.
+    let collision = find_collision(0, 1 << 24).unwrap();
+    let mut first = Table::new();
+    md4::traced(collision.first.as_slice(), &mut first);
+    let mut second = Table::new();
+    md4::traced(collision.second.as_slice(), &mut second);
+    let diff = first.diff(&second).unwrap();
+    println!("{}", diff);
+
+    let differences: Vec<u32> = bytes_to_words(&collision.first)
+        .iter()
+        .zip(bytes_to_words(&collision.second))
+        .map(|(x, y)| x ^ y)
+        .collect();
+    assert_eq!(diff.rows[1], Row::ExtendedWords(differences));
+
+    let registers: Vec<&[u32]> = diff
+        .rows
+        .iter()
+        .filter_map(|row| match row {
+            Row::Registers { values, .. } => Some(values.as_slice()),
+            _ => None,
+        })
+        .collect();
+    // M and M' first differ in m1, used by the second step.
+    assert_eq!(registers[0], [0; 4]);
+    assert_ne!(registers[1], [0; 4]);
+    assert_eq!(registers[47], [0; 4]);
+    assert!(diff
+        .to_string()
+        .contains(&format!("block   1 {}", "        .".repeat(4))));
+}
+
+#[test]
+fn core_length_extension() {
+    // Resuming from a digest traces the forged blocks only, starting from the
+    // registers the digest leaves behind.
+    let digest = sha1::default("abc");
+    let iv = (0xA9993E36, 0x4706816A, 0xBA3E2571, 0x7850C26C, 0x9CD0D89D);
+    let mut table = Table::new();
+    assert_eq!(
+        sha1::core_traced("def", 67, iv, &mut table),
+        sha1::core("def", 67, iv)
+    );
+    assert_eq!(
+        table.rows[0],
+        Row::ChainingValue {
+            block: 0,
+            value: sha1::State::from_digest(&digest, 64)
+                .unwrap()
+                .chaining_value
+                .to_vec(),
+        }
+    );
+
+    let digest = md4::default("abc");
+    let words = md4::State::from_digest(&digest, 64).unwrap().chaining_value;
+    let iv = (words[0], words[1], words[2], words[3]);
+    let mut table = Table::new();
+    assert_eq!(
+        md4::core_traced("def", 67, iv, &mut table),
+        md4::core("def", 67, iv)
+    );
+    assert_eq!(
+        table.rows[0],
+        Row::ChainingValue {
+            block: 0,
+            value: words.to_vec(),
+        }
+    );
+}